@@ -0,0 +1,256 @@
+//! Webhook notifications and log entries when sections enter or leave warning or critical state
+
+use std::{collections::HashMap, io, sync::Mutex};
+
+use crate::{banner, config, curl, fmt, journal, module::ModuleData, style::Severity};
+
+/// Section state change
+#[derive(serde::Serialize)]
+struct Notification<'a> {
+    /// Host name
+    hostname: &'a str,
+    /// Section key
+    section: &'a str,
+    /// Section title
+    #[serde(skip)]
+    title: &'a str,
+    /// Current severity
+    severity: Severity,
+    /// Severity at previous check
+    previous: Severity,
+    /// Single line summary of the section
+    summary: String,
+}
+
+impl Notification<'_> {
+    /// Notification title
+    fn title(&self) -> String {
+        format!("motd on {}", self.hostname)
+    }
+
+    /// Notification text
+    fn message(&self) -> String {
+        let state = match self.severity {
+            Severity::Ok => "recovered",
+            Severity::Warning => "is in warning state",
+            Severity::Critical => "is in critical state",
+        };
+        if self.summary.is_empty() {
+            format!("{} {state}", self.title)
+        } else {
+            format!("{} {state}: {}", self.title, self.summary)
+        }
+    }
+}
+
+/// Sends notifications on section severity changes, each change being notified once
+pub(crate) struct Alerter {
+    /// Webhooks to notify
+    webhooks: Vec<config::WebhookConfig>,
+    /// Log destination
+    log: Option<config::LogTarget>,
+    /// Host name
+    hostname: String,
+    /// Severity of each section at previous check
+    states: Mutex<HashMap<&'static str, Severity>>,
+}
+
+impl Alerter {
+    /// Create alerter, all sections being initially considered OK
+    pub(crate) fn new(cfg: &config::AlertConfig) -> io::Result<Self> {
+        Ok(Self {
+            webhooks: cfg.webhooks.clone(),
+            log: cfg.log,
+            hostname: banner::hostname()?,
+            states: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Only log state changes, without notifying webhooks
+    pub(crate) fn without_webhooks(self) -> Self {
+        Self {
+            webhooks: Vec::new(),
+            ..self
+        }
+    }
+
+    /// Replace webhooks and log destination with the ones of a reloaded config, keeping section
+    /// states so that unchanged ones are not notified again
+    pub(crate) fn reconfigure(&mut self, cfg: &config::AlertConfig) {
+        self.webhooks.clone_from(&cfg.webhooks);
+        self.log = cfg.log;
+    }
+
+    /// Notify webhooks and log if section severity changed since previous check
+    pub(crate) fn check(&self, section: &'static str, title: &str, data: &ModuleData) {
+        let severity = data.severity();
+        let previous = self
+            .states
+            .lock()
+            .unwrap()
+            .insert(section, severity)
+            .unwrap_or(Severity::Ok);
+        if severity == previous {
+            return;
+        }
+        let notification = Notification {
+            hostname: &self.hostname,
+            section,
+            title,
+            severity,
+            previous,
+            summary: fmt::strip_ansi(&data.compact()),
+        };
+        if let Some(target) = self.log {
+            if let Err(err) = log(target, &notification) {
+                eprintln!("Failed to log {section} state change: {err}");
+            }
+        }
+        for webhook in &self.webhooks {
+            if let Err(err) = notify(webhook, &notification) {
+                eprintln!("Failed to notify {}: {err:#}", webhook.url);
+            }
+        }
+    }
+}
+
+/// Send notification as a log entry
+fn log(target: config::LogTarget, notification: &Notification) -> io::Result<()> {
+    let severity = serde_json::to_value(notification.severity)?;
+    let previous = serde_json::to_value(notification.previous)?;
+    journal::send(
+        target,
+        notification.severity,
+        &notification.message(),
+        &[
+            ("MOTD_SECTION", notification.section),
+            ("MOTD_SEVERITY", severity.as_str().unwrap_or_default()),
+            (
+                "MOTD_PREVIOUS_SEVERITY",
+                previous.as_str().unwrap_or_default(),
+            ),
+            ("MOTD_SUMMARY", &notification.summary),
+        ],
+    )
+}
+
+/// Build webhook request URL, headers and body
+fn request(
+    webhook: &config::WebhookConfig,
+    notification: &Notification,
+) -> anyhow::Result<(String, Vec<String>, String)> {
+    let url = webhook.url.clone();
+    let request = match webhook.kind {
+        config::WebhookKind::Generic => (
+            url,
+            vec!["Content-Type: application/json".to_owned()],
+            serde_json::to_string(notification)?,
+        ),
+        config::WebhookKind::Ntfy => {
+            let (priority, tags) = match notification.severity {
+                Severity::Ok => ("default", "white_check_mark"),
+                Severity::Warning => ("high", "warning"),
+                Severity::Critical => ("urgent", "rotating_light"),
+            };
+            (
+                url,
+                vec![
+                    format!("Title: {}", notification.title()),
+                    format!("Priority: {priority}"),
+                    format!("Tags: {tags}"),
+                ],
+                notification.message(),
+            )
+        }
+        config::WebhookKind::Gotify => {
+            let token = webhook
+                .token
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Gotify webhook requires a token"))?;
+            let priority = match notification.severity {
+                Severity::Ok => 2,
+                Severity::Warning => 5,
+                Severity::Critical => 8,
+            };
+            (
+                format!("{}/message", url.trim_end_matches('/')),
+                vec![
+                    "Content-Type: application/json".to_owned(),
+                    format!("X-Gotify-Key: {token}"),
+                ],
+                serde_json::json!({
+                    "title": notification.title(),
+                    "message": notification.message(),
+                    "priority": priority,
+                })
+                .to_string(),
+            )
+        }
+        config::WebhookKind::Slack => (
+            url,
+            vec!["Content-Type: application/json".to_owned()],
+            serde_json::json!({
+                "text": format!("*{}*\n{}", notification.title(), notification.message()),
+            })
+            .to_string(),
+        ),
+    };
+    Ok(request)
+}
+
+/// Send notification to a webhook with curl
+fn notify(webhook: &config::WebhookConfig, notification: &Notification) -> anyhow::Result<()> {
+    let (url, headers, body) = request(webhook, notification)?;
+    curl::request(&url, &headers, Some(&body))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        let notification = Notification {
+            hostname: "server",
+            section: "fs",
+            title: "Filesystem usage",
+            severity: Severity::Critical,
+            previous: Severity::Ok,
+            summary: "/ 97%".to_owned(),
+        };
+        let mut webhook = config::WebhookConfig {
+            kind: config::WebhookKind::Generic,
+            url: "https://example.com/hook".to_owned(),
+            token: None,
+        };
+        assert_eq!(
+            request(&webhook, &notification).unwrap(),
+            (
+                "https://example.com/hook".to_owned(),
+                vec!["Content-Type: application/json".to_owned()],
+                "{\"hostname\":\"server\",\"section\":\"fs\",\"severity\":\"critical\",\"previous\":\"ok\",\"summary\":\"/ 97%\"}".to_owned()
+            )
+        );
+
+        webhook.kind = config::WebhookKind::Ntfy;
+        let (_, headers, body) = request(&webhook, &notification).unwrap();
+        assert_eq!(
+            headers,
+            vec![
+                "Title: motd on server",
+                "Priority: urgent",
+                "Tags: rotating_light"
+            ]
+        );
+        assert_eq!(body, "Filesystem usage is in critical state: / 97%");
+
+        webhook.kind = config::WebhookKind::Gotify;
+        assert!(request(&webhook, &notification).is_err());
+        webhook.token = Some("secret".to_owned());
+        let (gotify_url, gotify_headers, _) = request(&webhook, &notification).unwrap();
+        assert_eq!(gotify_url, "https://example.com/hook/message");
+        // Passed to curl on stdin, not in its arguments
+        assert_eq!(gotify_headers[1], "X-Gotify-Key: secret");
+    }
+}
@@ -0,0 +1,143 @@
+//! Hostname banner in large letters
+
+use std::{ffi::CStr, io};
+
+use crate::config;
+
+/// Height of font glyphs
+const FONT_HEIGHT: usize = 5;
+
+/// Character used to draw glyphs
+const FILL_CHAR: char = '█';
+
+/// Glyph rows, '#' is filled and '.' is empty
+type Glyph = [&'static str; FONT_HEIGHT];
+
+/// Get glyph of the embedded font, case insensitive, '?' for unsupported characters
+fn glyph(c: char) -> Glyph {
+    match c.to_ascii_lowercase() {
+        'a' => [".##.", "#..#", "####", "#..#", "#..#"],
+        'b' => ["###.", "#..#", "###.", "#..#", "###."],
+        'c' => [".###", "#...", "#...", "#...", ".###"],
+        'd' => ["###.", "#..#", "#..#", "#..#", "###."],
+        'e' => ["####", "#...", "###.", "#...", "####"],
+        'f' => ["####", "#...", "###.", "#...", "#..."],
+        'g' => [".###", "#...", "#.##", "#..#", ".###"],
+        'h' => ["#..#", "#..#", "####", "#..#", "#..#"],
+        'i' => ["###", ".#.", ".#.", ".#.", "###"],
+        'j' => ["..##", "...#", "...#", "#..#", ".##."],
+        'k' => ["#..#", "#.#.", "##..", "#.#.", "#..#"],
+        'l' => ["#...", "#...", "#...", "#...", "####"],
+        'm' => ["#...#", "##.##", "#.#.#", "#...#", "#...#"],
+        'n' => ["#...#", "##..#", "#.#.#", "#..##", "#...#"],
+        'o' => [".##.", "#..#", "#..#", "#..#", ".##."],
+        'p' => ["###.", "#..#", "###.", "#...", "#..."],
+        'q' => [".##.", "#..#", "#..#", "#.##", ".###"],
+        'r' => ["###.", "#..#", "###.", "#.#.", "#..#"],
+        's' => [".###", "#...", ".##.", "...#", "###."],
+        't' => ["#####", "..#..", "..#..", "..#..", "..#.."],
+        'u' => ["#..#", "#..#", "#..#", "#..#", ".##."],
+        'v' => ["#...#", "#...#", "#...#", ".#.#.", "..#.."],
+        'w' => ["#...#", "#...#", "#.#.#", "##.##", "#...#"],
+        'x' => ["#...#", ".#.#.", "..#..", ".#.#.", "#...#"],
+        'y' => ["#...#", ".#.#.", "..#..", "..#..", "..#.."],
+        'z' => ["####", "...#", ".##.", "#...", "####"],
+        '0' => [".##.", "#.##", "##.#", "#..#", ".##."],
+        '1' => [".#.", "##.", ".#.", ".#.", "###"],
+        '2' => ["###.", "...#", ".##.", "#...", "####"],
+        '3' => ["###.", "...#", ".##.", "...#", "###."],
+        '4' => ["#..#", "#..#", "####", "...#", "...#"],
+        '5' => ["####", "#...", "###.", "...#", "###."],
+        '6' => [".##.", "#...", "###.", "#..#", ".##."],
+        '7' => ["####", "...#", "..#.", ".#..", ".#.."],
+        '8' => [".##.", "#..#", ".##.", "#..#", ".##."],
+        '9' => [".##.", "#..#", ".###", "...#", ".##."],
+        '-' => ["...", "...", "###", "...", "..."],
+        '.' => [".", ".", ".", ".", "#"],
+        '_' => ["....", "....", "....", "....", "####"],
+        ' ' => ["..", "..", "..", "..", ".."],
+        _ => ["###.", "...#", ".##.", "....", ".#.."],
+    }
+}
+
+/// Render text in large letters, `None` if it does not fit in `columns`
+fn render_text(text: &str, columns: usize) -> Option<String> {
+    let glyphs: Vec<Glyph> = text.chars().map(glyph).collect();
+    let width = glyphs.iter().map(|g| g[0].len() + 1).sum::<usize>();
+    if width.saturating_sub(1) > columns {
+        return None;
+    }
+    let mut lines = String::new();
+    for row in 0..FONT_HEIGHT {
+        let line = glyphs
+            .iter()
+            .map(|g| {
+                g[row]
+                    .replace('#', &FILL_CHAR.to_string())
+                    .replace('.', " ")
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        lines.push_str(line.trim_end());
+        lines.push('\n');
+    }
+    Some(lines)
+}
+
+/// Get system hostname
+pub(crate) fn hostname() -> io::Result<String> {
+    let mut buf = [0_u8; 256];
+    // SAFETY: libc call, buffer size is passed, and is one byte larger to ensure null termination
+    let rc = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len() - 1) };
+    if rc != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let name = CStr::from_bytes_until_nul(&buf).map_err(io::Error::other)?;
+    Ok(name.to_string_lossy().into_owned())
+}
+
+/// Render banner lines, falling back to the short hostname, then to plain text if too wide
+pub(crate) fn render(cfg: &config::BannerConfig, columns: usize) -> io::Result<String> {
+    let text = match cfg.text.as_ref() {
+        Some(text) => text.to_owned(),
+        None => hostname()?,
+    };
+    let short_text = text.split('.').next().unwrap_or_default();
+    let lines = render_text(&text, columns)
+        .or_else(|| render_text(short_text, columns))
+        .unwrap_or_else(|| format!("{text}\n"));
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_text() {
+        assert_eq!(
+            render_text("Hi-1", 80).unwrap(),
+            "\
+█  █ ███      █
+█  █  █      ██
+████  █  ███  █
+█  █  █       █
+█  █ ███     ███
+"
+        );
+        assert_eq!(render_text("Hi-1", 16).unwrap().lines().count(), 5);
+        assert!(render_text("Hi-1", 15).is_none());
+    }
+
+    #[test]
+    fn test_render() {
+        let cfg = config::BannerConfig {
+            enabled: true,
+            text: Some("host.example.com".to_owned()),
+            style: None,
+        };
+        assert_eq!(render(&cfg, 200).unwrap().lines().count(), 5);
+        assert_eq!(render(&cfg, 20).unwrap(), render_text("host", 20).unwrap());
+        assert_eq!(render(&cfg, 10).unwrap(), "host.example.com\n");
+    }
+}
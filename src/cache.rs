@@ -0,0 +1,284 @@
+//! Rendered output cache, to display it instantly at login
+
+use std::{
+    fs::{self, File},
+    io::{self, Write as _},
+    mem,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use crate::fmt::strip_ansi;
+
+/// System wide cache directory, written to by root
+const SYSTEM_CACHE_DIR: &str = "/run/motd";
+
+/// Cached output with colors
+const COLOR_FILENAME: &str = "motd.ansi";
+
+/// Cached output without colors
+const PLAIN_FILENAME: &str = "motd.txt";
+
+/// Lock file held while refreshing cache in the background
+const REFRESH_LOCK_FILENAME: &str = "refresh.lock";
+
+/// Directory of cached section data, in the user cache directory
+const SECTIONS_DIRNAME: &str = "sections";
+
+/// Replace file content, so that readers never see a partially written file
+pub(crate) fn write_atomic(filepath: &Path, data: &[u8]) -> io::Result<()> {
+    /// Count of written files, to get unique temporary file names
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let mut tmp_filename = filepath.file_name().unwrap_or_default().to_owned();
+    tmp_filename.push(format!(
+        ".{}-{}.tmp",
+        process::id(),
+        COUNT.fetch_add(1, Ordering::SeqCst)
+    ));
+    let tmp_filepath = filepath.with_file_name(tmp_filename);
+    // Fails if it already exists, so that concurrent writers do not share it, and a symlink
+    // prepared by another user is not followed
+    let mut file = File::options()
+        .write(true)
+        .create_new(true)
+        .open(&tmp_filepath)?;
+    let res = file
+        .write_all(data)
+        .and_then(|()| file.sync_all())
+        .and_then(|()| fs::rename(&tmp_filepath, filepath));
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp_filepath);
+    }
+    res
+}
+
+/// User cache directory
+fn user_cache_dir() -> anyhow::Result<PathBuf> {
+    let binary_name = env!("CARGO_PKG_NAME");
+    let xdg_dirs = xdg::BaseDirectories::with_prefix(binary_name)?;
+    Ok(xdg_dirs.get_cache_home())
+}
+
+/// Directory to write cache to: system wide for root, user cache directory otherwise
+fn write_cache_dir() -> anyhow::Result<PathBuf> {
+    // SAFETY: libc call without arguments, that can not fail
+    if unsafe { libc::geteuid() } == 0 {
+        Ok(PathBuf::from(SYSTEM_CACHE_DIR))
+    } else {
+        user_cache_dir()
+    }
+}
+
+/// Store color and plain variants of rendered output
+pub(crate) fn write(color_output: &[u8]) -> anyhow::Result<()> {
+    let cache_dir = write_cache_dir()?;
+    fs::create_dir_all(&cache_dir)?;
+    write_atomic(&cache_dir.join(COLOR_FILENAME), color_output)?;
+    let plain_output = strip_ansi(&String::from_utf8_lossy(color_output));
+    write_atomic(&cache_dir.join(PLAIN_FILENAME), plain_output.as_bytes())?;
+    Ok(())
+}
+
+/// Fork a process detached from the terminal to refresh cache, and return true in that process
+///
+/// The process must not have started threads. If another refresh is running, the forked process
+/// exits immediately.
+pub(crate) fn fork_refresher() -> anyhow::Result<bool> {
+    // SAFETY: libc call, the process has a single thread so the forked process state is consistent
+    let pid = unsafe { libc::fork() };
+    anyhow::ensure!(pid >= 0, "Failed to fork: {}", io::Error::last_os_error());
+    if pid > 0 {
+        return Ok(false);
+    }
+    // New session without terminal, so that the refresher outlives the login shell and does not
+    // write to its terminal
+    // SAFETY: libc calls on this process and its own file descriptors
+    unsafe {
+        libc::setsid();
+        let devnull = libc::open(c"/dev/null".as_ptr(), libc::O_RDWR);
+        if devnull >= 0 {
+            for fd in 0..=2 {
+                libc::dup2(devnull, fd);
+            }
+            if devnull > 2 {
+                libc::close(devnull);
+            }
+        }
+    }
+    let cache_dir = write_cache_dir()?;
+    fs::create_dir_all(&cache_dir)?;
+    let lock = File::create(cache_dir.join(REFRESH_LOCK_FILENAME))?;
+    if lock.try_lock().is_err() {
+        process::exit(0);
+    }
+    // Lock is released when the process exits
+    mem::forget(lock);
+    Ok(true)
+}
+
+/// Cached section data file, named after the section key
+fn section_filepath(key: &str) -> anyhow::Result<PathBuf> {
+    let filename: String = key
+        .chars()
+        .map(|c| if c == '/' { '_' } else { c })
+        .collect();
+    Ok(user_cache_dir()?
+        .join(SECTIONS_DIRNAME)
+        .join(format!("{filename}.json")))
+}
+
+/// Store section data
+pub(crate) fn write_section<T: serde::Serialize>(key: &str, data: &T) -> anyhow::Result<()> {
+    write_section_file(&section_filepath(key)?, data)
+}
+
+/// Read section data, if it was stored less than `ttl` ago and can be parsed
+pub(crate) fn read_section<T: serde::de::DeserializeOwned>(key: &str, ttl: Duration) -> Option<T> {
+    read_section_file(&section_filepath(key).ok()?, ttl)
+}
+
+/// Store section data to a file
+fn write_section_file<T: serde::Serialize>(filepath: &Path, data: &T) -> anyhow::Result<()> {
+    if let Some(dir) = filepath.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_atomic(filepath, &serde_json::to_vec(data)?)?;
+    Ok(())
+}
+
+/// Read section data from a file, if it was modified less than `ttl` ago and can be parsed
+fn read_section_file<T: serde::de::DeserializeOwned>(filepath: &Path, ttl: Duration) -> Option<T> {
+    let age = fs::metadata(filepath)
+        .ok()?
+        .modified()
+        .ok()?
+        .elapsed()
+        .ok()?;
+    if age >= ttl {
+        return None;
+    }
+    serde_json::from_slice(&fs::read(filepath).ok()?).ok()
+}
+
+/// Read cached output, the most recent of user cache and system wide cache, if it is less than
+/// `max_age` old
+pub(crate) fn read(color: bool, max_age: Duration) -> Option<Vec<u8>> {
+    let filename = if color {
+        COLOR_FILENAME
+    } else {
+        PLAIN_FILENAME
+    };
+    let filepaths: Vec<_> = user_cache_dir()
+        .ok()
+        .into_iter()
+        .chain([PathBuf::from(SYSTEM_CACHE_DIR)])
+        .map(|d| d.join(filename))
+        .collect();
+    read_newest(&filepaths, max_age)
+}
+
+/// Read the most recently modified file, if it is less than `max_age` old
+fn read_newest(filepaths: &[PathBuf], max_age: Duration) -> Option<Vec<u8>> {
+    filepaths
+        .iter()
+        .filter_map(|p| {
+            let age = fs::metadata(p).ok()?.modified().ok()?.elapsed().ok()?;
+            (age < max_age).then_some((age, p))
+        })
+        .min_by_key(|(age, _)| *age)
+        .and_then(|(_, p)| fs::read(p).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, time::SystemTime};
+
+    use super::*;
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = env::temp_dir().join(format!("motd-cache-atomic-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let filepath = dir.join("motd");
+
+        write_atomic(&filepath, b"a").unwrap();
+        write_atomic(&filepath, b"b").unwrap();
+        assert_eq!(fs::read(&filepath).unwrap(), b"b");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // Temporary file is removed if replacing fails
+        fs::create_dir(dir.join("subdir")).unwrap();
+        fs::write(dir.join("subdir/file"), "").unwrap();
+        assert!(write_atomic(&dir.join("subdir"), b"c").is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_section_file() {
+        let dir = env::temp_dir().join(format!("motd-cache-test-{}", process::id()));
+        let filepath = dir.join(SECTIONS_DIRNAME).join("updates.json");
+        let ttl = Duration::from_hours(1);
+
+        assert_eq!(read_section_file::<Vec<u32>>(&filepath, ttl), None);
+
+        write_section_file(&filepath, &vec![1, 2]).unwrap();
+        assert_eq!(read_section_file(&filepath, ttl), Some(vec![1, 2]));
+        assert_eq!(
+            read_section_file::<Vec<u32>>(&filepath, Duration::ZERO),
+            None
+        );
+        assert_eq!(read_section_file::<String>(&filepath, ttl), None);
+
+        File::options()
+            .write(true)
+            .open(&filepath)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_hours(2))
+            .unwrap();
+        assert_eq!(read_section_file::<Vec<u32>>(&filepath, ttl), None);
+
+        fs::write(&filepath, "[1, 2").unwrap();
+        assert_eq!(read_section_file::<Vec<u32>>(&filepath, ttl), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_newest() {
+        let dir = env::temp_dir().join(format!("motd-cache-read-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let filepaths = [dir.join("user.ansi"), dir.join("system.ansi")];
+        let max_age = Duration::from_hours(1);
+        let set_age = |filepath: &Path, age: Duration| {
+            File::options()
+                .write(true)
+                .open(filepath)
+                .unwrap()
+                .set_modified(SystemTime::now() - age)
+                .unwrap();
+        };
+
+        assert_eq!(read_newest(&filepaths, max_age), None);
+
+        fs::write(&filepaths[1], "system").unwrap();
+        assert_eq!(read_newest(&filepaths, max_age), Some(b"system".to_vec()));
+
+        // Stale user cache does not hide fresher system wide cache
+        fs::write(&filepaths[0], "user").unwrap();
+        set_age(&filepaths[0], Duration::from_mins(30));
+        set_age(&filepaths[1], Duration::from_mins(5));
+        assert_eq!(read_newest(&filepaths, max_age), Some(b"system".to_vec()));
+        set_age(&filepaths[1], Duration::from_mins(40));
+        assert_eq!(read_newest(&filepaths, max_age), Some(b"user".to_vec()));
+
+        set_age(&filepaths[0], Duration::from_hours(2));
+        set_age(&filepaths[1], Duration::from_hours(3));
+        assert_eq!(read_newest(&filepaths, max_age), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,19 +1,182 @@
 //! Local configuration
 
+use std::{
+    env,
+    fmt::Write as _,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
+};
+
+use anyhow::Context as _;
+
+use crate::{banner, i18n, style};
+
 /// Local configuration
-#[derive(Debug, Default, serde::Deserialize)]
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub(crate) struct Config {
+    /// Default display choices, overridden by command line options
+    pub general: GeneralConfig,
+
+    /// Template file to render instead of the fixed section layout
+    pub template: Option<PathBuf>,
+
+    /// Only display entries above warning thresholds
+    pub quiet: bool,
+
+    /// Language of section titles and labels, from environment if not set
+    pub locale: Option<i18n::Locale>,
+
     /// Filesystem module config
     pub fs: FsConfig,
 
     /// Temp module config
     pub temp: TempConfig,
+
+    /// Network module config
+    pub net: NetConfig,
+
+    /// Color theme
+    pub theme: style::Theme,
+
+    /// Nerd Font icons
+    pub icons: IconsConfig,
+
+    /// Section layout
+    pub layout: LayoutConfig,
+
+    /// Section header display
+    pub display: DisplayConfig,
+
+    /// Hostname banner
+    pub banner: BannerConfig,
+
+    /// Greeting line
+    pub greeting: GreetingConfig,
+
+    /// Metric history
+    pub history: HistoryConfig,
+
+    /// Admin messages module config
+    pub message: MessageConfig,
+
+    /// Systemd module config
+    pub systemd: SystemdConfig,
+
+    /// Alert notifications in daemon mode
+    pub alert: AlertConfig,
+
+    /// MQTT broker to publish section data to
+    pub mqtt: MqttConfig,
+
+    /// Hosts of the fleet overview
+    pub fleet: FleetConfig,
+
+    /// User defined sections
+    pub plugins: Vec<PluginConfig>,
+
+    /// Section fetch timeouts
+    pub timeouts: TimeoutsConfig,
+}
+
+/// Default display choices, overridden by command line options
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct GeneralConfig {
+    /// Letters of sections to display, in order
+    pub sections: Option<Vec<String>>,
+    /// Maximum terminal columns to use, like the --columns option
+    pub columns: Option<isize>,
+    /// Display section titles
+    pub titles: bool,
+    /// Output format
+    pub format: Option<OutputFormat>,
+    /// When to use colors
+    pub color: Option<ColorMode>,
+    /// Output a single summary line
+    pub compact: bool,
+}
+
+impl Default for GeneralConfig {
+    fn default() -> Self {
+        Self {
+            sections: None,
+            columns: None,
+            titles: true,
+            format: None,
+            color: None,
+            compact: false,
+        }
+    }
+}
+
+impl GeneralConfig {
+    /// Override settings with `MOTD_*` environment variables
+    fn apply_env(&mut self) -> anyhow::Result<()> {
+        self.apply_vars(|name| env::var(name).ok().filter(|v| !v.is_empty()))
+    }
+
+    /// Override settings with variables of a lookup function
+    fn apply_vars<F: Fn(&str) -> Option<String>>(&mut self, var: F) -> anyhow::Result<()> {
+        if let Some(sections) = var("MOTD_SECTIONS") {
+            self.sections = Some(
+                sections
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect(),
+            );
+        }
+        if let Some(columns) = var("MOTD_COLUMNS") {
+            self.columns = Some(
+                columns
+                    .parse()
+                    .with_context(|| format!("Invalid MOTD_COLUMNS value {columns:?}"))?,
+            );
+        }
+        if let Some(format) = var("MOTD_FORMAT") {
+            self.format = Some(parse_var("MOTD_FORMAT", &format)?);
+        }
+        if let Some(color) = var("MOTD_COLOR") {
+            self.color = Some(parse_var("MOTD_COLOR", &color)?);
+        }
+        Ok(())
+    }
+}
+
+/// Parse enum setting from an environment variable value, with the same names as in config
+fn parse_var<T: serde::de::DeserializeOwned>(name: &str, val: &str) -> anyhow::Result<T> {
+    T::deserialize(serde::de::value::StrDeserializer::<serde::de::value::Error>::new(val))
+        .with_context(|| format!("Invalid {name} value {val:?}"))
+}
+
+/// Output format
+#[derive(Debug, Clone, Copy, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum OutputFormat {
+    Text,
+    Json,
+    Yaml,
+    Prometheus,
+    Csv,
+    Influx,
+    Nagios,
+}
+
+/// When to use colors
+#[derive(Debug, Clone, Copy, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ColorMode {
+    Auto,
+    Always,
+    Never,
 }
 
 /// Filesystem module config
-#[derive(Debug, Default, serde::Deserialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
+#[expect(clippy::struct_excessive_bools)]
 pub(crate) struct FsConfig {
     /// Exclude filesystem whose type match any of theses regexs
     #[serde(with = "serde_regex")]
@@ -21,27 +184,1031 @@ pub(crate) struct FsConfig {
     /// Exclude filesystem whose mount point match any of theses regexs
     #[serde(with = "serde_regex")]
     pub mount_path_blacklist: Vec<regex::Regex>,
+    /// If not empty, only include filesystems whose mount point match any of theses regexs
+    #[serde(with = "serde_regex")]
+    pub mount_path_whitelist: Vec<regex::Regex>,
+    /// Filesystem sort order
+    pub sort: FsSort,
+    /// Reverse sort order
+    pub sort_descending: bool,
+    /// Usage percentage above which filesystem is considered almost full
+    pub usage_warning: f32,
+    /// Usage percentage above which filesystem is considered critically full
+    pub usage_critical: f32,
+    /// Usage threshold overrides for mount points
+    pub thresholds: Vec<FsThresholds>,
+    /// Display names of mount points
+    pub aliases: Vec<FsAlias>,
+    /// Maximum time to wait for a filesystem to report its usage, in milliseconds
+    pub statvfs_timeout_ms: u64,
+    /// Show read-only image filesystems (snap packages, ISO images...), which are always full
+    pub show_read_only_images: bool,
+    /// Bar text mode
+    pub display: FsDisplay,
+    /// Line layout
+    pub layout: FsLayout,
+    /// Minimum usage bar length, can not be less than 30
+    pub min_bar_len: usize,
+    /// Fixed mount point column width, instead of the longest mount point
+    pub label_width: Option<usize>,
+    /// Pad mount points so that all bars start on the same column
+    pub align_bars: bool,
+    /// Color bars with a smooth gradient from usage, if terminal supports 256 colors or more
+    pub gradient: bool,
+    /// Estimate days until each filesystem is full, from usage history
+    pub fill_prediction: bool,
+    /// File to store usage history, defaults to `$XDG_STATE_HOME/motd/fs_usage`
+    pub fill_history_file: Option<PathBuf>,
+    /// Usage history duration to estimate growth rate, in days
+    pub fill_history_days: u32,
+    /// Estimated days until full below which estimate is highlighted
+    pub fill_warning_days: u32,
+}
+
+/// Filesystem bar text mode
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum FsDisplay {
+    /// Used space
+    #[default]
+    Used,
+    /// Space left
+    Available,
+}
+
+/// Filesystem line layout
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum FsLayout {
+    /// Mount point and bar on the same line
+    #[default]
+    Inline,
+    /// Mount point on a line, full width bar on the next one
+    TwoLines,
+}
+
+impl Default for FsConfig {
+    fn default() -> Self {
+        Self {
+            mount_type_blacklist: Vec::new(),
+            mount_path_blacklist: Vec::new(),
+            mount_path_whitelist: Vec::new(),
+            sort: FsSort::default(),
+            sort_descending: false,
+            usage_warning: 85.0,
+            usage_critical: 95.0,
+            thresholds: Vec::new(),
+            aliases: Vec::new(),
+            statvfs_timeout_ms: 1000,
+            show_read_only_images: false,
+            display: FsDisplay::default(),
+            layout: FsLayout::default(),
+            min_bar_len: 30,
+            label_width: None,
+            align_bars: true,
+            gradient: false,
+            fill_prediction: false,
+            fill_history_file: None,
+            fill_history_days: 7,
+            fill_warning_days: 30,
+        }
+    }
+}
+
+/// Filesystem usage thresholds for mount points whose path match a regex
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct FsThresholds {
+    /// Mount point regex
+    #[serde(with = "serde_regex")]
+    pub mount_path: regex::Regex,
+    /// Usage percentage above which filesystem is considered almost full
+    pub warning: f32,
+    /// Usage percentage above which filesystem is considered critically full
+    pub critical: f32,
+}
+
+/// Display name for mount points whose path match a regex
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct FsAlias {
+    /// Mount point regex
+    #[serde(with = "serde_regex")]
+    pub mount_path: regex::Regex,
+    /// Display name, can reference regex capture groups like `$1`
+    pub name: String,
+}
+
+/// Filesystem sort key
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum FsSort {
+    /// Mount point path
+    #[default]
+    Path,
+    /// Usage percentage
+    Usage,
+    /// Total size
+    Size,
+    /// Free space
+    Free,
 }
 
 /// Temp module config
-#[derive(Debug, Default, serde::Deserialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
+#[expect(clippy::struct_excessive_bools)]
 pub(crate) struct TempConfig {
     /// Exclude temp probes label (/sys/class/hwmon/hwmon*/temp*_label files) matching any of theses regexs
     #[serde(with = "serde_regex")]
     pub hwmon_label_blacklist: Vec<regex::Regex>,
     // TODO blacklist for names too (/sys/class/hwmon/hwmon*/name)?
+    /// Probe hwmon sensors directly from sysfs
+    pub hwmon_enabled: bool,
+    /// Parse `sensors -j` output if hwmon probing is disabled or found no sensor
+    pub lm_sensors_fallback: bool,
+    /// Collapse "Core N" sensors of each CPU package into a single min/avg/max line
+    pub aggregate_cpu_cores: bool,
+    /// Query hddtemp daemon for drive temperatures
+    pub hddtemp_enabled: bool,
+    /// hddtemp daemon address
+    pub hddtemp_addr: SocketAddr,
+    /// hddtemp connection timeout in milliseconds
+    pub hddtemp_timeout_ms: u64,
+    /// Skip drivetemp sensors of drives in standby, to avoid spinning them up. Querying the power
+    /// state needs root or `CAP_SYS_RAWIO`, otherwise only drives that runtime power management
+    /// reports as active are read
+    pub skip_standby_drives: bool,
+    /// Unit to display temperatures in
+    pub unit: TempUnit,
+    /// Temperature display mode
+    pub display: TempDisplay,
+    /// Explicit warning & critical temperatures for sensors, overriding values deduced from hwmon
+    pub thresholds: Vec<TempThresholds>,
+    /// Sensor renaming rules
+    pub rename: Vec<TempRename>,
+    /// Display temperature trend since previous run
+    pub trend: bool,
+    /// File to store temperatures between runs, defaults to `$XDG_STATE_HOME/motd/temps`
+    pub trend_state_file: Option<PathBuf>,
+}
+
+/// Temperature display mode
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TempDisplay {
+    /// Temperature values
+    #[default]
+    Text,
+    /// Bars scaled between ambient and critical temperature, followed by values
+    Bars,
+}
+
+/// Warning & critical temperatures for sensors whose name match a regex
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct TempThresholds {
+    /// Sensor name regex
+    #[serde(with = "serde_regex")]
+    pub label: regex::Regex,
+    /// Temperature in Celsius above which sensor is considered anormally hot
+    pub warning: u32,
+    /// Temperature in Celsius above which sensor is considered critically hot
+    pub critical: u32,
+}
+
+/// Temperature display unit
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TempUnit {
+    /// Degrees Celsius
+    #[default]
+    Celsius,
+    /// Degrees Fahrenheit
+    Fahrenheit,
+}
+
+impl Default for TempConfig {
+    fn default() -> Self {
+        Self {
+            hwmon_label_blacklist: Vec::new(),
+            hwmon_enabled: true,
+            lm_sensors_fallback: true,
+            aggregate_cpu_cores: false,
+            hddtemp_enabled: true,
+            hddtemp_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 7634),
+            hddtemp_timeout_ms: 100,
+            skip_standby_drives: true,
+            unit: TempUnit::default(),
+            display: TempDisplay::default(),
+            thresholds: Vec::new(),
+            rename: Vec::new(),
+            trend: false,
+            trend_state_file: None,
+        }
+    }
+}
+
+/// Network module config
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct NetConfig {
+    /// Also display interface driver, MAC and IP addresses, and duplex
+    pub verbose: bool,
+    /// Display overall connectivity state from NetworkManager or systemd-networkd in the section header
+    pub connectivity: bool,
+    /// Check for a global IPv6 address, and that `ipv6_target` is reachable
+    pub ipv6_check: bool,
+    /// IPv6 address and TCP port to connect to for the IPv6 check
+    pub ipv6_target: SocketAddr,
+    /// IPv6 check connection timeout in milliseconds
+    pub ipv6_timeout_ms: u64,
+    /// Exclude interfaces whose name match any of theses regexs
+    #[serde(with = "serde_regex")]
+    pub interface_blacklist: Vec<regex::Regex>,
+    /// If not empty, only include interfaces whose name match any of theses regexs
+    #[serde(with = "serde_regex")]
+    pub interface_whitelist: Vec<regex::Regex>,
+    /// Unit of rates
+    pub unit: RateUnit,
+}
+
+/// Network rate unit
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RateUnit {
+    /// Bits per second, like line speeds
+    #[default]
+    Bits,
+    /// Bytes per second, like download speeds
+    Bytes,
 }
 
-/// Parse local configuration
+impl Default for NetConfig {
+    fn default() -> Self {
+        Self {
+            verbose: false,
+            connectivity: true,
+            ipv6_check: false,
+            // Cloudflare public DNS, also listening on HTTPS
+            ipv6_target: SocketAddr::new(
+                IpAddr::V6(Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111)),
+                443,
+            ),
+            ipv6_timeout_ms: 500,
+            interface_blacklist: Vec::new(),
+            interface_whitelist: Vec::new(),
+            unit: RateUnit::default(),
+        }
+    }
+}
+
+/// Nerd Font icons config, each section is opt-in
+#[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+#[expect(clippy::struct_excessive_bools)]
+pub(crate) struct IconsConfig {
+    /// Show icons in load section
+    pub load: bool,
+    /// Show icons in memory section
+    pub memory: bool,
+    /// Show icons in swap section
+    pub swap: bool,
+    /// Show icons in filesystem section
+    pub fs: bool,
+    /// Show icons in temperature section
+    pub temp: bool,
+    /// Show icons in network section
+    pub net: bool,
+    /// Show icons in systemd failed units section
+    pub systemd: bool,
+    /// Show icons in messages section
+    pub message: bool,
+    /// Show icons in plugin sections
+    pub plugin: bool,
+}
+
+/// Section layout config
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct LayoutConfig {
+    /// Letters of sections that can be placed side by side on wide terminals
+    pub side_by_side: Vec<String>,
+    /// Minimum width of each column
+    pub min_column_width: usize,
+    /// Maximum number of columns
+    pub max_columns: usize,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            side_by_side: Vec::new(),
+            min_column_width: 50,
+            max_columns: 3,
+        }
+    }
+}
+
+/// Admin messages module config
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct MessageConfig {
+    /// Message files, or directories containing message files
+    pub paths: Vec<PathBuf>,
+    /// Shell command whose output is displayed as a message
+    pub command: Option<String>,
+    /// Ignore message files not modified since this number of days
+    pub max_age_days: Option<u32>,
+}
+
+/// Systemd module config
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct SystemdConfig {
+    /// Managers to query units of
+    pub scopes: Vec<SystemdScope>,
+    /// Units to ignore, like known flaky units
+    #[serde(with = "serde_regex")]
+    pub unit_blacklist: Vec<regex::Regex>,
+    /// Also display units activating or deactivating for at least this number of minutes, 0 to
+    /// disable
+    pub stuck_minutes: u64,
+    /// Display services using deleted shared libraries, which need a restart after upgrades
+    pub needs_restart: bool,
+}
+
+impl Default for SystemdConfig {
+    fn default() -> Self {
+        Self {
+            scopes: vec![SystemdScope::System, SystemdScope::User],
+            unit_blacklist: Vec::new(),
+            stuck_minutes: 10,
+            needs_restart: true,
+        }
+    }
+}
+
+/// Systemd manager scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SystemdScope {
+    /// System manager
+    System,
+    /// Manager of the current user
+    User,
+}
+
+/// Alert notifications config
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct AlertConfig {
+    /// Webhooks to notify when a section enters or leaves warning or critical state
+    pub webhooks: Vec<WebhookConfig>,
+    /// Log destination of section state changes, in daemon mode and Nagios checks
+    pub log: Option<LogTarget>,
+}
+
+/// Log destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogTarget {
+    /// Journald native protocol, with structured fields
+    Journald,
+    /// Syslog socket
+    Syslog,
+}
+
+/// Alert webhook
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct WebhookConfig {
+    /// Service type, that determines the request format
+    #[serde(default)]
+    pub kind: WebhookKind,
+    /// URL to send requests to
+    pub url: String,
+    /// Application token, for Gotify
+    pub token: Option<String>,
+}
+
+/// Webhook service type
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum WebhookKind {
+    /// JSON object with hostname, section, severity, previous severity and summary
+    #[default]
+    Generic,
+    /// ntfy topic
+    Ntfy,
+    /// Gotify server
+    Gotify,
+    /// Slack incoming webhook
+    Slack,
+}
+
+/// User defined section, displaying the output of a command
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct PluginConfig {
+    /// Key in structured output
+    pub name: String,
+    /// Section title
+    pub title: String,
+    /// Shell command
+    pub command: Option<String>,
+    /// Native plugin shared library, used instead of a command
+    pub library: Option<PathBuf>,
+    /// Command output format
+    #[serde(default)]
+    pub format: PluginFormat,
+    /// Letter of the section to display this section after, if not set it is displayed at the
+    /// position of the plugins letter
+    pub after: Option<String>,
+    /// Seconds to reuse cached output for, instead of running the plugin for each output, only for
+    /// command plugins since built-in sections are never cached
+    pub cache_ttl: Option<u64>,
+}
+
+/// Plugin command output format
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PluginFormat {
+    /// Plain text, with severity from the exit code like Nagios plugins
+    #[default]
+    Text,
+    /// JSON object with lines and their severity
+    Json,
+}
+
+/// Fleet overview config
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct FleetConfig {
+    /// Hosts to display, used if none are passed on the command line
+    pub hosts: Vec<FleetHost>,
+}
+
+/// Fleet host
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct FleetHost {
+    /// Displayed name, defaults to the URL host
+    pub name: Option<String>,
+    /// URL of JSON data, like the `/json` endpoint of `motd serve`
+    pub url: String,
+}
+
+/// MQTT publishing config
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct MqttConfig {
+    /// Broker host
+    pub host: Option<String>,
+    /// Broker port, defaults to 1883, or 8883 with TLS
+    pub port: Option<u16>,
+    /// Topics are `<prefix>/<hostname>/<section>`
+    pub topic_prefix: String,
+    /// Connect with TLS
+    pub tls: bool,
+    /// CA certificate file for TLS, system certificates are used if not set
+    pub ca_file: Option<PathBuf>,
+    /// Authentication user name
+    pub username: Option<String>,
+    /// Authentication password
+    pub password: Option<String>,
+    /// Publish retained messages, so that subscribers get the last data immediately
+    pub retain: bool,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host: None,
+            port: None,
+            topic_prefix: "motd".to_owned(),
+            tls: false,
+            ca_file: None,
+            username: None,
+            password: None,
+            retain: true,
+        }
+    }
+}
+
+/// Metric history config
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct HistoryConfig {
+    /// Display sparklines of recent load, memory usage and network throughput
+    pub sparklines: bool,
+    /// Number of samples to keep
+    pub samples: usize,
+    /// Minimum interval between stored samples, in minutes, 0 to store one at each run
+    pub min_interval_minutes: u64,
+    /// Directory to store history, defaults to `$XDG_STATE_HOME/motd`
+    pub state_dir: Option<PathBuf>,
+    /// Record samples of all metrics in watch and daemon modes
+    pub store: bool,
+    /// Remove recorded samples older than this, in days
+    pub retention_days: u64,
+    /// Highlight values this many times above their recorded median, 0 to disable
+    pub anomaly_factor: f64,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            sparklines: false,
+            samples: 12,
+            min_interval_minutes: 0,
+            state_dir: None,
+            store: false,
+            retention_days: 30,
+            anomaly_factor: 5.0,
+        }
+    }
+}
+
+/// Greeting line config
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct GreetingConfig {
+    /// Display greeting line above sections
+    pub enabled: bool,
+    /// `MiniJinja` template, with `user`, `hostname` and `last_login` (`time`, `ago`, `line` and `host`) values
+    pub template: String,
+}
+
+impl Default for GreetingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            template: "Welcome back, {{ user }}\
+                {% if last_login %} — last login {{ last_login.ago }}\
+                {% if last_login.host %} from {{ last_login.host }}{% endif %}{% endif %}"
+                .to_owned(),
+        }
+    }
+}
+
+/// Hostname banner config
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct BannerConfig {
+    /// Display banner above sections
+    pub enabled: bool,
+    /// Text to display instead of the hostname
+    pub text: Option<String>,
+    /// Banner style
+    #[serde(
+        serialize_with = "style::serialize_style",
+        deserialize_with = "style::deserialize_style"
+    )]
+    pub style: Option<ansi_term::Style>,
+}
+
+/// Section header display config
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct DisplayConfig {
+    /// Character used to fill header lines
+    pub fill: char,
+    /// Title position in header line
+    pub align: TitleAlign,
+    /// Title casing
+    pub case: TitleCase,
+    /// Only display a separator line, without title
+    pub separator_only: bool,
+    /// Number of blank lines between sections
+    pub section_spacing: usize,
+    /// Display consecutive sections under a single header
+    pub merge_headers: bool,
+    /// Display a separator line after the last section
+    pub trailing_separator: bool,
+    /// Maximum number of lines for each section
+    pub max_lines: MaxLinesConfig,
+    /// Usage bar characters
+    pub bar: BarConfig,
+    /// Prefixes of memory, swap and filesystem sizes
+    pub units: SizeUnits,
+    /// Decimal places of sizes and rates
+    pub precision: usize,
+    /// Minimum width of sizes and rates, right aligned
+    pub value_width: usize,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            fill: '─',
+            align: TitleAlign::default(),
+            case: TitleCase::default(),
+            separator_only: false,
+            section_spacing: 0,
+            merge_headers: false,
+            trailing_separator: false,
+            max_lines: MaxLinesConfig::default(),
+            bar: BarConfig::default(),
+            units: SizeUnits::default(),
+            precision: 1,
+            value_width: 0,
+        }
+    }
+}
+
+/// Size prefixes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SizeUnits {
+    /// Powers of 1024, like free and df -h
+    #[default]
+    Binary,
+    /// Powers of 1000, like storage vendors and df -H
+    Si,
+    /// No prefix, exact values with thousands separators
+    Exact,
+}
+
+/// Usage bar characters, of memory, swap, filesystem and temperature bars
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct BarConfig {
+    /// Character filling used parts
+    pub fill: char,
+    /// Character filling empty parts
+    pub empty: char,
+    /// Left end cap
+    pub left: char,
+    /// Right end cap
+    pub right: char,
+}
+
+impl Default for BarConfig {
+    fn default() -> Self {
+        Self {
+            fill: '█',
+            empty: ' ',
+            left: '▕',
+            right: '▏',
+        }
+    }
+}
+
+/// Maximum number of lines config, sections are not limited by default
+#[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct MaxLinesConfig {
+    /// Maximum lines in load section
+    pub load: Option<usize>,
+    /// Maximum lines in memory section
+    pub memory: Option<usize>,
+    /// Maximum lines in swap section
+    pub swap: Option<usize>,
+    /// Maximum lines in filesystem section
+    pub fs: Option<usize>,
+    /// Maximum lines in temperature section
+    pub temp: Option<usize>,
+    /// Maximum lines in network section
+    pub net: Option<usize>,
+    /// Maximum lines in systemd failed units section
+    pub systemd: Option<usize>,
+    /// Maximum lines in messages section
+    pub message: Option<usize>,
+}
+
+/// Section fetch timeouts config in milliseconds, sections are not limited by default
+#[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct TimeoutsConfig {
+    /// Timeout of sections without a specific timeout
+    pub default: Option<u64>,
+    /// Load section timeout
+    pub load: Option<u64>,
+    /// Memory section timeout
+    pub memory: Option<u64>,
+    /// Swap section timeout
+    pub swap: Option<u64>,
+    /// Filesystem section timeout
+    pub fs: Option<u64>,
+    /// Temperature section timeout
+    pub temp: Option<u64>,
+    /// Network section timeout
+    pub net: Option<u64>,
+    /// Systemd failed units section timeout
+    pub systemd: Option<u64>,
+    /// Messages section timeout
+    pub message: Option<u64>,
+    /// Plugin sections timeout
+    pub plugin: Option<u64>,
+}
+
+/// Section title position
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TitleAlign {
+    /// Left of header line
+    Left,
+    /// Centered in header line
+    #[default]
+    Center,
+    /// Right of header line
+    Right,
+}
+
+/// Section title casing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TitleCase {
+    /// Title as is
+    #[default]
+    Unchanged,
+    /// Upper case
+    Upper,
+    /// Lower case
+    Lower,
+}
+
+/// Friendly name for sensors whose name match a regex
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct TempRename {
+    /// Sensor name regex
+    #[serde(with = "serde_regex")]
+    pub pattern: regex::Regex,
+    /// Replacement name, can reference regex capture groups like `$1`
+    pub name: String,
+}
+
+/// Parse local configuration, merging `config.toml` with `conf.d/*.toml` drop-ins in file name
+/// order, host profiles matching the hostname, and `MOTD_*` environment variables
 pub(crate) fn parse_config() -> anyhow::Result<Config> {
     let binary_name = env!("CARGO_PKG_NAME");
     let xdg_dirs = xdg::BaseDirectories::with_prefix(binary_name)?;
-    let config = if let Some(config_filepath) = xdg_dirs.find_config_file("config.toml") {
-        let toml_data = std::fs::read_to_string(config_filepath)?;
-        toml::from_str(&toml_data)?
-    } else {
-        Config::default()
-    };
+    // Drop-ins of the user config directory replace the system ones with the same name
+    let mut drop_in_filepaths: Vec<PathBuf> = xdg_dirs
+        .list_config_files_once("conf.d")
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|e| e == "toml"))
+        .collect();
+    drop_in_filepaths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    let mut table = toml::Table::new();
+    // Config files of system directories first, so that the user one is merged over them
+    for filepath in xdg_dirs
+        .find_config_files("config.toml")
+        .chain(drop_in_filepaths)
+    {
+        let toml_data = std::fs::read_to_string(&filepath)?;
+        // Parse each file on its own first, so errors point to the right file and line
+        toml::from_str::<Config>(&toml_data)
+            .with_context(|| format!("Invalid config file {}", filepath.display()))?;
+        let file_table: toml::Table = toml::from_str(&toml_data)?;
+        // Values appended to lists are only checked once their key is normalized
+        let mut normalized = toml::Table::new();
+        merge_table(&mut normalized, file_table.clone(), true);
+        toml::Value::Table(normalized)
+            .try_into::<Config>()
+            .with_context(|| format!("Invalid config file {}", filepath.display()))?;
+        merge_table(&mut table, file_table, true);
+    }
+    apply_host_profiles(&mut table, &banner::hostname()?)?;
+    let mut config: Config = toml::Value::Table(table).try_into()?;
+    config.general.apply_env()?;
     Ok(config)
 }
+
+/// Merge config table into another, merging sub tables, and replacing other values, except
+/// arrays set with a key ending with `+`, and arrays of tables (like plugins) if enabled, that are
+/// appended to
+fn merge_table(base: &mut toml::Table, other: toml::Table, append_tables: bool) {
+    for (key, val) in other {
+        let (key, append) = match key.strip_suffix('+') {
+            Some(name) if val.is_array() => (name.to_owned(), true),
+            _ => (key, false),
+        };
+        match (base.get_mut(&key), val) {
+            (Some(toml::Value::Table(base_sub)), toml::Value::Table(sub)) => {
+                merge_table(base_sub, sub, append_tables);
+            }
+            (Some(toml::Value::Array(base_array)), toml::Value::Array(array))
+                if append || (append_tables && array.iter().all(toml::Value::is_table)) =>
+            {
+                base_array.extend(array);
+            }
+            (_, val) => {
+                base.insert(key, val);
+            }
+        }
+    }
+}
+
+/// Remove `[host."pattern"]` profiles from config table, and merge the ones whose glob pattern
+/// matches the hostname over it, in pattern order
+fn apply_host_profiles(table: &mut toml::Table, hostname: &str) -> anyhow::Result<()> {
+    let Some(profiles) = table.remove("host") else {
+        return Ok(());
+    };
+    let toml::Value::Table(profiles) = profiles else {
+        anyhow::bail!("Invalid host profiles, expected a table");
+    };
+    for (pattern, profile) in profiles {
+        let toml::Value::Table(profile) = profile else {
+            anyhow::bail!("Invalid host profile {pattern:?}, expected a table");
+        };
+        // Validate all profiles, not only the ones of this host
+        toml::Value::Table(profile.clone())
+            .try_into::<Config>()
+            .with_context(|| format!("Invalid host profile {pattern:?}"))?;
+        if host_matches(&pattern, hostname) {
+            merge_table(table, profile, false);
+        }
+    }
+    Ok(())
+}
+
+/// Match hostname against a glob pattern, where `*` matches any characters and `?` a single one
+fn host_matches(pattern: &str, hostname: &str) -> bool {
+    let regex = regex::escape(pattern)
+        .replace(r"\*", ".*")
+        .replace(r"\?", ".");
+    regex::Regex::new(&format!("^{regex}$")).is_ok_and(|r| r.is_match(hostname))
+}
+
+/// Generate config file with all keys and their current values, unset keys being commented out
+pub(crate) fn dump(cfg: &Config) -> anyhow::Result<String> {
+    let mut out = String::from(
+        "# motd config file, with current values of all keys\n\
+         # Unset keys are commented out, see README for their meaning\n",
+    );
+    let serde_json::Value::Object(table) = serde_json::to_value(cfg)? else {
+        anyhow::bail!("Config is not a table");
+    };
+    dump_table(&mut out, &[], &table)?;
+    Ok(out)
+}
+
+/// Write keys of a TOML table, followed by its sub tables
+fn dump_table(
+    out: &mut String,
+    path: &[&str],
+    table: &serde_json::Map<String, serde_json::Value>,
+) -> anyhow::Result<()> {
+    let is_table_array = |v: &serde_json::Value| {
+        v.as_array()
+            .is_some_and(|a| !a.is_empty() && a.iter().all(serde_json::Value::is_object))
+    };
+    for (key, val) in table {
+        if val.is_null() {
+            writeln!(out, "# {} =", dump_key(key))?;
+        } else if !val.is_object() && !is_table_array(val) {
+            writeln!(out, "{} = {val}", dump_key(key))?;
+        }
+    }
+    for (key, val) in table {
+        let sub_path: Vec<&str> = path.iter().copied().chain([key.as_str()]).collect();
+        let header = sub_path
+            .iter()
+            .map(|k| dump_key(k))
+            .collect::<Vec<_>>()
+            .join(".");
+        if let serde_json::Value::Object(sub_table) = val {
+            writeln!(out, "\n[{header}]")?;
+            dump_table(out, &sub_path, sub_table)?;
+        } else if is_table_array(val) {
+            for sub_table in val
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_object())
+            {
+                writeln!(out, "\n[[{header}]]")?;
+                dump_table(out, &sub_path, sub_table)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Format TOML key, quoted if it is not a bare key
+fn dump_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || (b == b'_') || (b == b'-'))
+    {
+        key.to_owned()
+    } else {
+        serde_json::Value::from(key).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump() {
+        let cfg: Config = toml::from_str(
+            "[general]\nsections = [\"l\", \"m\"]\n\n\
+             [[plugins]]\nname = \"raid\"\ntitle = \"RAID\"\ncommand = \"true\"\n\n\
+             [display.max_lines]\nfs = 3\n",
+        )
+        .unwrap();
+        let dumped = dump(&cfg).unwrap();
+        assert!(dumped.contains("\n[general]\nsections = [\"l\",\"m\"]\n"));
+        assert!(dumped.contains("\n[[plugins]]\nname = \"raid\"\n"));
+        assert!(dumped.contains("\n# columns =\n"));
+        let reparsed: Config = toml::from_str(&dumped).unwrap();
+        assert_eq!(dump(&reparsed).unwrap(), dumped);
+    }
+
+    #[test]
+    fn test_merge_table() {
+        let mut base: toml::Table = toml::from_str(
+            "quiet = false\n\
+             [fs]\nmount_type_blacklist = [\"^tmpfs$\"]\nusage_warning = 80.0\n",
+        )
+        .unwrap();
+        let drop_in: toml::Table = toml::from_str(
+            "quiet = true\n\
+             [fs]\nmount_type_blacklist = [\"^squashfs$\"]\n\
+             [[plugins]]\nname = \"raid\"\ntitle = \"RAID\"\ncommand = \"true\"\n",
+        )
+        .unwrap();
+        merge_table(&mut base, drop_in, true);
+        let append_drop_in: toml::Table = toml::from_str(
+            "[fs]\n\"mount_type_blacklist+\" = [\"^overlay$\"]\n\
+             [[plugins]]\nname = \"smart\"\ntitle = \"SMART\"\ncommand = \"true\"\n",
+        )
+        .unwrap();
+        merge_table(&mut base, append_drop_in, true);
+        let cfg: Config = toml::Value::Table(base).try_into().unwrap();
+        assert!(cfg.quiet);
+        assert_eq!(
+            cfg.fs
+                .mount_type_blacklist
+                .iter()
+                .map(regex::Regex::as_str)
+                .collect::<Vec<_>>(),
+            ["^squashfs$", "^overlay$"]
+        );
+        assert!((cfg.fs.usage_warning - 80.0).abs() < f32::EPSILON);
+        assert_eq!(
+            cfg.plugins
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>(),
+            ["raid", "smart"]
+        );
+    }
+
+    #[test]
+    fn test_apply_host_profiles() {
+        let parse = |toml_data: &str, hostname: &str| -> anyhow::Result<Config> {
+            let mut table: toml::Table = toml::from_str(toml_data)?;
+            apply_host_profiles(&mut table, hostname)?;
+            Ok(toml::Value::Table(table).try_into()?)
+        };
+        let toml_data = "[general]\nsections = [\"l\", \"m\"]\n\
+                         [fs]\nusage_warning = 70.0\n\
+                         [host.\"nas*\"]\ngeneral.sections = [\"f\"]\nfs.usage_warning = 95.0\n\
+                         [host.\"web-?\"]\nquiet = true\n";
+        let nas_cfg = parse(toml_data, "nas.lan").unwrap();
+        assert_eq!(nas_cfg.general.sections.unwrap(), ["f"]);
+        assert!((nas_cfg.fs.usage_warning - 95.0).abs() < f32::EPSILON);
+        assert!(!nas_cfg.quiet);
+        let laptop_cfg = parse(toml_data, "laptop").unwrap();
+        assert_eq!(laptop_cfg.general.sections.unwrap(), ["l", "m"]);
+        assert!(parse("[host.\"web-*\"]\nquiet = \"yes\"\n", "laptop").is_err());
+    }
+
+    #[test]
+    fn test_host_matches() {
+        assert!(host_matches("web-*", "web-01.example.com"));
+        assert!(host_matches("web-?", "web-1"));
+        assert!(!host_matches("web-?", "web-10"));
+        assert!(!host_matches("web.lan", "webXlan"));
+    }
+
+    #[test]
+    fn test_apply_vars() {
+        let mut general = GeneralConfig {
+            columns: Some(80),
+            ..GeneralConfig::default()
+        };
+        general
+            .apply_vars(|name| match name {
+                "MOTD_SECTIONS" => Some("l, m,,f".to_owned()),
+                "MOTD_COLOR" => Some("never".to_owned()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(general.sections.unwrap(), ["l", "m", "f"]);
+        assert_eq!(general.columns, Some(80));
+        assert_eq!(general.color, Some(ColorMode::Never));
+        assert_eq!(general.format, None);
+
+        assert!(GeneralConfig::default()
+            .apply_vars(|name| (name == "MOTD_FORMAT").then(|| "xml".to_owned()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_dump_key() {
+        assert_eq!(dump_key("usage_warning"), "usage_warning");
+        assert_eq!(dump_key("eth0.100"), "\"eth0.100\"");
+    }
+}
@@ -0,0 +1,65 @@
+//! CSV output of metrics
+
+use std::fmt;
+
+use crate::prometheus;
+
+/// Header row
+const HEADER: &str = "metric,labels,value,timestamp";
+
+/// Write header row
+pub(crate) fn write_header(f: &mut dyn fmt::Write) -> fmt::Result {
+    writeln!(f, "{HEADER}")
+}
+
+/// Write a row for each sample of metrics in Prometheus text format
+pub(crate) fn write_samples(f: &mut dyn fmt::Write, metrics: &str, ts: u64) -> fmt::Result {
+    for (key, value) in prometheus::parse_samples(metrics) {
+        write_row(f, key, value, ts)?;
+    }
+    Ok(())
+}
+
+/// Write a row for a sample, splitting metric name and labels
+pub(crate) fn write_row(f: &mut dyn fmt::Write, key: &str, value: f64, ts: u64) -> fmt::Result {
+    let (name, labels) = match key.split_once('{') {
+        Some((name, labels)) => (name, labels.strip_suffix('}').unwrap_or(labels)),
+        None => (key, ""),
+    };
+    writeln!(
+        f,
+        "{},{},{value},{ts}",
+        escape_field(name),
+        escape_field(labels)
+    )
+}
+
+/// Quote field if needed, as in RFC 4180
+fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_samples() {
+        let mut s = String::new();
+        write_header(&mut s).unwrap();
+        write_samples(
+            &mut s,
+            "# HELP motd_tasks Task count\n# TYPE motd_tasks gauge\nmotd_tasks 12\nmotd_load_average{period=\"1m\",x=\"y\"} 0.5\n",
+            1000,
+        )
+        .unwrap();
+        assert_eq!(
+            s,
+            "metric,labels,value,timestamp\nmotd_tasks,,12,1000\nmotd_load_average,\"period=\"\"1m\"\",x=\"\"y\"\"\",0.5,1000\n"
+        );
+    }
+}
@@ -0,0 +1,572 @@
+//! Minimal D-Bus client, to call methods without depending on command line tools
+
+use std::{
+    env,
+    ffi::OsStr,
+    io::{Read as _, Write as _},
+    os::{
+        linux::net::SocketAddrExt as _,
+        unix::{
+            ffi::OsStrExt as _,
+            net::{SocketAddr, UnixStream},
+        },
+    },
+    path::PathBuf,
+    time::Duration,
+};
+
+/// Timeout of socket reads and writes
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Message type of method calls
+const MSG_METHOD_CALL: u8 = 1;
+
+/// Message type of method returns
+const MSG_METHOD_RETURN: u8 = 2;
+
+/// Message type of errors
+const MSG_ERROR: u8 = 3;
+
+/// Header field codes
+const FIELD_PATH: u8 = 1;
+const FIELD_INTERFACE: u8 = 2;
+const FIELD_MEMBER: u8 = 3;
+const FIELD_ERROR_NAME: u8 = 4;
+const FIELD_REPLY_SERIAL: u8 = 5;
+const FIELD_DESTINATION: u8 = 6;
+const FIELD_SIGNATURE: u8 = 8;
+
+/// Bus name and interface of the message bus itself
+const BUS_NAME: &str = "org.freedesktop.DBus";
+
+/// Object path of the message bus itself
+const BUS_PATH: &str = "/org/freedesktop/DBus";
+
+/// Size of message header, before header fields
+const HEADER_FIXED_LEN: usize = 16;
+
+/// Message bus
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Bus {
+    /// System wide bus
+    System,
+    /// Bus of the current user session
+    Session,
+}
+
+impl Bus {
+    /// Get bus socket address from environment, or default location
+    fn address(self) -> anyhow::Result<SocketAddr> {
+        let (var, default) = match self {
+            Self::System => (
+                "DBUS_SYSTEM_BUS_ADDRESS",
+                Some(PathBuf::from("/run/dbus/system_bus_socket")),
+            ),
+            Self::Session => (
+                "DBUS_SESSION_BUS_ADDRESS",
+                env::var_os("XDG_RUNTIME_DIR").map(|d| PathBuf::from(d).join("bus")),
+            ),
+        };
+        if let Ok(address) = env::var(var) {
+            return parse_address(&address);
+        }
+        let path = default.ok_or_else(|| anyhow::anyhow!("No D-Bus session bus address"))?;
+        Ok(SocketAddr::from_pathname(path)?)
+    }
+}
+
+/// Parse the first supported address of a D-Bus address list
+fn parse_address(address: &str) -> anyhow::Result<SocketAddr> {
+    for transport in address.split(';') {
+        let Some(params) = transport.strip_prefix("unix:") else {
+            continue;
+        };
+        for param in params.split(',') {
+            if let Some(path) = param.strip_prefix("path=") {
+                return Ok(SocketAddr::from_pathname(OsStr::from_bytes(&unescape(
+                    path,
+                )?))?);
+            }
+            if let Some(name) = param.strip_prefix("abstract=") {
+                return Ok(SocketAddr::from_abstract_name(unescape(name)?)?);
+            }
+        }
+    }
+    anyhow::bail!("No supported D-Bus address in {address:?}")
+}
+
+/// Unescape percent encoded bytes of an address value
+fn unescape(val: &str) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(val.len());
+    let mut iter = val.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex: Vec<u8> = iter.by_ref().take(2).collect();
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex)?, 16)?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Message serializer, aligning values relative to the message start
+#[derive(Default)]
+struct Writer {
+    /// Serialized bytes
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    /// Pad with zeros to alignment
+    fn align(&mut self, alignment: usize) {
+        self.buf
+            .resize(self.buf.len().next_multiple_of(alignment), 0);
+    }
+
+    /// Write byte
+    fn byte(&mut self, val: u8) {
+        self.buf.push(val);
+    }
+
+    /// Write 32-bit unsigned integer
+    fn u32(&mut self, val: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&val.to_ne_bytes());
+    }
+
+    /// Write string or object path
+    fn string(&mut self, val: &str) {
+        self.u32(val.len() as u32);
+        self.buf.extend_from_slice(val.as_bytes());
+        self.buf.push(0);
+    }
+
+    /// Write type signature
+    fn signature(&mut self, val: &str) {
+        self.buf.push(val.len() as u8);
+        self.buf.extend_from_slice(val.as_bytes());
+        self.buf.push(0);
+    }
+
+    /// Write header field with a string value
+    fn field(&mut self, code: u8, signature: &str, val: &str) {
+        self.align(8);
+        self.byte(code);
+        self.signature(signature);
+        if signature == "g" {
+            self.signature(val);
+        } else {
+            self.string(val);
+        }
+    }
+}
+
+/// Message deserializer, aligning values relative to the message start
+pub(crate) struct Reader<'a> {
+    /// Serialized bytes
+    buf: &'a [u8],
+    /// Current offset
+    pos: usize,
+    /// Byte order of the message
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    /// Create reader over message bytes
+    fn new(buf: &'a [u8], big_endian: bool) -> Self {
+        Self {
+            buf,
+            pos: 0,
+            big_endian,
+        }
+    }
+
+    /// Get next bytes
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        let bytes = self
+            .buf
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| anyhow::anyhow!("Truncated D-Bus message"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Skip padding to alignment
+    pub(crate) fn align(&mut self, alignment: usize) {
+        self.pos = self.pos.next_multiple_of(alignment);
+    }
+
+    /// Read byte
+    fn byte(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Read 32-bit unsigned integer
+    pub(crate) fn u32(&mut self) -> anyhow::Result<u32> {
+        self.align(4);
+        let bytes = self.take(4)?.try_into()?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    /// Read string or object path
+    pub(crate) fn string(&mut self) -> anyhow::Result<&'a str> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len + 1)?;
+        Ok(std::str::from_utf8(bytes.get(..len).unwrap_or_default())?)
+    }
+
+    /// Read 64-bit unsigned integer
+    pub(crate) fn u64(&mut self) -> anyhow::Result<u64> {
+        self.align(8);
+        let bytes = self.take(8)?.try_into()?;
+        Ok(if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+    }
+
+    /// Read 32-bit signed integer
+    pub(crate) fn i32(&mut self) -> anyhow::Result<i32> {
+        Ok(self.u32()?.cast_signed())
+    }
+
+    /// Read type signature, also found at the start of variants
+    pub(crate) fn signature(&mut self) -> anyhow::Result<&'a str> {
+        let len = usize::from(self.byte()?);
+        let bytes = self.take(len + 1)?;
+        Ok(std::str::from_utf8(bytes.get(..len).unwrap_or_default())?)
+    }
+
+    /// Read array, calling a function to read each element
+    pub(crate) fn array<T, F>(&mut self, alignment: usize, mut read: F) -> anyhow::Result<Vec<T>>
+    where
+        F: FnMut(&mut Self) -> anyhow::Result<T>,
+    {
+        let len = self.u32()? as usize;
+        self.align(alignment);
+        let end = self.pos + len;
+        let mut vals = Vec::new();
+        while self.pos < end {
+            vals.push(read(self)?);
+        }
+        anyhow::ensure!(self.pos == end, "Invalid D-Bus array length");
+        Ok(vals)
+    }
+}
+
+/// Parsed message header
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Header {
+    /// Message type
+    msg_type: u8,
+    /// Serial of the call this message replies to
+    reply_serial: Option<u32>,
+    /// Error name, for error messages
+    error_name: Option<String>,
+    /// Body signature
+    signature: String,
+}
+
+/// Parse header fields
+fn parse_header(reader: &mut Reader) -> anyhow::Result<Header> {
+    reader.pos = 1;
+    let mut header = Header {
+        msg_type: reader.byte()?,
+        ..Header::default()
+    };
+    reader.pos = HEADER_FIXED_LEN - 4;
+    reader.array(8, |r| {
+        r.align(8);
+        let code = r.byte()?;
+        match (code, r.signature()?) {
+            (FIELD_REPLY_SERIAL, "u") => header.reply_serial = Some(r.u32()?),
+            (FIELD_ERROR_NAME, "s") => header.error_name = Some(r.string()?.to_owned()),
+            (FIELD_SIGNATURE, "g") => r.signature()?.clone_into(&mut header.signature),
+            (_, "s" | "o") => {
+                r.string()?;
+            }
+            (_, "g") => {
+                r.signature()?;
+            }
+            (_, "u") => {
+                r.u32()?;
+            }
+            (_, sig) => anyhow::bail!("Unexpected D-Bus header field signature {sig:?}"),
+        }
+        Ok(())
+    })?;
+    Ok(header)
+}
+
+/// Method call arguments
+pub(crate) struct MethodCall<'a> {
+    /// Bus name of the destination
+    pub destination: &'a str,
+    /// Object path
+    pub path: &'a str,
+    /// Interface name
+    pub interface: &'a str,
+    /// Method name
+    pub member: &'a str,
+    /// String arguments
+    pub args: &'a [&'a str],
+}
+
+impl MethodCall<'_> {
+    /// Serialize call message
+    fn serialize(&self, serial: u32) -> Vec<u8> {
+        let mut body = Writer::default();
+        for arg in self.args {
+            body.string(arg);
+        }
+
+        let mut w = Writer::default();
+        w.byte(if cfg!(target_endian = "big") {
+            b'B'
+        } else {
+            b'l'
+        });
+        w.byte(MSG_METHOD_CALL);
+        w.byte(0);
+        w.byte(1);
+        w.u32(body.buf.len() as u32);
+        w.u32(serial);
+        // Header fields array length, set once fields are written
+        w.u32(0);
+        w.field(FIELD_PATH, "o", self.path);
+        w.field(FIELD_INTERFACE, "s", self.interface);
+        w.field(FIELD_MEMBER, "s", self.member);
+        w.field(FIELD_DESTINATION, "s", self.destination);
+        if !self.args.is_empty() {
+            w.field(FIELD_SIGNATURE, "g", &"s".repeat(self.args.len()));
+        }
+        let fields_len = (w.buf.len() - HEADER_FIXED_LEN) as u32;
+        w.buf
+            .get_mut(HEADER_FIXED_LEN - 4..HEADER_FIXED_LEN)
+            .unwrap_or_default()
+            .copy_from_slice(&fields_len.to_ne_bytes());
+        w.align(8);
+        w.buf.extend(body.buf);
+        w.buf
+    }
+}
+
+/// Connection to a message bus
+pub(crate) struct Connection {
+    /// Bus socket
+    stream: UnixStream,
+    /// Serial of the last sent message
+    serial: u32,
+}
+
+impl Connection {
+    /// Connect and authenticate to bus
+    pub(crate) fn open(bus: Bus) -> anyhow::Result<Self> {
+        let stream = UnixStream::connect_addr(&bus.address()?)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let mut conn = Self { stream, serial: 0 };
+        conn.authenticate()?;
+        conn.call(&MethodCall {
+            destination: BUS_NAME,
+            path: BUS_PATH,
+            interface: BUS_NAME,
+            member: "Hello",
+            args: &[],
+        })?;
+        Ok(conn)
+    }
+
+    /// Authenticate with the user id of the process
+    fn authenticate(&mut self) -> anyhow::Result<()> {
+        // SAFETY: libc call
+        let uid = unsafe { libc::getuid() };
+        let uid_hex = uid
+            .to_string()
+            .bytes()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .concat();
+        write!(self.stream, "\0AUTH EXTERNAL {uid_hex}\r\n")?;
+        // Read byte by byte, to not consume anything after the line
+        let mut line = Vec::new();
+        let mut byte = [0; 1];
+        while !line.ends_with(b"\r\n") {
+            self.stream.read_exact(&mut byte)?;
+            line.extend_from_slice(&byte);
+        }
+        anyhow::ensure!(
+            line.starts_with(b"OK "),
+            "D-Bus authentication failed: {}",
+            String::from_utf8_lossy(&line).trim()
+        );
+        self.stream.write_all(b"BEGIN\r\n")?;
+        Ok(())
+    }
+
+    /// Call method, and get its reply
+    pub(crate) fn call(&mut self, call: &MethodCall) -> anyhow::Result<Reply> {
+        self.serial += 1;
+        self.stream.write_all(&call.serialize(self.serial))?;
+        loop {
+            let (msg, body_offset) = self.read_message()?;
+            let big_endian = msg.first() == Some(&b'B');
+            let header = parse_header(&mut Reader::new(&msg, big_endian))?;
+            if header.reply_serial != Some(self.serial) {
+                // Signal, like NameAcquired after Hello
+                continue;
+            }
+            let body = msg.get(body_offset..).unwrap_or_default().to_vec();
+            match header.msg_type {
+                MSG_METHOD_RETURN => {
+                    return Ok(Reply {
+                        body,
+                        big_endian,
+                        signature: header.signature,
+                    });
+                }
+                MSG_ERROR => {
+                    let message = if header.signature.starts_with('s') {
+                        Reader::new(&body, big_endian).string()?.to_owned()
+                    } else {
+                        String::new()
+                    };
+                    anyhow::bail!(
+                        "{} failed: {} {message}",
+                        call.member,
+                        header.error_name.unwrap_or_default()
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Check if a bus name is owned, like a service running
+    pub(crate) fn name_has_owner(&mut self, name: &str) -> anyhow::Result<bool> {
+        let reply = self.call(&MethodCall {
+            destination: BUS_NAME,
+            path: BUS_PATH,
+            interface: BUS_NAME,
+            member: "NameHasOwner",
+            args: &[name],
+        })?;
+        anyhow::ensure!(
+            reply.signature == "b",
+            "Unexpected NameHasOwner reply signature {:?}",
+            reply.signature
+        );
+        Ok(reply.reader().u32()? != 0)
+    }
+
+    /// Get object property, as a reply with a single variant
+    pub(crate) fn get_property(
+        &mut self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        property: &str,
+    ) -> anyhow::Result<Reply> {
+        let reply = self.call(&MethodCall {
+            destination,
+            path,
+            interface: "org.freedesktop.DBus.Properties",
+            member: "Get",
+            args: &[interface, property],
+        })?;
+        anyhow::ensure!(
+            reply.signature == "v",
+            "Unexpected property reply signature {:?}",
+            reply.signature
+        );
+        Ok(reply)
+    }
+
+    /// Read a whole message, and get the offset of its body
+    fn read_message(&mut self) -> anyhow::Result<(Vec<u8>, usize)> {
+        let mut msg = vec![0; HEADER_FIXED_LEN];
+        self.stream.read_exact(&mut msg)?;
+        let mut reader = Reader::new(&msg, msg.first() == Some(&b'B'));
+        reader.pos = 4;
+        let body_len = reader.u32()? as usize;
+        reader.pos = HEADER_FIXED_LEN - 4;
+        let fields_len = reader.u32()? as usize;
+        let body_offset = (HEADER_FIXED_LEN + fields_len).next_multiple_of(8);
+        msg.resize(body_offset + body_len, 0);
+        self.stream
+            .read_exact(msg.get_mut(HEADER_FIXED_LEN..).unwrap_or_default())?;
+        Ok((msg, body_offset))
+    }
+}
+
+/// Method call reply
+pub(crate) struct Reply {
+    /// Serialized body
+    body: Vec<u8>,
+    /// Byte order of the body
+    big_endian: bool,
+    /// Body signature
+    pub signature: String,
+}
+
+impl Reply {
+    /// Get reader of the body
+    pub(crate) fn reader(&self) -> Reader<'_> {
+        Reader::new(&self.body, self.big_endian)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(
+            parse_address("unix:path=/run/user/1000/bus")
+                .unwrap()
+                .as_pathname(),
+            Some(PathBuf::from("/run/user/1000/bus").as_path())
+        );
+        assert_eq!(
+            parse_address("tcp:host=localhost;unix:abstract=/tmp/dbus%2dX,guid=1234")
+                .unwrap()
+                .as_abstract_name(),
+            Some(b"/tmp/dbus-X".as_slice())
+        );
+        assert!(parse_address("tcp:host=localhost").is_err());
+    }
+
+    #[test]
+    fn test_serialize_parse() {
+        let msg = MethodCall {
+            destination: "org.freedesktop.systemd1",
+            path: "/org/freedesktop/systemd1/unit/foo_2eservice",
+            interface: "org.freedesktop.DBus.Properties",
+            member: "Get",
+            args: &["org.freedesktop.systemd1.Unit", "ActiveState"],
+        }
+        .serialize(7);
+        let big_endian = cfg!(target_endian = "big");
+        let header = parse_header(&mut Reader::new(&msg, big_endian)).unwrap();
+        assert_eq!(
+            header,
+            Header {
+                msg_type: MSG_METHOD_CALL,
+                reply_serial: None,
+                error_name: None,
+                signature: "ss".to_owned(),
+            }
+        );
+        let body = msg.get(msg.len() - 52..).unwrap();
+        let mut reader = Reader::new(body, big_endian);
+        assert_eq!(reader.string().unwrap(), "org.freedesktop.systemd1.Unit");
+        assert_eq!(reader.string().unwrap(), "ActiveState");
+    }
+}
@@ -0,0 +1,283 @@
+//! Native plugins, loaded from shared libraries exposing a C ABI
+
+use std::{
+    ffi::{c_char, c_void, CStr, CString},
+    fmt, mem,
+    os::unix::ffi::OsStrExt as _,
+    path::Path,
+    ptr::NonNull,
+};
+
+use crate::{fmt::strip_ansi, module::Module, plugin, style::Severity};
+
+/// Version of the plugin interface, checked at load time
+const ABI_VERSION: u32 = 2;
+
+/// Symbol of the function returning the plugin interface
+const REGISTER_SYMBOL: &CStr = c"motd_plugin_register";
+
+/// Plugin interface, as returned by the registration function
+#[repr(C)]
+struct PluginInterface {
+    /// Must be equal to `ABI_VERSION`
+    abi_version: u32,
+    /// Fetch section data, as an opaque pointer passed to other functions, or null on error
+    fetch: Option<unsafe extern "C" fn() -> *mut c_void>,
+    /// Render data as text, that may contain ANSI escape sequences
+    render: Option<unsafe extern "C" fn(*const c_void) -> *mut c_char>,
+    /// Get data severity: 0 for OK, 1 for warning, 2 for critical
+    severity: Option<unsafe extern "C" fn(*const c_void) -> u32>,
+    /// Get single line summary, optional
+    summary: Option<unsafe extern "C" fn(*const c_void) -> *mut c_char>,
+    /// Get data as a JSON value for structured output, optional
+    json: Option<unsafe extern "C" fn(*const c_void) -> *mut c_char>,
+    /// Free string returned by other functions
+    free_string: Option<unsafe extern "C" fn(*mut c_char)>,
+    /// Free data returned by fetch
+    free_data: Option<unsafe extern "C" fn(*mut c_void)>,
+}
+
+/// Plugin registration function
+type RegisterFn = unsafe extern "C" fn() -> *const PluginInterface;
+
+/// Plugin functions, with mandatory ones checked at load time
+#[derive(Clone, Copy)]
+struct Functions {
+    /// See [`PluginInterface::fetch`]
+    fetch: unsafe extern "C" fn() -> *mut c_void,
+    /// See [`PluginInterface::render`]
+    render: unsafe extern "C" fn(*const c_void) -> *mut c_char,
+    /// See [`PluginInterface::severity`]
+    severity: unsafe extern "C" fn(*const c_void) -> u32,
+    /// See [`PluginInterface::summary`]
+    summary: Option<unsafe extern "C" fn(*const c_void) -> *mut c_char>,
+    /// See [`PluginInterface::json`]
+    json: Option<unsafe extern "C" fn(*const c_void) -> *mut c_char>,
+    /// See [`PluginInterface::free_string`]
+    free_string: unsafe extern "C" fn(*mut c_char),
+    /// See [`PluginInterface::free_data`]
+    free_data: unsafe extern "C" fn(*mut c_void),
+}
+
+/// Section data of a native plugin, owned by the plugin until dropped
+pub(crate) struct NativeData {
+    /// Plugin name
+    name: String,
+    /// Plugin functions
+    functions: Functions,
+    /// Opaque data returned by fetch
+    data: NonNull<c_void>,
+}
+
+// SAFETY: the ABI contract requires plugin functions to be thread safe, since sections are
+// fetched and rendered in parallel threads
+unsafe impl Send for NativeData {}
+// SAFETY: see above, data is only passed to plugin functions as a const pointer
+unsafe impl Sync for NativeData {}
+
+/// Fetch section data of a plugin library
+pub(crate) fn fetch(path: &Path, name: &str) -> anyhow::Result<NativeData> {
+    let functions = load(path)?;
+    // SAFETY: plugin function, following the ABI contract
+    let data = unsafe { (functions.fetch)() };
+    let data = NonNull::new(data).ok_or_else(|| anyhow::anyhow!("Plugin failed to fetch data"))?;
+    Ok(NativeData {
+        name: name.to_owned(),
+        functions,
+        data,
+    })
+}
+
+impl NativeData {
+    /// Call plugin function returning a string, and take it
+    fn string(&self, function: unsafe extern "C" fn(*const c_void) -> *mut c_char) -> String {
+        // SAFETY: plugin function, data was returned by fetch and is not freed yet
+        let s = unsafe { function(self.data.as_ptr()) };
+        if s.is_null() {
+            return String::new();
+        }
+        // SAFETY: non null string returned by plugin, valid until freed
+        let owned = unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned();
+        // SAFETY: plugin function, string is not used afterwards
+        unsafe {
+            (self.functions.free_string)(s);
+        }
+        owned
+    }
+}
+
+impl Drop for NativeData {
+    fn drop(&mut self) {
+        // SAFETY: plugin function, data is not used afterwards
+        unsafe {
+            (self.functions.free_data)(self.data.as_ptr());
+        }
+    }
+}
+
+impl Module for NativeData {
+    /// Keep the whole section if it is above warning threshold
+    fn retain_alerts(&mut self) -> bool {
+        self.severity() != Severity::Ok
+    }
+
+    /// Severity reported by the plugin
+    fn severity(&self) -> Severity {
+        // SAFETY: plugin function, data was returned by fetch and is not freed yet
+        match unsafe { (self.functions.severity)(self.data.as_ptr()) } {
+            0 => Severity::Ok,
+            1 => Severity::Warning,
+            _ => Severity::Critical,
+        }
+    }
+
+    /// Summary reported by the plugin, or first rendered line if not OK
+    fn compact(&self) -> String {
+        if let Some(summary) = self.functions.summary {
+            return self.string(summary);
+        }
+        if self.severity() == Severity::Ok {
+            return String::new();
+        }
+        self.to_string()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_owned()
+    }
+
+    /// Write plugin severity as Prometheus metric
+    fn write_prometheus(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        plugin::write_severity_metric(f, &self.name, self.severity())
+    }
+}
+
+impl fmt::Display for NativeData {
+    /// Output text rendered by the plugin
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = self.string(self.functions.render);
+        if text.is_empty() || text.ends_with('\n') {
+            write!(f, "{text}")
+        } else {
+            writeln!(f, "{text}")
+        }
+    }
+}
+
+impl serde::Serialize for NativeData {
+    /// Serialize JSON value from the plugin, or rendered text and severity if it has none
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(json) = self.functions.json {
+            let value: serde_json::Value =
+                serde_json::from_str(&self.string(json)).map_err(serde::ser::Error::custom)?;
+            return value.serialize(serializer);
+        }
+        serde_json::json!({
+            "text": strip_ansi(&self.to_string()),
+            "severity": self.severity(),
+        })
+        .serialize(serializer)
+    }
+}
+
+/// Load plugin library if not already loaded, and get its functions
+fn load(path: &Path) -> anyhow::Result<Functions> {
+    let path_c = CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: libc call, library is never unloaded so its symbols and data remain valid, and
+    // loading an already loaded library returns the same handle
+    let handle = unsafe { libc::dlopen(path_c.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+    anyhow::ensure!(
+        !handle.is_null(),
+        "Failed to load plugin library: {}",
+        dl_error()
+    );
+    // SAFETY: libc call, handle is valid
+    let symbol = unsafe { libc::dlsym(handle, REGISTER_SYMBOL.as_ptr()) };
+    anyhow::ensure!(
+        !symbol.is_null(),
+        "Plugin library has no {} function",
+        REGISTER_SYMBOL.to_string_lossy()
+    );
+    // SAFETY: the symbol is the registration function, following the ABI contract
+    let register = unsafe { mem::transmute::<*mut c_void, RegisterFn>(symbol) };
+    // SAFETY: plugin function, returning a pointer to static data or null
+    let interface = unsafe { register().as_ref() }
+        .ok_or_else(|| anyhow::anyhow!("Plugin registration failed"))?;
+    anyhow::ensure!(
+        interface.abi_version == ABI_VERSION,
+        "Unsupported plugin ABI version {}, expected {ABI_VERSION}",
+        interface.abi_version
+    );
+    let missing = |name| anyhow::anyhow!("Plugin has no {name} function");
+    Ok(Functions {
+        fetch: interface.fetch.ok_or_else(|| missing("fetch"))?,
+        render: interface.render.ok_or_else(|| missing("render"))?,
+        severity: interface.severity.ok_or_else(|| missing("severity"))?,
+        summary: interface.summary,
+        json: interface.json,
+        free_string: interface
+            .free_string
+            .ok_or_else(|| missing("free_string"))?,
+        free_data: interface.free_data.ok_or_else(|| missing("free_data"))?,
+    })
+}
+
+/// Get last dynamic linker error
+fn dl_error() -> String {
+    // SAFETY: libc call
+    let err = unsafe { libc::dlerror() };
+    if err.is_null() {
+        "unknown error".to_owned()
+    } else {
+        // SAFETY: non null error string from libc
+        unsafe { CStr::from_ptr(err) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf, process, process::Command};
+
+    use super::*;
+
+    /// Build fixture plugin library
+    fn build_fixture() -> PathBuf {
+        let dir = env::temp_dir().join(format!("motd-dylib-test-{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let library = dir.join("libraid.so");
+        let status = Command::new("cc")
+            .args(["-shared", "-fPIC", "-o"])
+            .arg(&library)
+            .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/native_plugin.c"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+        library
+    }
+
+    #[test]
+    fn test_fetch() {
+        let library = build_fixture();
+
+        let data = fetch(&library, "raid").unwrap();
+        assert_eq!(data.to_string(), "md0: ok\nmd1: degraded\n");
+        assert_eq!(data.severity(), Severity::Warning);
+        assert_eq!(data.compact(), "1 degraded array");
+        assert_eq!(
+            serde_json::to_string(&data).unwrap(),
+            r#"{"arrays":2,"degraded":1}"#
+        );
+        let mut metrics = String::new();
+        data.write_prometheus(&mut metrics).unwrap();
+        assert!(metrics.ends_with("motd_plugin_raid_severity 1\n"));
+        let mut data: Box<dyn Module> = Box::new(data);
+        assert!(data.retain_alerts());
+        drop(data);
+
+        assert!(fetch(&library.with_file_name("missing.so"), "raid").is_err());
+
+        std::fs::remove_dir_all(library.parent().unwrap()).unwrap();
+    }
+}
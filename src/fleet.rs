@@ -0,0 +1,255 @@
+//! Overview of several hosts, from the JSON data they serve
+
+use std::{fmt, thread};
+
+use crate::{
+    config, curl,
+    fmt::{Align, Table},
+    style::{self, Severity},
+};
+
+/// Condensed status of a host
+#[derive(Debug, Default, PartialEq)]
+struct HostSummary {
+    /// Load average 1 minute
+    load: Option<f64>,
+    /// Memory usage percentage
+    mem_usage: Option<f64>,
+    /// Usage percentage of the fullest filesystem
+    fs_usage: Option<f64>,
+    /// Count of failed Systemd units
+    failed_units: Option<usize>,
+    /// Temperature of the hottest sensor, in Celsius, with its severity
+    max_temp: Option<(u64, Severity)>,
+}
+
+/// Summarize host data, as output by `--format json` or served at `/json`
+fn summarize(data: &serde_json::Value) -> HostSummary {
+    let load = data
+        .pointer("/load/load_avg_1m")
+        .and_then(serde_json::Value::as_f64);
+    let mem_usage = data.get("memory").and_then(|m| {
+        let used = m.get("used")?.as_f64()?;
+        let total = m.get("total")?.as_f64()?;
+        (total > 0.0).then(|| 100.0 * used / total)
+    });
+    let fs_usage = data
+        .pointer("/filesystems/mounts")
+        .and_then(serde_json::Value::as_array)
+        .and_then(|mounts| {
+            mounts
+                .iter()
+                .filter_map(|m| {
+                    let used = m.get("used_bytes")?.as_f64()?;
+                    let reserved = m.get("reserved_bytes")?.as_f64()?;
+                    let total = m.get("total_bytes")?.as_f64()?;
+                    // Usage as seen by unprivileged users, like df does
+                    let usable = total - reserved;
+                    (usable > 0.0).then(|| 100.0 * used / usable)
+                })
+                .max_by(f64::total_cmp)
+        });
+    let failed_units = data.get("systemd_failed_units").map(|u| {
+        ["system", "user"]
+            .iter()
+            .filter_map(|k| u.get(k)?.as_array().map(Vec::len))
+            .sum()
+    });
+    let max_temp = data
+        .pointer("/temperatures/temps")
+        .and_then(serde_json::Value::as_array)
+        .and_then(|temps| {
+            temps
+                .iter()
+                .filter_map(|t| {
+                    let temp = t.get("temp")?.as_u64()?;
+                    let severity = if temp >= t.get("temp_critical")?.as_u64()? {
+                        Severity::Critical
+                    } else if temp >= t.get("temp_warning")?.as_u64()? {
+                        Severity::Warning
+                    } else {
+                        Severity::Ok
+                    };
+                    Some((temp, severity))
+                })
+                .max()
+        });
+    HostSummary {
+        load,
+        mem_usage,
+        fs_usage,
+        failed_units,
+        max_temp,
+    }
+}
+
+/// Get host name from its URL
+fn url_host(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme
+        .split(['/', ':'])
+        .next()
+        .unwrap_or(without_scheme)
+}
+
+/// Fetch JSON data of a host with curl, the URL may contain credentials
+fn fetch_host(url: &str) -> anyhow::Result<serde_json::Value> {
+    let body = curl::request(url, &[], None)?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Fleet table, one host per row
+pub(crate) struct Fleet {
+    /// Host names and summaries, or fetch errors
+    hosts: Vec<(String, Result<HostSummary, String>)>,
+    /// Filesystem usage thresholds
+    fs_usage_thresholds: (f32, f32),
+}
+
+/// Fetch data of all hosts in parallel
+pub(crate) fn fetch(hosts: &[config::FleetHost], fs_cfg: &config::FsConfig) -> Fleet {
+    let hosts = thread::scope(|scope| {
+        let futs: Vec<_> = hosts
+            .iter()
+            .map(|h| scope.spawn(|| fetch_host(&h.url).map(|d| summarize(&d))))
+            .collect();
+        hosts
+            .iter()
+            .zip(futs)
+            .map(|(host, fut)| {
+                let name = host
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| url_host(&host.url).to_owned());
+                let summary = fut
+                    .join()
+                    .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))
+                    .and_then(|r| r)
+                    .map_err(|e| format!("{e:#}"));
+                (name, summary)
+            })
+            .collect()
+    });
+    Fleet {
+        hosts,
+        fs_usage_thresholds: (fs_cfg.usage_warning, fs_cfg.usage_critical),
+    }
+}
+
+/// Format optional value, or a dash if missing
+fn cell<T, F: Fn(T) -> String>(val: Option<T>, format: F) -> String {
+    val.map_or_else(|| "-".to_owned(), format)
+}
+
+impl fmt::Display for Fleet {
+    /// Output aligned table
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = style::theme();
+        let (fs_warning, fs_critical) = self.fs_usage_thresholds;
+        let header = ["Host", "Load", "Mem", "FS", "Failed", "Temp"];
+        let rows: Vec<Vec<(String, Severity)>> = self
+            .hosts
+            .iter()
+            .map(|(name, summary)| {
+                let mut row = vec![(name.clone(), Severity::Ok)];
+                match summary {
+                    Ok(s) => {
+                        let fs_severity = s.fs_usage.map_or(Severity::Ok, |u| {
+                            if u >= f64::from(fs_critical) {
+                                Severity::Critical
+                            } else if u >= f64::from(fs_warning) {
+                                Severity::Warning
+                            } else {
+                                Severity::Ok
+                            }
+                        });
+                        row.extend([
+                            (cell(s.load, |l| format!("{l:.2}")), Severity::Ok),
+                            (cell(s.mem_usage, |u| format!("{u:.0}%")), Severity::Ok),
+                            (cell(s.fs_usage, |u| format!("{u:.0}%")), fs_severity),
+                            (
+                                cell(s.failed_units, |c| c.to_string()),
+                                if s.failed_units.unwrap_or(0) > 0 {
+                                    Severity::Critical
+                                } else {
+                                    Severity::Ok
+                                },
+                            ),
+                            (
+                                cell(s.max_temp, |(t, _)| format!("{t}°C")),
+                                s.max_temp.map_or(Severity::Ok, |(_, sev)| sev),
+                            ),
+                        ]);
+                    }
+                    Err(err) => row.push((err.clone(), Severity::Critical)),
+                }
+                row
+            })
+            .collect();
+
+        let mut table = Table::with_header(&[Align::Left; 6], &header);
+        for row in &rows {
+            table.push(row.iter().map(|(val, _)| val.clone()).collect());
+        }
+        let widths = table.widths();
+        let mut lines = table.padded_rows(&widths).into_iter();
+        if let Some(header_line) = lines.next() {
+            writeln!(f, "{}", header_line.join("  ").trim_end())?;
+        }
+        for (row, cells) in rows.iter().zip(lines) {
+            let line = row
+                .iter()
+                .zip(cells)
+                .map(|((_, severity), padded)| {
+                    if *severity == Severity::Ok {
+                        padded
+                    } else {
+                        theme.style(*severity).paint(padded).to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let data = serde_json::json!({
+            "load": {"load_avg_1m": 0.5, "load_avg_5m": 0.4, "load_avg_15m": 0.3, "task_count": 100},
+            "memory": {"total": 1000, "used": 250},
+            "filesystems": {"mounts": [
+                {"used_bytes": 50, "reserved_bytes": 0, "total_bytes": 100},
+                {"used_bytes": 90, "reserved_bytes": 10, "total_bytes": 110},
+            ]},
+            "systemd_failed_units": {"system": ["a.service"], "user": []},
+            "temperatures": {"temps": [
+                {"temp": 40, "temp_warning": 60, "temp_critical": 80},
+                {"temp": 65, "temp_warning": 60, "temp_critical": 80},
+            ]},
+        });
+        assert_eq!(
+            summarize(&data),
+            HostSummary {
+                load: Some(0.5),
+                mem_usage: Some(25.0),
+                fs_usage: Some(90.0),
+                failed_units: Some(1),
+                max_temp: Some((65, Severity::Warning)),
+            }
+        );
+        assert_eq!(summarize(&serde_json::json!({})), HostSummary::default());
+    }
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(url_host("http://nas.lan:8080/json"), "nas.lan");
+        assert_eq!(url_host("nas.lan/json"), "nas.lan");
+    }
+}
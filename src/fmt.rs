@@ -1,37 +1,584 @@
-/// Format numeric value with K/M/G/T prefix
-pub(crate) fn format_kmgt(val: u64, unit: &str) -> String {
+use std::{cmp, sync::RwLock};
+
+use crate::config::{DisplayConfig, RateUnit, SizeUnits, TitleAlign, TitleCase};
+
+/// Format numeric value with K/M/G/T prefix, right aligned to a minimum width
+fn format_kmgt(val: u64, unit: &str, precision: usize, width: usize) -> String {
     const K: u64 = 1024;
     const M: u64 = K * 1024;
     const G: u64 = M * 1024;
     const T: u64 = G * 1024;
-    if val >= T {
-        format!("{:.1} T{}", val as f32 / T as f32, unit)
+    let s = if val >= T {
+        format!("{:.precision$} T{}", val as f32 / T as f32, unit)
     } else if val >= G {
-        format!("{:.1} G{}", val as f32 / G as f32, unit)
+        format!("{:.precision$} G{}", val as f32 / G as f32, unit)
     } else if val >= M {
-        format!("{:.1} M{}", val as f32 / M as f32, unit)
+        format!("{:.precision$} M{}", val as f32 / M as f32, unit)
     } else if val >= K {
-        format!("{:.1} K{}", val as f32 / K as f32, unit)
+        format!("{:.precision$} K{}", val as f32 / K as f32, unit)
     } else {
         format!("{val} {unit}")
-    }
+    };
+    format!("{s:>width$}")
 }
 
-/// Format numeric value with k/M/G/T prefix
-pub(crate) fn format_kmgt_si(val: u64, unit: &str) -> String {
+/// Format numeric value with k/M/G/T prefix, right aligned to a minimum width
+fn format_kmgt_si(val: u64, unit: &str, precision: usize, width: usize) -> String {
     const K_SI: u64 = 1000;
     const M_SI: u64 = K_SI * 1000;
     const G_SI: u64 = M_SI * 1000;
     const T_SI: u64 = G_SI * 1000;
-    if val >= T_SI {
-        format!("{:.1} T{}", val as f32 / T_SI as f32, unit)
+    let s = if val >= T_SI {
+        format!("{:.precision$} T{}", val as f32 / T_SI as f32, unit)
     } else if val >= G_SI {
-        format!("{:.1} G{}", val as f32 / G_SI as f32, unit)
+        format!("{:.precision$} G{}", val as f32 / G_SI as f32, unit)
     } else if val >= M_SI {
-        format!("{:.1} M{}", val as f32 / M_SI as f32, unit)
+        format!("{:.precision$} M{}", val as f32 / M_SI as f32, unit)
     } else if val >= K_SI {
-        format!("{:.1} k{}", val as f32 / K_SI as f32, unit)
+        format!("{:.precision$} k{}", val as f32 / K_SI as f32, unit)
     } else {
         format!("{val} {unit}")
+    };
+    format!("{s:>width$}")
+}
+
+/// Format exact numeric value with thousands separators, right aligned to a minimum width
+fn format_exact(val: u64, unit: &str, width: usize) -> String {
+    let digits = val.to_string();
+    let mut s = String::with_capacity(digits.len() * 4 / 3 + unit.len() + 1);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            s.push(',');
+        }
+        s.push(c);
+    }
+    let s = format!("{s} {unit}");
+    format!("{s:>width$}")
+}
+
+/// Format size in bytes, with binary or SI prefix and precision from display config, right
+/// aligned to a minimum width, or the configured one if larger
+pub(crate) fn format_size(bytes: u64, width: usize) -> String {
+    let cfg = display();
+    let width = cmp::max(width, cfg.value_width);
+    match cfg.units {
+        SizeUnits::Binary => format_kmgt(bytes, "B", cfg.precision, width),
+        SizeUnits::Si => format_kmgt_si(bytes, "B", cfg.precision, width),
+        SizeUnits::Exact => format_exact(bytes, "B", width),
+    }
+}
+
+/// Format rate in bits per second, as bits with SI prefix, or as bytes like sizes, with precision
+/// from display config, right aligned to a minimum width, or the configured one if larger
+pub(crate) fn format_rate(bps: u64, unit: RateUnit, width: usize) -> String {
+    let cfg = display();
+    let width = cmp::max(width, cfg.value_width);
+    match (unit, cfg.units) {
+        (RateUnit::Bits, SizeUnits::Binary | SizeUnits::Si) => {
+            format_kmgt_si(bps, "b/s", cfg.precision, width)
+        }
+        (RateUnit::Bits, SizeUnits::Exact) => format_exact(bps, "b/s", width),
+        (RateUnit::Bytes, SizeUnits::Binary) => format_kmgt(bps / 8, "B/s", cfg.precision, width),
+        (RateUnit::Bytes, SizeUnits::Si) => format_kmgt_si(bps / 8, "B/s", cfg.precision, width),
+        (RateUnit::Bytes, SizeUnits::Exact) => format_exact(bps / 8, "B/s", width),
+    }
+}
+
+/// Format duration with the largest unit, rounded down
+pub(crate) fn format_duration(secs: u64) -> String {
+    if secs >= 24 * 60 * 60 {
+        format!("{}d", secs / (24 * 60 * 60))
+    } else if secs >= 60 * 60 {
+        format!("{}h", secs / (60 * 60))
+    } else if secs >= 60 {
+        format!("{}min", secs / 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+/// Terminal display width of a character: 0 for combining and zero width characters, 2 for East
+/// Asian wide and fullwidth characters and emojis, 1 otherwise
+fn char_width(c: char) -> usize {
+    match u32::from(c) {
+        0x00..=0x1f
+        | 0x7f..=0x9f
+        | 0x0300..=0x036f
+        | 0x0483..=0x0489
+        | 0x0591..=0x05bd
+        | 0x0610..=0x061a
+        | 0x064b..=0x065f
+        | 0x0e31
+        | 0x0e34..=0x0e3a
+        | 0x0e47..=0x0e4e
+        | 0x1ab0..=0x1aff
+        | 0x1dc0..=0x1dff
+        | 0x200b..=0x200f
+        | 0x20d0..=0x20ff
+        | 0xfe00..=0xfe0f
+        | 0xfe20..=0xfe2f
+        | 0xfeff
+        | 0xe0100..=0xe01ef => 0,
+        0x1100..=0x115f
+        | 0x2e80..=0x303e
+        | 0x3041..=0x33ff
+        | 0x3400..=0x4dbf
+        | 0x4e00..=0x9fff
+        | 0xa000..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f900..=0x1f9ff
+        | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+/// Terminal display width of text without escape sequences, to pad and align it
+pub(crate) fn text_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+
+/// Truncate text to a maximum display width, ending it with an ellipsis if it was truncated
+pub(crate) fn ellipsis(s: &str, max_width: usize) -> String {
+    assert!(max_width >= 1);
+
+    if text_width(s) <= max_width {
+        return s.to_owned();
+    }
+    let mut truncated = String::with_capacity(s.len());
+    let mut width = 0;
+    for c in s.chars() {
+        width += char_width(c);
+        if width > max_width - 1 {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Display width of text, ignoring ANSI escape sequences
+pub(crate) fn ansi_width(s: &str) -> usize {
+    text_width(&strip_ansi(s))
+}
+
+/// Pad text containing ANSI escape sequences with spaces, up to a minimum display width
+pub(crate) fn pad_ansi(s: &str, width: usize) -> String {
+    format!("{s}{}", " ".repeat(width.saturating_sub(ansi_width(s))))
+}
+
+/// Truncate text containing ANSI escape sequences to a maximum display width
+pub(crate) fn truncate_ansi(s: &str, max_width: usize) -> String {
+    slice_ansi(s, 0, max_width)
+}
+
+/// Get display columns `start..end` of text containing ANSI escape sequences, keeping all escape
+/// sequences so that styles are still applied and reset
+pub(crate) fn slice_ansi(s: &str, start: usize, end: usize) -> String {
+    let mut sliced = String::with_capacity(s.len());
+    let mut col = 0;
+    // Whether the last non zero width character was kept, to keep the combining ones following it
+    let mut kept = false;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            sliced.push(c);
+            for seq_char in chars.by_ref() {
+                sliced.push(seq_char);
+                if seq_char.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        let width = char_width(c);
+        if width > 0 {
+            // Wide characters crossing a slice boundary are dropped
+            kept = col >= start && col + width <= end;
+            col += width;
+        }
+        if kept {
+            sliced.push(c);
+        }
+    }
+    sliced
+}
+
+/// Remove ANSI SGR escape sequences (colors and styles)
+pub(crate) fn strip_ansi(s: &str) -> String {
+    let mut stripped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip CSI sequence until its final byte
+            for seq_char in chars.by_ref() {
+                if seq_char.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Table column alignment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Align {
+    Left,
+    Right,
+}
+
+/// Text table, with columns as wide as their widest cell.
+/// Rows with less cells than columns have their last cell spanning the remaining columns, like
+/// error messages, and it does not count for column widths.
+pub(crate) struct Table {
+    /// Alignment of each column
+    aligns: Vec<Align>,
+    /// Header and rows cells, which may contain ANSI escape sequences
+    rows: Vec<Vec<String>>,
+    /// Whether the first row is a header
+    has_header: bool,
+}
+
+impl Table {
+    /// Create empty table without header
+    pub(crate) fn new(aligns: &[Align]) -> Self {
+        Self {
+            aligns: aligns.to_vec(),
+            rows: Vec::new(),
+            has_header: false,
+        }
+    }
+
+    /// Create empty table with a header row
+    pub(crate) fn with_header(aligns: &[Align], header: &[&str]) -> Self {
+        Self {
+            aligns: aligns.to_vec(),
+            rows: vec![header.iter().map(|h| (*h).to_owned()).collect()],
+            has_header: true,
+        }
+    }
+
+    /// Add row
+    pub(crate) fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    /// Whether the table has no rows, besides the header
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rows.len() == usize::from(self.has_header)
+    }
+
+    /// Display width of each column
+    pub(crate) fn widths(&self) -> Vec<usize> {
+        let mut widths = vec![0; self.aligns.len()];
+        for row in &self.rows {
+            let spanning = row.len() < widths.len();
+            for (i, (width, cell)) in widths.iter_mut().zip(row).enumerate() {
+                if !(spanning && i + 1 == row.len()) {
+                    *width = (*width).max(ansi_width(cell));
+                }
+            }
+        }
+        widths
+    }
+
+    /// Display width of each column, shrinking `column` so that the table fits in `max_width`,
+    /// with `gap_width` columns between each column, but not below 1
+    pub(crate) fn fit_widths(
+        &self,
+        column: usize,
+        max_width: usize,
+        gap_width: usize,
+    ) -> Vec<usize> {
+        let mut widths = self.widths();
+        let total = widths.iter().sum::<usize>() + gap_width * widths.len().saturating_sub(1);
+        if let Some(width) = widths.get_mut(column) {
+            *width = width.saturating_sub(total.saturating_sub(max_width)).max(1);
+        }
+        widths
+    }
+
+    /// Cells of each row, including the header, aligned to column widths, and truncated with an
+    /// ellipsis if larger
+    pub(crate) fn padded_rows(&self, widths: &[usize]) -> Vec<Vec<String>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let spanning = row.len() < widths.len();
+                row.iter()
+                    .zip(widths.iter().zip(&self.aligns))
+                    .enumerate()
+                    .map(|(i, (cell, (&width, align)))| {
+                        let cell_width = ansi_width(cell);
+                        if spanning && i + 1 == row.len() {
+                            cell.clone()
+                        } else if cell_width > width {
+                            ellipsis(&strip_ansi(cell), width)
+                        } else {
+                            let pad = " ".repeat(width - cell_width);
+                            match align {
+                                Align::Left => format!("{cell}{pad}"),
+                                Align::Right => format!("{pad}{cell}"),
+                            }
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Global section header display config, set at startup and replaced on reload
+static DISPLAY: RwLock<Option<DisplayConfig>> = RwLock::new(None);
+
+/// Set global section header display config
+pub(crate) fn set_display(cfg: DisplayConfig) {
+    *DISPLAY.write().unwrap() = Some(cfg);
+}
+
+/// Get global display config
+pub(crate) fn display() -> DisplayConfig {
+    DISPLAY.read().unwrap().unwrap_or_default()
+}
+
+/// Left aligned partial block character for a fraction of a bar cell, in eighths, if bars are
+/// made of full blocks and blanks, and the fraction is not rounded to an empty or full cell
+pub(crate) fn partial_bar_char(frac: f32) -> Option<char> {
+    const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+    let bar = display().bar;
+    if bar.fill != '█' || bar.empty != ' ' {
+        return None;
+    }
+    let eighths = (frac * 8.0).round() as usize;
+    eighths.checked_sub(1).and_then(|i| EIGHTHS.get(i)).copied()
+}
+
+/// Build section header line using global display config, with a suffix after the title
+pub(crate) fn title_line(title: &str, prefix: &str, suffix: &str, columns: usize) -> String {
+    header_line(display(), &[(title, prefix, suffix)], columns)
+}
+
+/// Build header line of sections sharing it using global display config, joining their titles
+pub(crate) fn merged_title_line(titles: &[(&str, &str, &str)], columns: usize) -> String {
+    header_line(display(), titles, columns)
+}
+
+/// Build separator line using global display config
+pub(crate) fn separator_line(columns: usize) -> String {
+    display().fill.to_string().repeat(columns)
+}
+
+/// Keep the first `max` lines of text, followed by a line counting the hidden ones
+pub(crate) fn truncate_lines(text: &str, max: usize) -> String {
+    let count = text.lines().count();
+    if count <= max {
+        return text.to_owned();
+    }
+    let kept: String = text.split_inclusive('\n').take(max).collect();
+    format!("{kept}… and {} more\n", count - max)
+}
+
+/// Build section header line from titles with their prefix and suffix, filled up to `columns`
+/// characters. Suffixes may contain ANSI escape sequences, and are not affected by title case.
+fn header_line(cfg: DisplayConfig, titles: &[(&str, &str, &str)], columns: usize) -> String {
+    let text = if cfg.separator_only {
+        String::new()
+    } else {
+        let titles: Vec<String> = titles
+            .iter()
+            .map(|(title, prefix, suffix)| {
+                let title = match cfg.case {
+                    TitleCase::Unchanged => (*title).to_owned(),
+                    TitleCase::Upper => title.to_uppercase(),
+                    TitleCase::Lower => title.to_lowercase(),
+                };
+                if suffix.is_empty() {
+                    format!("{prefix}{title}")
+                } else {
+                    format!("{prefix}{title} ({suffix})")
+                }
+            })
+            .collect();
+        format!(" {} ", titles.join(" · "))
+    };
+    let fill_len = columns.saturating_sub(ansi_width(&text));
+    let left_len = match cfg.align {
+        TitleAlign::Left => cmp::min(2, fill_len),
+        TitleAlign::Center => fill_len / 2,
+        TitleAlign::Right => fill_len.saturating_sub(2),
+    };
+    let fill = cfg.fill.to_string();
+    format!(
+        "{}{text}{}",
+        fill.repeat(left_len),
+        fill.repeat(fill_len - left_len)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_kmgt() {
+        assert_eq!(format_kmgt(512, "B", 1, 0), "512 B");
+        assert_eq!(format_kmgt(7_967_049_728, "B", 2, 0), "7.42 GB");
+        assert_eq!(format_kmgt(7_967_049_728, "B", 0, 0), "7 GB");
+        assert_eq!(format_kmgt(1536, "B", 1, 8), "  1.5 KB");
+        assert_eq!(format_kmgt_si(1_500_000, "b/s", 1, 0), "1.5 Mb/s");
+        assert_eq!(format_kmgt_si(999, "b/s", 1, 8), " 999 b/s");
+        assert_eq!(format_exact(0, "B", 0), "0 B");
+        assert_eq!(format_exact(999, "B", 0), "999 B");
+        assert_eq!(format_exact(7_967_049_728, "B", 0), "7,967,049,728 B");
+        assert_eq!(format_exact(1_500_000, "b/s", 15), "  1,500,000 b/s");
+    }
+
+    #[test]
+    fn test_format_rate() {
+        assert_eq!(format_rate(1_500_000, RateUnit::Bits, 0), "1.5 Mb/s");
+        assert_eq!(format_rate(12_582_912, RateUnit::Bytes, 0), "1.5 MB/s");
+        assert_eq!(format_rate(4, RateUnit::Bytes, 0), "0 B/s");
+    }
+
+    #[test]
+    fn test_partial_bar_char() {
+        assert_eq!(partial_bar_char(0.0), None);
+        assert_eq!(partial_bar_char(0.05), None);
+        assert_eq!(partial_bar_char(0.1), Some('▏'));
+        assert_eq!(partial_bar_char(0.5), Some('▌'));
+        assert_eq!(partial_bar_char(0.9), Some('▉'));
+        assert_eq!(partial_bar_char(0.99), None);
+    }
+
+    #[test]
+    fn test_truncate_lines() {
+        assert_eq!(truncate_lines("", 2), "");
+        assert_eq!(truncate_lines("a\nb\n", 2), "a\nb\n");
+        assert_eq!(truncate_lines("a\nb\nc\nd\n", 2), "a\nb\n… and 2 more\n");
+        assert_eq!(truncate_lines("a\nb\n", 0), "… and 2 more\n");
+    }
+
+    #[test]
+    fn test_header_line() {
+        let cfg = DisplayConfig::default();
+        assert_eq!(header_line(cfg, &[("Load", "", "")], 11), "── Load ───");
+        assert_eq!(
+            header_line(cfg, &[("Load", "", "")], 11),
+            format!("{:─^11}", " Load ")
+        );
+        assert_eq!(header_line(cfg, &[("Load", "", "")], 4), " Load ");
+
+        let custom_cfg = DisplayConfig {
+            fill: '=',
+            align: TitleAlign::Left,
+            case: TitleCase::Upper,
+            ..DisplayConfig::default()
+        };
+        assert_eq!(
+            header_line(custom_cfg, &[("Load", "", "")], 12),
+            "== LOAD ===="
+        );
+
+        let right_cfg = DisplayConfig {
+            align: TitleAlign::Right,
+            case: TitleCase::Lower,
+            ..custom_cfg
+        };
+        assert_eq!(
+            header_line(right_cfg, &[("Load", "x ", "")], 14),
+            "==== x load =="
+        );
+
+        let separator_cfg = DisplayConfig {
+            separator_only: true,
+            ..custom_cfg
+        };
+        assert_eq!(
+            header_line(separator_cfg, &[("Load", "x ", "")], 5),
+            "====="
+        );
+
+        assert_eq!(
+            header_line(custom_cfg, &[("Net", "", "\u{1b}[31mlimited\u{1b}[0m")], 20),
+            "== NET (\u{1b}[31mlimited\u{1b}[0m) ==="
+        );
+        assert_eq!(
+            header_line(cfg, &[("Load", "x ", ""), ("Net", "", "none")], 25),
+            "── x Load · Net (none) ──"
+        );
+    }
+
+    #[test]
+    fn test_text_width() {
+        assert_eq!(text_width(""), 0);
+        assert_eq!(text_width("/mnt/data"), 9);
+        assert_eq!(text_width("/mnt/データ"), 11);
+        assert_eq!(text_width("e\u{301}te\u{301}"), 3);
+        assert_eq!(text_width("\u{f0a0a} disk"), 6);
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        assert_eq!(ellipsis("/mnt/data", 9), "/mnt/data");
+        assert_eq!(ellipsis("/mnt/data", 5), "/mnt…");
+        assert_eq!(ellipsis("/mnt/データ", 8), "/mnt/デ…");
+        assert_eq!(ellipsis("/mnt/データ", 7), "/mnt/…");
+        assert_eq!(ellipsis("データ", 1), "…");
+    }
+
+    #[test]
+    fn test_ansi_helpers() {
+        let s = "12 \u{1b}[31mGB\u{1b}[0m free";
+        assert_eq!(ansi_width(s), 10);
+        assert_eq!(pad_ansi(s, 12), format!("{s}  "));
+        assert_eq!(pad_ansi(s, 4), s);
+        assert_eq!(truncate_ansi(s, 4), "12 \u{1b}[31mG\u{1b}[0m");
+        assert_eq!(slice_ansi(s, 4, 7), "\u{1b}[31mB\u{1b}[0m f");
+        assert_eq!(slice_ansi("データ", 1, 5), "ー");
+        assert_eq!(slice_ansi("e\u{301}te\u{301}", 2, 3), "e\u{301}");
+    }
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::with_header(&[Align::Left, Align::Right], &["Host", "Load"]);
+        assert!(table.is_empty());
+        table.push(vec!["nas".to_owned(), "0.25".to_owned()]);
+        table.push(vec![
+            "router".to_owned(),
+            "\u{1b}[31m12.50\u{1b}[0m".to_owned(),
+        ]);
+        table.push(vec!["offline-host".to_owned()]);
+        assert!(!table.is_empty());
+        assert_eq!(table.widths(), [6, 5]);
+        assert_eq!(
+            table.padded_rows(&table.widths()),
+            [
+                vec!["Host  ", " Load"],
+                vec!["nas   ", " 0.25"],
+                vec!["router", "\u{1b}[31m12.50\u{1b}[0m"],
+                vec!["offline-host"],
+            ]
+        );
+        assert_eq!(table.fit_widths(0, 10, 2), [3, 5]);
+        assert_eq!(table.fit_widths(0, 4, 2), [1, 5]);
+        assert_eq!(table.fit_widths(0, 80, 2), [6, 5]);
+        assert_eq!(
+            table.padded_rows(&[3, 5])[2],
+            ["ro…", "\u{1b}[31m12.50\u{1b}[0m"]
+        );
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi(""), "");
+        assert_eq!(strip_ansi("▕██\u{1b}[7;31m50%\u{1b}[0m ▏\n"), "▕██50% ▏\n");
     }
 }
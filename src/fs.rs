@@ -1,112 +1,606 @@
 use std::{
+    borrow::Cow,
     cmp,
-    collections::HashSet,
-    ffi::{CStr, CString, OsStr},
-    fmt, io, mem,
-    os::unix::ffi::OsStrExt,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    ffi::{CString, OsStr, OsString},
+    fmt::{self, Write as _},
+    fs, io, mem,
+    os::unix::ffi::{OsStrExt, OsStringExt},
     path::{Path, PathBuf},
-    sync::atomic::Ordering,
+    sync::{atomic::Ordering, mpsc, Mutex},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use ansi_term::{
-    Colour::{Red, Yellow},
-    Style,
-};
-use libc::{endmntent, getmntent, setmntent, statvfs};
+use ansi_term::Style;
+use libc::statvfs;
 
 use crate::{
     config,
-    fmt::format_kmgt,
-    module::{ModuleData, TERM_COLUMNS},
+    fmt::{
+        ansi_width, ellipsis, format_size, partial_bar_char, slice_ansi, text_width, truncate_ansi,
+        Align, Table,
+    },
+    i18n, icons,
+    module::{BuiltinModule, Module, TERM_COLUMNS},
+    prometheus,
+    provider::{Providers, Source},
+    style::{self, Severity},
+    sysroot,
 };
 
 const MIN_FS_BAR_LEN: usize = 30;
 
+/// Filesystem types that are always mounted read-only
+const READ_ONLY_FS_TYPES: [&str; 4] = ["erofs", "iso9660", "squashfs", "udf"];
+
+/// Filesystem problem worth reporting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FsProblem {
+    /// Mounted read-only, although filesystem type is writable
+    ReadOnly,
+    /// Filesystem reported errors
+    Errors,
+    /// Filesystem did not respond in time
+    Stalled,
+}
+
+impl FsProblem {
+    /// Problem name, for metrics labels
+    fn name(self) -> &'static str {
+        match self {
+            FsProblem::ReadOnly => "read_only",
+            FsProblem::Errors => "errors",
+            FsProblem::Stalled => "stalled",
+        }
+    }
+
+    /// Short marker to display next to mount point
+    fn marker(self) -> &'static str {
+        match self {
+            FsProblem::ReadOnly => "(ro)",
+            FsProblem::Errors => "(errors)",
+            FsProblem::Stalled => "(stalled)",
+        }
+    }
+}
+
 /// Information on a filesystem
+#[derive(serde::Serialize)]
 pub(crate) struct FsMountInfo {
     mount_path: PathBuf,
     used_bytes: u64,
+    /// Free space only available to root
+    reserved_bytes: u64,
     total_bytes: u64,
+    problem: Option<FsProblem>,
+    /// Usage percentage above which to display in yellow
+    #[serde(skip)]
+    usage_warning: f32,
+    /// Usage percentage above which to display in red
+    #[serde(skip)]
+    usage_critical: f32,
+    /// Estimated days until filesystem is full at recent growth rate
+    days_until_full: Option<u32>,
+    /// Display name from config aliases, instead of mount point
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    /// Usage could not be fetched, only mount point and problem are known
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    usage_unavailable: bool,
+}
+
+impl FsMountInfo {
+    /// Build mount info for a filesystem whose usage is not known
+    fn without_usage(mount_path: PathBuf, problem: Option<FsProblem>) -> Self {
+        Self {
+            mount_path,
+            used_bytes: 0,
+            reserved_bytes: 0,
+            total_bytes: 0,
+            problem,
+            usage_warning: 0.0,
+            usage_critical: 0.0,
+            days_until_full: None,
+            label: None,
+            usage_unavailable: true,
+        }
+    }
+
+    /// Space available to unprivileged users
+    fn available_bytes(&self) -> u64 {
+        self.total_bytes - self.used_bytes - self.reserved_bytes
+    }
+
+    /// Usage ratio as seen by unprivileged users, like df does
+    fn usage_ratio(&self) -> f64 {
+        self.used_bytes as f64 / (self.used_bytes + self.available_bytes()) as f64
+    }
+
+    /// Get severity from problem and usage thresholds
+    fn severity(&self) -> Severity {
+        let fs_usage = 100.0 * self.usage_ratio() as f32;
+        if self.problem.is_some() || fs_usage >= self.usage_critical {
+            Severity::Critical
+        } else if fs_usage >= self.usage_warning {
+            Severity::Warning
+        } else {
+            Severity::Ok
+        }
+    }
+
+    /// Get display style from usage thresholds, or from a gradient if color depth is set
+    fn style(&self, gradient_depth: Option<style::ColorDepth>) -> Style {
+        let fs_usage = 100.0 * self.usage_ratio() as f32;
+        let gradient_color = gradient_depth.and_then(|depth| {
+            style::gradient_color(fs_usage, self.usage_warning, self.usage_critical, depth)
+        });
+        match gradient_color {
+            Some(color) if self.problem.is_none() => color.normal(),
+            _ => style::theme().style(self.severity()),
+        }
+    }
+}
+
+/// Mount whose information is being fetched by a worker thread
+struct PendingMount {
+    mount_path: PathBuf,
+    problem: Option<FsProblem>,
+    /// Worker result, none if the worker of a previous fetch is still blocked
+    rx: Option<mpsc::Receiver<io::Result<FsMountInfo>>>,
+}
+
+/// Mounts whose statvfs worker thread has not returned yet, possibly blocked forever, so that no
+/// other worker is started for them until it does
+static STATVFS_WORKERS: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// Filesystem display options
+#[derive(Clone, Copy)]
+pub(crate) struct FsLayoutOptions {
+    display: config::FsDisplay,
+    layout: config::FsLayout,
+    min_bar_len: usize,
+    label_width: Option<usize>,
+    align_bars: bool,
+    gradient: bool,
+    fill_warning_days: u32,
+}
+
+impl Default for FsLayoutOptions {
+    fn default() -> Self {
+        Self::from(&config::FsConfig::default())
+    }
+}
+
+impl From<&config::FsConfig> for FsLayoutOptions {
+    fn from(cfg: &config::FsConfig) -> Self {
+        Self {
+            display: cfg.display,
+            layout: cfg.layout,
+            min_bar_len: cmp::max(cfg.min_bar_len, MIN_FS_BAR_LEN),
+            label_width: cfg.label_width.map(|w| cmp::max(w, 1)),
+            align_bars: cfg.align_bars,
+            gradient: cfg.gradient,
+            fill_warning_days: cfg.fill_warning_days,
+        }
+    }
 }
 
 /// Information on all filesystems
+#[derive(serde::Serialize)]
 pub(crate) struct FsInfo {
     mounts: Vec<FsMountInfo>,
+    #[serde(skip)]
+    layout: FsLayoutOptions,
 }
 
-/// Fetch filesystem information for all filesystems
-pub(crate) fn fetch(cfg: &config::FsConfig) -> anyhow::Result<ModuleData> {
-    let mut mounts = Vec::new();
+/// Mounted filesystem, from the mount list
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MountEntry {
+    /// Mount ID
+    id: u32,
+    /// Mount ID of the parent mount
+    parent_id: u32,
+    /// Device or source
+    dev: String,
+    /// Mount point
+    path: PathBuf,
+    /// Filesystem type, with its subtype if any, like `fuse.rclone`
+    fs_type: String,
+    /// Per mount options
+    opts: String,
+    /// Filesystem (superblock) options
+    super_opts: String,
+}
 
-    // Open mount list file
-    // Note: /etc/mtab is a symlink to /proc/self/mounts
-    let path = CString::new("/proc/mounts")?;
-    let mode = CString::new("r")?;
-    // SAFETY: libc call
-    let mount_file = unsafe { setmntent(path.as_ptr(), mode.as_ptr()) };
-    anyhow::ensure!(!mount_file.is_null(), "setmntent failed");
+/// Read mounted filesystems list
+pub(crate) fn read_mounts() -> anyhow::Result<Vec<MountEntry>> {
+    // Read as bytes, because mount points may not be valid UTF-8
+    let mountinfo = fs::read(sysroot::path("/proc/self/mountinfo"))?;
+    mountinfo
+        .split(|&b| b == b'\n')
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            parse_mountinfo_line(l).ok_or_else(|| {
+                anyhow::anyhow!("Invalid mountinfo line {:?}", String::from_utf8_lossy(l))
+            })
+        })
+        .collect()
+}
 
-    // Loop over mounts
-    let mut known_devices = HashSet::new();
-    loop {
-        // SAFETY: libc call
-        let mount = unsafe { getmntent(mount_file) };
-        if mount.is_null() {
-            break;
-        }
-        let mount_path_raw;
-        let fs_type;
-        let fs_dev;
-        // SAFETY: get getmntend output
-        unsafe {
-            mount_path_raw = CStr::from_ptr((*mount).mnt_dir);
-            fs_type = CStr::from_ptr((*mount).mnt_type).to_str()?;
-            fs_dev = CStr::from_ptr((*mount).mnt_fsname).to_str()?;
+/// Parse a `/proc/self/mountinfo` line, see `proc_pid_mountinfo(5)`
+fn parse_mountinfo_line(line: &[u8]) -> Option<MountEntry> {
+    let mut fields = line.split(|&b| b == b' ');
+    let id = str::from_utf8(fields.next()?).ok()?.parse().ok()?;
+    let parent_id = str::from_utf8(fields.next()?).ok()?.parse().ok()?;
+    // Device number and root of the mount in the filesystem
+    let _ = fields.nth(1)?;
+    let path = PathBuf::from(OsString::from_vec(unescape_octal(fields.next()?)));
+    let opts = String::from_utf8_lossy(fields.next()?).into_owned();
+    // Optional fields, like shared:1, until the separator
+    fields.find(|f| *f == b"-")?;
+    let fs_type = String::from_utf8_lossy(&unescape_octal(fields.next()?)).into_owned();
+    let dev = String::from_utf8_lossy(&unescape_octal(fields.next()?)).into_owned();
+    let super_opts = String::from_utf8_lossy(fields.next()?).into_owned();
+    Some(MountEntry {
+        id,
+        parent_id,
+        dev,
+        path,
+        fs_type,
+        opts,
+        super_opts,
+    })
+}
+
+/// Decode octal escapes of mountinfo fields, like `\040` for spaces
+fn unescape_octal(field: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(field.len());
+    let mut rest = field;
+    while let Some((&b, next)) = rest.split_first() {
+        let escaped = (b == b'\\')
+            .then(|| next.get(..3))
+            .flatten()
+            .and_then(|o| str::from_utf8(o).ok())
+            .and_then(|o| u8::from_str_radix(o, 8).ok());
+        if let Some(escaped) = escaped {
+            unescaped.push(escaped);
+            rest = &next[3..];
+        } else {
+            unescaped.push(b);
+            rest = next;
         }
-        let mount_path: &Path = OsStr::from_bytes(mount_path_raw.to_bytes()).as_ref();
+    }
+    unescaped
+}
+
+/// Check if a mount is hidden by another mount on the same mount point, in which case its
+/// information would be the one of the top mount
+fn is_overmounted(entry: &MountEntry, entries: &[MountEntry]) -> bool {
+    entries
+        .iter()
+        .any(|e| (e.parent_id == entry.id) && (e.path == entry.path))
+}
+
+/// Fetch filesystem information for filesystems of the mount list
+pub(crate) fn fetch(cfg: &config::FsConfig, entries: &[MountEntry]) -> FsInfo {
+    let mut mounts = Vec::new();
+
+    let mut known_devices = HashSet::new();
+    let mut pending_mounts = Vec::new();
+    for entry in entries {
+        let mount_path = entry.path.as_path();
+        let fs_type = entry.fs_type.as_str();
+        let fs_dev = entry.dev.as_str();
 
         // Exclusions
-        if cfg.mount_type_blacklist.iter().any(|r| r.is_match(fs_type)) {
+        if is_overmounted(entry, entries) || is_type_excluded(cfg, fs_type) {
             continue;
         }
         if let Some(mount_path) = mount_path.to_str() {
-            if cfg
-                .mount_path_blacklist
-                .iter()
-                .any(|r| r.is_match(mount_path))
-            {
+            if is_path_excluded(cfg, mount_path) {
                 continue;
             }
         }
 
+        // Without statvfs, virtual filesystems (procfs, sysfs...) can only be told apart by their
+        // source not being a device
+        if sysroot::is_set() && !fs_dev.starts_with('/') {
+            continue;
+        }
+
         // Exclude mounts of devices already mounted (avoids duplicate for bind mounts or btrfs subvolumes)
-        if fs_dev.starts_with('/') {
-            if known_devices.contains(&fs_dev) {
-                continue;
-            }
-            known_devices.insert(fs_dev);
+        if fs_dev.starts_with('/') && !known_devices.insert(fs_dev) {
+            continue;
         }
 
-        // Get filesystem info
-        let Ok(mount_info) = fetch_mount_info(mount_path) else {
+        let problem = if fs_type == "ext4" && has_ext4_errors(fs_dev) {
+            Some(FsProblem::Errors)
+        } else if is_unexpectedly_read_only(entry) {
+            Some(FsProblem::ReadOnly)
+        } else {
+            None
+        };
+
+        // Usage would come from the running system, not from the inspected tree
+        if sysroot::is_set() {
+            mounts.push(FsMountInfo::without_usage(
+                mount_path.to_path_buf(),
+                problem,
+            ));
             continue;
+        }
+
+        // Get filesystem info in a worker thread, because statvfs can block forever on network filesystems
+        let worker_mount_path = mount_path.to_path_buf();
+        let rx = STATVFS_WORKERS
+            .lock()
+            .unwrap()
+            .insert(worker_mount_path.clone())
+            .then(|| {
+                let (tx, rx) = mpsc::channel();
+                thread::spawn(move || {
+                    let mount_info = fetch_mount_info(&worker_mount_path);
+                    STATVFS_WORKERS.lock().unwrap().remove(&worker_mount_path);
+                    let _ = tx.send(mount_info);
+                });
+                rx
+            });
+        pending_mounts.push(PendingMount {
+            mount_path: mount_path.to_path_buf(),
+            problem,
+            rx,
+        });
+    }
+
+    // Collect results, stalled workers are left behind
+    let deadline = Instant::now() + Duration::from_millis(cfg.statvfs_timeout_ms);
+    for pending in pending_mounts {
+        let result = match &pending.rx {
+            Some(rx) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => Err(mpsc::RecvTimeoutError::Timeout),
+        };
+        let mount_info = match result {
+            Ok(Ok(mount_info)) => {
+                if mount_info.total_bytes == 0 {
+                    // procfs, sysfs...
+                    continue;
+                }
+                FsMountInfo {
+                    problem: pending.problem,
+                    ..mount_info
+                }
+            }
+            Ok(Err(_)) | Err(mpsc::RecvTimeoutError::Disconnected) => continue,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                FsMountInfo::without_usage(pending.mount_path, Some(FsProblem::Stalled))
+            }
         };
+        mounts.push(mount_info);
+    }
+
+    for mount_info in &mut mounts {
+        (mount_info.usage_warning, mount_info.usage_critical) =
+            usage_thresholds(cfg, &mount_info.mount_path);
+        mount_info.label = mount_alias(&cfg.aliases, &mount_info.mount_path);
+    }
+
+    if cfg.fill_prediction {
+        update_fill_prediction(
+            &mut mounts,
+            cfg.fill_history_file.as_deref(),
+            cfg.fill_history_days,
+        );
+    }
+
+    sort_mounts(&mut mounts, cfg.sort, cfg.sort_descending);
+
+    FsInfo {
+        mounts,
+        layout: FsLayoutOptions::from(cfg),
+    }
+}
+
+/// Minimum interval between two usage samples of the same filesystem, in seconds
+const FILL_HISTORY_SAMPLE_INTERVAL_S: u64 = 60 * 60;
+
+/// Minimum usage history duration to estimate growth rate, in seconds
+const FILL_HISTORY_MIN_SPAN_S: u64 = 6 * 60 * 60;
+
+/// Usage sample of a filesystem: timestamp in seconds since epoch, and used bytes
+type UsageSample = (u64, u64);
+
+/// Get default usage history filepath
+fn default_fill_history_filepath() -> anyhow::Result<PathBuf> {
+    let binary_name = env!("CARGO_PKG_NAME");
+    let xdg_dirs = xdg::BaseDirectories::with_prefix(binary_name)?;
+    Ok(xdg_dirs.place_state_file("fs_usage")?)
+}
+
+/// Estimate days until full from usage history file, and append current usage to it
+fn update_fill_prediction(
+    mounts: &mut [FsMountInfo],
+    history_filepath: Option<&Path>,
+    history_days: u32,
+) {
+    let Some(history_filepath) = history_filepath
+        .map(Path::to_path_buf)
+        .or_else(|| default_fill_history_filepath().ok())
+    else {
+        return;
+    };
+    let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) else {
+        return;
+    };
+    let now = now.as_secs();
+    let min_ts = now.saturating_sub(u64::from(history_days) * 24 * 60 * 60);
+
+    let history_raw = fs::read_to_string(&history_filepath).unwrap_or_default();
+    let mut history = parse_fill_history(&history_raw);
+    history.retain(|_, samples| {
+        samples.retain(|(ts, _)| (min_ts..=now).contains(ts));
+        !samples.is_empty()
+    });
+
+    for mount_info in mounts.iter_mut() {
         if mount_info.total_bytes == 0 {
-            // procfs, sysfs...
             continue;
         }
-        mounts.push(mount_info);
+        let Some(mount_path) = mount_info.mount_path.to_str() else {
+            continue;
+        };
+        let samples = history.entry(mount_path.to_owned()).or_default();
+        mount_info.days_until_full = estimate_days_until_full(samples, now, mount_info);
+        if samples
+            .last()
+            .is_none_or(|(ts, _)| now - ts >= FILL_HISTORY_SAMPLE_INTERVAL_S)
+        {
+            samples.push((now, mount_info.used_bytes));
+        }
     }
 
-    // Close mount list file
-    // SAFETY: libc call
-    unsafe {
-        endmntent(mount_file);
-    } // endmntent always returns 1
+    // Failing to save history is not worth failing the whole section
+    let _ = fs::write(&history_filepath, serialize_fill_history(&history));
+}
+
+/// Parse usage history file, made of tab separated mount point, timestamp and used bytes lines
+fn parse_fill_history(history: &str) -> BTreeMap<String, Vec<UsageSample>> {
+    let mut samples: BTreeMap<String, Vec<UsageSample>> = BTreeMap::new();
+    for (mount_path, sample) in history.lines().filter_map(|l| {
+        let mut tokens = l.split('\t');
+        let mount_path = tokens.next()?;
+        let ts = tokens.next()?.parse().ok()?;
+        let used_bytes = tokens.next()?.parse().ok()?;
+        Some((mount_path, (ts, used_bytes)))
+    }) {
+        samples
+            .entry(mount_path.to_owned())
+            .or_default()
+            .push(sample);
+    }
+    for mount_samples in samples.values_mut() {
+        mount_samples.sort_unstable();
+    }
+    samples
+}
+
+/// Serialize usage history file
+fn serialize_fill_history(history: &BTreeMap<String, Vec<UsageSample>>) -> String {
+    history
+        .iter()
+        .flat_map(|(mount_path, samples)| samples.iter().map(move |s| (mount_path, s)))
+        .fold(String::new(), |mut s, (mount_path, (ts, used_bytes))| {
+            let _ = writeln!(s, "{mount_path}\t{ts}\t{used_bytes}");
+            s
+        })
+}
+
+/// Estimate days until full from growth rate since oldest sample, if usage is growing
+fn estimate_days_until_full(
+    samples: &[UsageSample],
+    now: u64,
+    mount_info: &FsMountInfo,
+) -> Option<u32> {
+    let (oldest_ts, oldest_used_bytes) = *samples.first()?;
+    let span_s = now.checked_sub(oldest_ts)?;
+    if span_s < FILL_HISTORY_MIN_SPAN_S {
+        return None;
+    }
+    let growth_bytes = mount_info.used_bytes.checked_sub(oldest_used_bytes)?;
+    if growth_bytes == 0 {
+        return None;
+    }
+    let bytes_per_day = growth_bytes as f64 * (24.0 * 60.0 * 60.0) / span_s as f64;
+    Some((mount_info.available_bytes() as f64 / bytes_per_day) as u32)
+}
+
+/// Check if filesystem type is excluded by blacklist, or is a read-only image
+fn is_type_excluded(cfg: &config::FsConfig, fs_type: &str) -> bool {
+    cfg.mount_type_blacklist.iter().any(|r| r.is_match(fs_type))
+        || (!cfg.show_read_only_images && READ_ONLY_FS_TYPES.contains(&fs_type))
+}
+
+/// Check if mount point is excluded by blacklist or whitelist
+fn is_path_excluded(cfg: &config::FsConfig, mount_path: &str) -> bool {
+    cfg.mount_path_blacklist
+        .iter()
+        .any(|r| r.is_match(mount_path))
+        || (!cfg.mount_path_whitelist.is_empty()
+            && !cfg
+                .mount_path_whitelist
+                .iter()
+                .any(|r| r.is_match(mount_path)))
+}
+
+/// Get usage warning and critical percentages for a mount point
+fn usage_thresholds(cfg: &config::FsConfig, mount_path: &Path) -> (f32, f32) {
+    mount_path
+        .to_str()
+        .and_then(|p| cfg.thresholds.iter().find(|t| t.mount_path.is_match(p)))
+        .map_or((cfg.usage_warning, cfg.usage_critical), |t| {
+            (t.warning, t.critical)
+        })
+}
 
-    mounts.sort_by(|a, b| a.mount_path.cmp(&b.mount_path));
+/// Get display name of a mount point from the first matching alias
+fn mount_alias(aliases: &[config::FsAlias], mount_path: &Path) -> Option<String> {
+    let mount_path = mount_path.to_str()?;
+    let alias = aliases.iter().find(|a| a.mount_path.is_match(mount_path))?;
+    Some(
+        alias
+            .mount_path
+            .replace(mount_path, alias.name.as_str())
+            .into_owned(),
+    )
+}
+
+/// Sort mounts by given key, using path as secondary key
+fn sort_mounts(mounts: &mut [FsMountInfo], sort: config::FsSort, descending: bool) {
+    mounts.sort_by(|a, b| {
+        let ord = match sort {
+            config::FsSort::Path => cmp::Ordering::Equal,
+            config::FsSort::Usage => a.usage_ratio().total_cmp(&b.usage_ratio()),
+            config::FsSort::Size => a.total_bytes.cmp(&b.total_bytes),
+            config::FsSort::Free => a.available_bytes().cmp(&b.available_bytes()),
+        }
+        .then_with(|| a.mount_path.cmp(&b.mount_path));
+        if descending {
+            ord.reverse()
+        } else {
+            ord
+        }
+    });
+}
 
-    Ok(ModuleData::Fs(FsInfo { mounts }))
+/// Check if filesystem is read-only while its type normally is not
+///
+/// Only superblock options are checked, where errors=remount-ro shows up, because read-only mount
+/// points are intentional (bind mounts, or systemd sandboxing).
+fn is_unexpectedly_read_only(entry: &MountEntry) -> bool {
+    entry.super_opts.split(',').any(|o| o == "ro")
+        && !READ_ONLY_FS_TYPES.contains(&entry.fs_type.as_str())
+}
+
+/// Check if ext4 filesystem on given device has recorded errors
+fn has_ext4_errors(fs_dev: &str) -> bool {
+    // Resolve /dev/mapper/xxx or /dev/disk/by-xxx/yyy symlinks to the kernel device name
+    let dev_path = sysroot::path(fs_dev);
+    let Some(dev_name) = dev_path
+        .canonicalize()
+        .unwrap_or(dev_path)
+        .file_name()
+        .map(OsStr::to_os_string)
+    else {
+        return false;
+    };
+    let errors_count_filepath = sysroot::path("/sys/fs/ext4")
+        .join(dev_name)
+        .join("errors_count");
+    fs::read_to_string(errors_count_filepath)
+        .ok()
+        .and_then(|s| s.trim_end().parse::<u64>().ok())
+        .is_some_and(|c| c > 0)
 }
 
 /// Fetch detailed filesystem information
@@ -116,121 +610,421 @@ fn fetch_mount_info(mount_path: &Path) -> Result<FsMountInfo, io::Error> {
     let mut fs_stat: statvfs = unsafe { mem::zeroed() };
     let mount_point = CString::new(mount_path.as_os_str().as_bytes())?;
     // SAFETY: libc call
-    let rc = unsafe { statvfs(mount_point.as_ptr(), &mut fs_stat) };
+    let rc = unsafe { statvfs(mount_point.as_ptr(), &raw mut fs_stat) };
     if rc != 0 {
         return Err(io::Error::last_os_error());
     }
 
     let total_bytes = fs_stat.f_blocks * fs_stat.f_bsize as u64;
     let used_bytes = total_bytes - fs_stat.f_bfree * fs_stat.f_bsize as u64;
+    let reserved_bytes = fs_stat.f_bfree.saturating_sub(fs_stat.f_bavail) * fs_stat.f_bsize as u64;
 
     Ok(FsMountInfo {
         total_bytes,
         used_bytes,
+        reserved_bytes,
         mount_path: mount_path.to_path_buf(),
+        problem: None,
+        usage_warning: 0.0,
+        usage_critical: 0.0,
+        days_until_full: None,
+        label: None,
+        usage_unavailable: false,
     })
 }
 
 /// Generate a bar to represent filesystem usage
-#[expect(clippy::string_slice)]
-pub(crate) fn get_fs_bar(mount_info: &FsMountInfo, length: usize, style: Style) -> String {
+pub(crate) fn get_fs_bar(
+    mount_info: &FsMountInfo,
+    length: usize,
+    style: Style,
+    display: config::FsDisplay,
+) -> String {
     assert!(length >= MIN_FS_BAR_LEN);
 
-    let bar_text = format!(
-        "{} / {} ({:.1}%)",
-        format_kmgt(mount_info.used_bytes, "B"),
-        format_kmgt(mount_info.total_bytes, "B"),
-        100.0 * mount_info.used_bytes as f32 / mount_info.total_bytes as f32
+    let (bar_text, short_bar_text) = match display {
+        config::FsDisplay::Used => {
+            let used = format_size(mount_info.used_bytes, 0);
+            let pct = 100.0 * mount_info.usage_ratio();
+            (
+                format!(
+                    "{used} / {} ({pct:.1}%)",
+                    format_size(mount_info.total_bytes, 0)
+                ),
+                format!("{used} ({pct:.1}%)"),
+            )
+        }
+        config::FsDisplay::Available => {
+            let available = format_size(mount_info.available_bytes(), 0);
+            (
+                format!(
+                    "{available} free of {}",
+                    format_size(mount_info.total_bytes, 0)
+                ),
+                format!("{available} free"),
+            )
+        }
+    };
+    // Drop total if it does not fit, like with exact values
+    let bar_text = truncate_ansi(
+        if ansi_width(&bar_text) > length - 2 {
+            &short_bar_text
+        } else {
+            &bar_text
+        },
+        length - 2,
     );
 
     // Center bar text inside fill chars
-    let bar_text_len = bar_text.len();
+    let bar_text_len = ansi_width(&bar_text);
     let fill_count_before = (length - 2 - bar_text_len) / 2;
     let chars_used =
         ((length - 2) as u64 * mount_info.used_bytes / mount_info.total_bytes) as usize;
+    // Fraction of the cell following used ones, drawn as a partial block if it is empty
+    let partial = partial_bar_char(
+        ((length - 2) as f64 * mount_info.used_bytes as f64 / mount_info.total_bytes as f64).fract()
+            as f32,
+    );
+    let chars_reserved_end = ((length - 2) as u64
+        * (mount_info.used_bytes + mount_info.reserved_bytes)
+        / mount_info.total_bytes) as usize;
 
-    let bar_char = '█';
+    let bar_chars = crate::fmt::display().bar;
+    let bar_char = bar_chars.fill;
+    let reserved_style = style.dimmed();
 
     let pos1 = cmp::min(chars_used, fill_count_before);
+    let pos1r = cmp::max(pos1, cmp::min(chars_reserved_end, fill_count_before));
     let pos2 = fill_count_before;
     let pos3 = cmp::max(
         fill_count_before,
         cmp::min(chars_used, fill_count_before + bar_text_len),
     );
+    let pos3r = cmp::max(
+        pos3,
+        cmp::min(chars_reserved_end, fill_count_before + bar_text_len),
+    );
     let pos4 = fill_count_before + bar_text_len;
     let pos5 = cmp::max(chars_used, fill_count_before + bar_text_len);
+    let pos5r = cmp::max(pos5, cmp::min(chars_reserved_end, length - 2));
 
-    format!(
-        "{}{}{}{}{}{}{}{}",
-        style.paint("▕"),
+    // Reserved segments are only emitted if not empty
+    let mut parts = vec![
+        style.paint(bar_chars.left.to_string()),
         style.paint(bar_char.to_string().repeat(pos1)),
-        style.paint(' '.to_string().repeat(pos2 - pos1)),
-        style.reverse().paint(&bar_text[0..(pos3 - pos2)]),
-        style.paint(&bar_text[(pos3 - pos2)..]),
-        style.paint(bar_char.to_string().repeat(pos5 - pos4)),
-        style.paint(' '.to_string().repeat(length - 2 - pos5)),
-        style.paint("▏"),
-    )
+    ];
+    if pos1r > pos1 {
+        parts.push(reserved_style.paint(bar_char.to_string().repeat(pos1r - pos1)));
+    }
+    let mut empty_before = pos2 - pos1r;
+    if let Some(partial) = partial.filter(|_| pos1r == chars_used && empty_before > 0) {
+        parts.push(style.paint(partial.to_string()));
+        empty_before -= 1;
+    }
+    parts.push(style.paint(bar_chars.empty.to_string().repeat(empty_before)));
+    parts.push(style.reverse().paint(slice_ansi(&bar_text, 0, pos3 - pos2)));
+    if pos3r > pos3 {
+        parts.push(reserved_style.reverse().paint(slice_ansi(
+            &bar_text,
+            pos3 - pos2,
+            pos3r - pos2,
+        )));
+    }
+    parts.push(style.paint(slice_ansi(&bar_text, pos3r - pos2, bar_text_len)));
+    parts.push(style.paint(bar_char.to_string().repeat(pos5 - pos4)));
+    if pos5r > pos5 {
+        parts.push(reserved_style.paint(bar_char.to_string().repeat(pos5r - pos5)));
+    }
+    let mut empty_after = length - 2 - pos5r;
+    if let Some(partial) = partial.filter(|_| pos5r == chars_used && empty_after > 0) {
+        parts.push(style.paint(partial.to_string()));
+        empty_after -= 1;
+    }
+    parts.push(style.paint(bar_chars.empty.to_string().repeat(empty_after)));
+    parts.push(style.paint(bar_chars.right.to_string()));
+    // Empty segments would still be wrapped in escape sequences
+    parts
+        .iter()
+        .filter(|p| !p.is_empty())
+        .map(ToString::to_string)
+        .collect()
 }
 
-fn ellipsis(s: &str, max_len: usize) -> String {
-    assert!(max_len >= 1);
+impl BuiltinModule for FsInfo {
+    const LETTER: &'static str = "f";
+    const KEY: &'static str = "filesystems";
+    const TITLE: i18n::Label = i18n::Label::FsTitle;
+    const ICON: icons::Icon = icons::Icon::Disk;
+    const SOURCES: &'static [Source] = &[Source::Mounts];
 
-    if s.chars().count() <= max_len {
-        s.to_owned()
-    } else {
-        let mut new_s: String = s.to_owned().chars().take(max_len - 1).collect(); // truncate on unicode char boundaries
-        new_s.push('…');
-        new_s
+    fn max_lines(cfg: &config::MaxLinesConfig) -> Option<usize> {
+        cfg.fs
+    }
+
+    fn timeout(cfg: &config::TimeoutsConfig) -> Option<u64> {
+        cfg.fs
+    }
+
+    fn fetch(cfg: &config::Config, providers: &Providers) -> anyhow::Result<Self> {
+        Ok(fetch(&cfg.fs, &providers.mounts()?))
     }
 }
 
+impl Module for FsInfo {
+    /// Only keep filesystems with a problem, almost full, or predicted to be full soon
+    fn retain_alerts(&mut self) -> bool {
+        let fill_warning_days = self.layout.fill_warning_days;
+        self.mounts.retain(|m| {
+            m.problem.is_some()
+                || 100.0 * m.usage_ratio() as f32 >= m.usage_warning
+                || m.days_until_full.is_some_and(|d| d < fill_warning_days)
+        });
+        !self.mounts.is_empty()
+    }
+
+    /// Highest severity of filesystems
+    fn severity(&self) -> Severity {
+        self.mounts
+            .iter()
+            .map(FsMountInfo::severity)
+            .max()
+            .unwrap_or(Severity::Ok)
+    }
+
+    /// Single line summary, with usage percentage of each filesystem
+    fn compact(&self) -> String {
+        self.mounts
+            .iter()
+            .map(|m| {
+                let path = m
+                    .label
+                    .as_deref()
+                    .map_or_else(|| m.mount_path.to_string_lossy(), Cow::Borrowed);
+                let marker = m
+                    .problem
+                    .map(|p| format!(" {}", p.marker()))
+                    .unwrap_or_default();
+                if m.usage_unavailable {
+                    format!("{path}{marker}")
+                } else {
+                    let usage = format!("{:.0}%", 100.0 * m.usage_ratio());
+                    format!("{path} {}{marker}", m.style(None).paint(usage))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Write filesystem usage as Prometheus metrics
+    fn write_prometheus(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        // Stacked mounts on the same path would produce duplicate series
+        let mut known_paths = HashSet::new();
+        let mounts: Vec<(&FsMountInfo, &str)> = self
+            .mounts
+            .iter()
+            .filter_map(|m| Some((m, m.mount_path.to_str()?)))
+            .filter(|(_, p)| known_paths.insert(*p))
+            .collect();
+        let responding = || mounts.iter().filter(|(m, _)| !m.usage_unavailable);
+
+        let metrics: [prometheus::GaugeDef<FsMountInfo, u64>; 3] = [
+            ("filesystem_size_bytes", "Filesystem size", |m| {
+                m.total_bytes
+            }),
+            ("filesystem_used_bytes", "Filesystem used space", |m| {
+                m.used_bytes
+            }),
+            (
+                "filesystem_avail_bytes",
+                "Filesystem space available to unprivileged users",
+                FsMountInfo::available_bytes,
+            ),
+        ];
+        for (name, help, value) in metrics {
+            prometheus::write_gauge(f, name, help)?;
+            for (mount_info, mount_path) in responding() {
+                prometheus::write_sample(
+                    f,
+                    name,
+                    &[("mountpoint", mount_path)],
+                    value(mount_info),
+                )?;
+            }
+        }
+
+        prometheus::write_gauge(
+            f,
+            "filesystem_days_until_full",
+            "Estimated days until filesystem is full",
+        )?;
+        for (mount_info, mount_path) in responding() {
+            if let Some(days) = mount_info.days_until_full {
+                prometheus::write_sample(
+                    f,
+                    "filesystem_days_until_full",
+                    &[("mountpoint", mount_path)],
+                    days,
+                )?;
+            }
+        }
+
+        prometheus::write_gauge(
+            f,
+            "filesystem_problem",
+            "Filesystem problem (read_only, errors, stalled)",
+        )?;
+        for (mount_info, mount_path) in &mounts {
+            if let Some(problem) = mount_info.problem {
+                prometheus::write_sample(
+                    f,
+                    "filesystem_problem",
+                    &[("mountpoint", mount_path), ("problem", problem.name())],
+                    1,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Build mount point label, with problem marker if any
+fn mount_label(mount_info: &FsMountInfo, term_width: usize, path_max_len: usize) -> Option<String> {
+    let mount_path = match mount_info.label.as_deref() {
+        Some(label) => label,
+        None => mount_info.mount_path.to_str()?,
+    };
+    let icon = icons::prefix(icons::Icon::Disk);
+    let icon_len = text_width(&icon);
+    // Mounts without usage have no bar, so they can use the whole line
+    let max_len = if mount_info.usage_unavailable {
+        term_width
+    } else {
+        path_max_len
+    };
+    Some(if let Some(problem) = mount_info.problem {
+        let marker = problem.marker();
+        format!(
+            "{icon}{} {marker}",
+            ellipsis(
+                mount_path,
+                cmp::max(max_len.saturating_sub(icon_len + text_width(marker) + 1), 1)
+            )
+        )
+    } else {
+        format!(
+            "{icon}{}",
+            ellipsis(mount_path, cmp::max(max_len.saturating_sub(icon_len), 1))
+        )
+    })
+}
+
 impl fmt::Display for FsInfo {
     /// Output filesystem information
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let term_width = cmp::max(TERM_COLUMNS.load(Ordering::SeqCst), MIN_FS_BAR_LEN + 3);
-        let path_max_len = term_width - 1 - MIN_FS_BAR_LEN;
+        let min_bar_len = self.layout.min_bar_len;
+        let color_depth = style::detect_color_depth();
+        let term_width = cmp::max(TERM_COLUMNS.load(Ordering::SeqCst), min_bar_len + 3);
+        let two_lines = self.layout.layout == config::FsLayout::TwoLines;
+        let path_max_len = if two_lines {
+            term_width
+        } else {
+            let max_len = term_width - 1 - min_bar_len;
+            self.layout
+                .label_width
+                .map_or(max_len, |w| cmp::min(w, max_len))
+        };
 
         let pretty_mount_paths: Vec<String> = self
             .mounts
             .iter()
-            .map(|x| {
-                Ok(ellipsis(
-                    x.mount_path
-                        .to_str()
-                        .ok_or_else(|| anyhow::anyhow!("Unable to decode mount point"))?,
-                    path_max_len,
-                ))
-            })
-            .collect::<anyhow::Result<Vec<_>>>()
-            .map_err(|_| fmt::Error)?;
-        let max_path_len = pretty_mount_paths
-            .iter()
-            .map(|x| x.chars().count())
-            .max()
-            .unwrap();
+            .map(|x| mount_label(x, term_width, path_max_len))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(fmt::Error)?;
+        let label_width = if two_lines || !self.layout.align_bars {
+            None
+        } else if self.layout.label_width.is_some() {
+            Some(path_max_len)
+        } else {
+            // Mounts without usage have no bar to align
+            let mut table = Table::new(&[Align::Left]);
+            for (label, _) in pretty_mount_paths
+                .iter()
+                .zip(&self.mounts)
+                .filter(|(_, m)| !m.usage_unavailable)
+            {
+                table.push(vec![label.clone()]);
+            }
+            table.widths().first().copied()
+        };
 
         for (mount_info, pretty_mount_path) in self.mounts.iter().zip(pretty_mount_paths) {
-            let fs_usage = mount_info.used_bytes as f32 / mount_info.total_bytes as f32;
-            let text_style = if fs_usage >= 0.95 {
-                Red.normal()
-            } else if fs_usage >= 0.85 {
-                Yellow.normal()
+            if mount_info.usage_unavailable {
+                writeln!(
+                    f,
+                    "{}",
+                    style::theme().paint(&pretty_mount_path, mount_info.severity())
+                )?;
+                continue;
+            }
+            let text_style = mount_info.style(self.layout.gradient.then_some(color_depth));
+
+            let fill_estimate = mount_info
+                .days_until_full
+                .map(|d| format!(" {d}d"))
+                .unwrap_or_default();
+            let severity = mount_info.severity();
+            let symbol = style::theme().symbol(severity);
+            let suffix_len = fill_estimate.len() + text_width(&symbol);
+            let symbol = if symbol.is_empty() {
+                symbol
+            } else {
+                style::theme().style(severity).paint(symbol).to_string()
+            };
+            let fill_style = if mount_info
+                .days_until_full
+                .is_some_and(|d| d < self.layout.fill_warning_days)
+            {
+                style::theme().warning
             } else {
                 Style::new()
             };
 
-            writeln!(
-                f,
-                "{}{} {}",
-                text_style.paint(&pretty_mount_path),
-                text_style.paint(" ".repeat(max_path_len - pretty_mount_path.chars().count())),
-                get_fs_bar(
-                    mount_info,
-                    cmp::max(term_width - max_path_len - 1, MIN_FS_BAR_LEN),
-                    text_style
-                )
-            )?;
+            if two_lines {
+                writeln!(f, "{}", text_style.paint(&pretty_mount_path))?;
+                writeln!(
+                    f,
+                    "{}{}{}",
+                    get_fs_bar(
+                        mount_info,
+                        cmp::max(term_width.saturating_sub(suffix_len), min_bar_len),
+                        text_style,
+                        self.layout.display
+                    ),
+                    fill_style.paint(&fill_estimate),
+                    symbol
+                )?;
+            } else {
+                let path_len = text_width(&pretty_mount_path);
+                let padded_len = label_width.map_or(path_len, |w| cmp::max(w, path_len));
+                let label = pretty_mount_path + &" ".repeat(padded_len - path_len);
+                writeln!(
+                    f,
+                    "{} {}{}{}",
+                    text_style.paint(label),
+                    get_fs_bar(
+                        mount_info,
+                        cmp::max(
+                            term_width.saturating_sub(padded_len + 1 + suffix_len),
+                            min_bar_len
+                        ),
+                        text_style,
+                        self.layout.display
+                    ),
+                    fill_style.paint(&fill_estimate),
+                    symbol
+                )?;
+            }
         }
 
         Ok(())
@@ -238,11 +1032,120 @@ impl fmt::Display for FsInfo {
 }
 
 #[cfg(test)]
+#[expect(clippy::shadow_unrelated)]
 mod tests {
+    use ansi_term::Colour::Red;
     use serial_test::serial;
 
     use super::*;
 
+    /// Build mount info, with default thresholds
+    fn mount(path: &str, used_bytes: u64, total_bytes: u64) -> FsMountInfo {
+        FsMountInfo {
+            mount_path: PathBuf::from(path),
+            used_bytes,
+            reserved_bytes: 0,
+            total_bytes,
+            problem: None,
+            usage_warning: 85.0,
+            usage_critical: 95.0,
+            days_until_full: None,
+            label: None,
+            usage_unavailable: false,
+        }
+    }
+
+    #[test]
+    fn test_compact() {
+        assert_eq!(
+            FsInfo {
+                layout: FsLayoutOptions::default(),
+                mounts: vec![
+                    mount("/", 410, 1000),
+                    FsMountInfo {
+                        problem: Some(FsProblem::ReadOnly),
+                        ..mount("/mnt/iso", 900, 1000)
+                    },
+                    FsMountInfo::without_usage(PathBuf::from("/mnt/nfs"), Some(FsProblem::Stalled)),
+                    FsMountInfo::without_usage(PathBuf::from("/mnt/usb"), None),
+                ]
+            }
+            .compact(),
+            "/ 41% /mnt/iso \u{1b}[31m90%\u{1b}[0m (ro) /mnt/nfs (stalled) /mnt/usb"
+        );
+    }
+
+    #[test]
+    fn test_fetch_pending_statvfs() {
+        let entry = |id, path: &str| MountEntry {
+            id,
+            parent_id: 1,
+            dev: format!("server:/export{id}"),
+            path: PathBuf::from(path),
+            fs_type: "nfs4".to_owned(),
+            opts: "rw".to_owned(),
+            super_opts: "rw".to_owned(),
+        };
+        let stalled_path = PathBuf::from("/mnt/motd-test-stalled");
+        STATVFS_WORKERS.lock().unwrap().insert(stalled_path.clone());
+
+        let info = fetch(
+            &config::FsConfig::default(),
+            &[entry(2, "/"), entry(3, stalled_path.to_str().unwrap())],
+        );
+        let stalled = info
+            .mounts
+            .iter()
+            .find(|m| m.mount_path == stalled_path)
+            .unwrap();
+        assert_eq!(stalled.problem, Some(FsProblem::Stalled));
+        assert!(info.mounts.iter().any(|m| m.mount_path == Path::new("/")));
+        let workers = STATVFS_WORKERS.lock().unwrap();
+        assert!(workers.contains(&stalled_path));
+        assert!(!workers.contains(Path::new("/")));
+        drop(workers);
+
+        STATVFS_WORKERS.lock().unwrap().remove(&stalled_path);
+    }
+
+    #[test]
+    fn test_retain_alerts() {
+        let mut fs_info = FsInfo {
+            layout: FsLayoutOptions::default(),
+            mounts: vec![
+                mount("/", 410, 1000),
+                mount("/home", 850, 1000),
+                FsMountInfo {
+                    problem: Some(FsProblem::ReadOnly),
+                    ..mount("/mnt/iso", 100, 1000)
+                },
+                FsMountInfo {
+                    days_until_full: Some(10),
+                    ..mount("/var", 500, 1000)
+                },
+                FsMountInfo {
+                    days_until_full: Some(60),
+                    ..mount("/srv", 500, 1000)
+                },
+            ],
+        };
+        assert!(fs_info.retain_alerts());
+        assert_eq!(
+            fs_info
+                .mounts
+                .iter()
+                .map(|m| m.mount_path.to_str().unwrap())
+                .collect::<Vec<_>>(),
+            ["/home", "/mnt/iso", "/var"]
+        );
+
+        let mut fs_info = FsInfo {
+            layout: FsLayoutOptions::default(),
+            mounts: vec![mount("/", 410, 1000)],
+        };
+        assert!(!fs_info.retain_alerts());
+    }
+
     #[test]
     #[serial]
     fn test_output_fs_info() {
@@ -251,31 +1154,21 @@ mod tests {
             format!(
                 "{}",
                 FsInfo {
+                    layout: FsLayoutOptions::default(),
                     mounts: vec![
-                        FsMountInfo {
-                            mount_path: PathBuf::from("/foo/bar"),
-                            used_bytes: 234_560,
-                            total_bytes: 7_891_011
-                        },
-                        FsMountInfo {
-                            mount_path: PathBuf::from("/foo/baz"),
-                            used_bytes: 2_345_600_000,
-                            total_bytes: 7_891_011_000
-                        }
+                        mount("/foo/bar", 234_560, 7_891_011),
+                        mount("/foo/baz", 2_345_600_000, 7_891_011_000)
                     ]
                 },
             ),
-            "/foo/bar ▕  \u{1b}[7m\u{1b}[0m229.1 KB / 7.5 MB (3.0%)   ▏\n/foo/baz ▕███\u{1b}[7m2.2 G\u{1b}[0mB / 7.3 GB (29.7%)   ▏\n"
+            "/foo/bar ▕▉ 229.1 KB / 7.5 MB (3.0%)   ▏\n/foo/baz ▕███\u{1b}[7m2.2 G\u{1b}[0mB / 7.3 GB (29.7%)   ▏\n"
         );
         assert_eq!(
             format!(
                 "{}",
                 FsInfo {
-                    mounts: vec![FsMountInfo {
-                        mount_path: PathBuf::from("/0123456789"),
-                        used_bytes: 500,
-                        total_bytes: 1000
-                    },]
+                    layout: FsLayoutOptions::default(),
+                    mounts: vec![mount("/0123456789", 500, 1000),]
                 },
             ),
             "/0123456… ▕███\u{1b}[7m500 B / 100\u{1b}[0m0 B (50.0%)   ▏\n"
@@ -286,106 +1179,403 @@ mod tests {
     fn test_get_fs_bar() {
         assert_eq!(
             get_fs_bar(
-                &FsMountInfo{
-                    mount_path: PathBuf::from("/foo/bar"),
-                    used_bytes: 23456,
-                    total_bytes: 7_891_011
-                },
+                &mount("/foo/bar", 23456, 7_891_011),
                 40,
-                Red.normal()
+                Red.normal(),
+                config::FsDisplay::Used
             ),
-            "\u{1b}[31m▕\u{1b}[0m\u{1b}[31m\u{1b}[0m\u{1b}[31m       \u{1b}[0m\u{1b}[7;31m\u{1b}[0m\u{1b}[31m22.9 KB / 7.5 MB (0.3%)\u{1b}[0m\u{1b}[31m\u{1b}[0m\u{1b}[31m        \u{1b}[0m\u{1b}[31m▏\u{1b}[0m"
+            "\u{1b}[31m▕\u{1b}[0m\u{1b}[31m▏\u{1b}[0m\u{1b}[31m      \u{1b}[0m\u{1b}[31m22.9 KB / 7.5 MB (0.3%)\u{1b}[0m\u{1b}[31m        \u{1b}[0m\u{1b}[31m▏\u{1b}[0m"
         );
         assert_eq!(
             get_fs_bar(
-                &FsMountInfo {
-                    mount_path: PathBuf::from("/foo/bar"),
-                    used_bytes: 0,
-                    total_bytes: 7_891_011
-                },
+                &mount("/foo/bar", 0, 7_891_011),
                 40,
-                Style::new()
+                Style::new(),
+                config::FsDisplay::Used
             ),
-            "▕         \u{1b}[7m\u{1b}[0m0 B / 7.5 MB (0.0%)          ▏"
+            "▕         0 B / 7.5 MB (0.0%)          ▏"
         );
         assert_eq!(
             get_fs_bar(
-                &FsMountInfo {
-                    mount_path: PathBuf::from("/foo/bar"),
-                    used_bytes: 434_560,
-                    total_bytes: 7_891_011
-                },
+                &mount("/foo/bar", 434_560, 7_891_011),
                 40,
-                Style::new()
+                Style::new(),
+                config::FsDisplay::Used
             ),
-            "▕██     \u{1b}[7m\u{1b}[0m424.4 KB / 7.5 MB (5.5%)       ▏"
+            "▕██▏    424.4 KB / 7.5 MB (5.5%)       ▏"
         );
         assert_eq!(
             get_fs_bar(
-                &FsMountInfo {
-                    mount_path: PathBuf::from("/foo/bar"),
-                    used_bytes: 4_891_011_000,
-                    total_bytes: 7_891_011_000
-                },
+                &mount("/foo/bar", 4_891_011_000, 7_891_011_000),
                 40,
-                Style::new()
+                Style::new(),
+                config::FsDisplay::Used
             ),
             "▕███████\u{1b}[7m4.6 GB / 7.3 GB \u{1b}[0m(62.0%)        ▏"
         );
         assert_eq!(
             get_fs_bar(
-                &FsMountInfo {
-                    mount_path: PathBuf::from("/foo/bar"),
-                    used_bytes: 4_891_011_000,
-                    total_bytes: 7_891_011_000
-                },
+                &mount("/foo/bar", 4_891_011_000, 7_891_011_000),
                 30,
-                Style::new()
+                Style::new(),
+                config::FsDisplay::Used
             ),
             "▕██\u{1b}[7m4.6 GB / 7.3 GB\u{1b}[0m (62.0%)   ▏"
         );
         assert_eq!(
             get_fs_bar(
-                &FsMountInfo {
-                    mount_path: PathBuf::from("/foo/bar"),
-                    used_bytes: 4_891_011_000,
-                    total_bytes: 7_891_011_000
-                },
+                &mount("/foo/bar", 4_891_011_000, 7_891_011_000),
                 50,
-                Style::new()
+                Style::new(),
+                config::FsDisplay::Used
             ),
             "▕████████████\u{1b}[7m4.6 GB / 7.3 GB (\u{1b}[0m62.0%)             ▏"
         );
         assert_eq!(
             get_fs_bar(
-                &FsMountInfo {
-                    mount_path: PathBuf::from("/foo/bar"),
-                    used_bytes: 6_891_011_000_000,
-                    total_bytes: 7_891_011_000_000
-                },
+                &mount("/foo/bar", 6_891_011_000_000, 7_891_011_000_000),
                 40,
-                Style::new()
+                Style::new(),
+                config::FsDisplay::Used
+            ),
+            "▕███████\u{1b}[7m6.3 TB / 7.2 TB (87.3%)\u{1b}[0m███▏    ▏"
+        );
+        assert_eq!(
+            get_fs_bar(
+                &mount("/foo/bar", 7_891_011_000_000, 7_891_011_000_000),
+                40,
+                Style::new(),
+                config::FsDisplay::Used
+            ),
+            "▕███████\u{1b}[7m7.2 TB / 7.2 TB (100.0%)\u{1b}[0m███████▏"
+        );
+    }
+
+    #[test]
+    fn test_sort_mounts() {
+        let mut mounts = vec![
+            mount("/", 50, 100),
+            mount("/boot", 90, 100),
+            mount("/data", 500, 10000),
+        ];
+        let paths = |sorted: &[FsMountInfo]| {
+            sorted
+                .iter()
+                .map(|m| m.mount_path.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        sort_mounts(&mut mounts, config::FsSort::Usage, true);
+        assert_eq!(paths(&mounts), vec!["/boot", "/", "/data"]);
+        sort_mounts(&mut mounts, config::FsSort::Size, false);
+        assert_eq!(paths(&mounts), vec!["/", "/boot", "/data"]);
+        sort_mounts(&mut mounts, config::FsSort::Free, false);
+        assert_eq!(paths(&mounts), vec!["/boot", "/", "/data"]);
+        sort_mounts(&mut mounts, config::FsSort::Path, true);
+        assert_eq!(paths(&mounts), vec!["/data", "/boot", "/"]);
+    }
+
+    #[test]
+    fn test_parse_mountinfo_line() {
+        assert_eq!(
+            parse_mountinfo_line(
+                b"29 1 254:1 / / rw,relatime shared:1 - ext4 /dev/mapper/root rw,errors=remount-ro"
+            ),
+            Some(MountEntry {
+                id: 29,
+                parent_id: 1,
+                dev: "/dev/mapper/root".to_owned(),
+                path: PathBuf::from("/"),
+                fs_type: "ext4".to_owned(),
+                opts: "rw,relatime".to_owned(),
+                super_opts: "rw,errors=remount-ro".to_owned(),
+            })
+        );
+        assert_eq!(
+            parse_mountinfo_line(
+                b"412 29 0:58 / /mnt/my\\040drive ro,nosuid,nodev - fuse.rclone gdrive: rw,user_id=1000"
+            ),
+            Some(MountEntry {
+                id: 412,
+                parent_id: 29,
+                dev: "gdrive:".to_owned(),
+                path: PathBuf::from("/mnt/my drive"),
+                fs_type: "fuse.rclone".to_owned(),
+                opts: "ro,nosuid,nodev".to_owned(),
+                super_opts: "rw,user_id=1000".to_owned(),
+            })
+        );
+        assert_eq!(
+            parse_mountinfo_line(b"29 1 254:1 / / rw,relatime shared:1 master:2"),
+            None
+        );
+        assert_eq!(unescape_octal(b"a\\011b\\134c\\04"), b"a\tb\\c\\04");
+    }
+
+    #[test]
+    fn test_is_overmounted() {
+        let mount = |id: u32, parent_id: u32, path: &str| MountEntry {
+            id,
+            parent_id,
+            dev: "tmpfs".to_owned(),
+            path: PathBuf::from(path),
+            fs_type: "tmpfs".to_owned(),
+            opts: "rw".to_owned(),
+            super_opts: "rw".to_owned(),
+        };
+        let entries = [mount(1, 0, "/"), mount(2, 1, "/tmp"), mount(3, 2, "/tmp")];
+        assert!(!is_overmounted(&entries[0], &entries));
+        assert!(is_overmounted(&entries[1], &entries));
+        assert!(!is_overmounted(&entries[2], &entries));
+    }
+
+    #[test]
+    fn test_is_unexpectedly_read_only() {
+        let entry = MountEntry {
+            id: 29,
+            parent_id: 1,
+            dev: "/dev/sda2".to_owned(),
+            path: PathBuf::from("/"),
+            fs_type: "ext4".to_owned(),
+            opts: "rw,relatime".to_owned(),
+            super_opts: "ro,errors=remount-ro".to_owned(),
+        };
+        assert!(is_unexpectedly_read_only(&entry));
+        assert!(!is_unexpectedly_read_only(&MountEntry {
+            super_opts: "rw,errors=remount-ro".to_owned(),
+            ..entry.clone()
+        }));
+        // Read-only bind mount, or mount made read-only by systemd ProtectSystem
+        assert!(!is_unexpectedly_read_only(&MountEntry {
+            opts: "ro,relatime".to_owned(),
+            super_opts: "rw,errors=remount-ro".to_owned(),
+            ..entry.clone()
+        }));
+        assert!(!is_unexpectedly_read_only(&MountEntry {
+            fs_type: "squashfs".to_owned(),
+            opts: "ro,nodev,relatime".to_owned(),
+            super_opts: "ro".to_owned(),
+            ..entry
+        }));
+    }
+
+    #[test]
+    #[serial]
+    fn test_output_fs_problem() {
+        TERM_COLUMNS.store(40, Ordering::SeqCst);
+        assert_eq!(
+            format!(
+                "{}",
+                FsInfo {
+                    layout: FsLayoutOptions::default(),
+                    mounts: vec![FsMountInfo { problem: Some(FsProblem::ReadOnly), ..mount("/", 500, 1000) }]
+                },
             ),
-            "▕███████\u{1b}[7m6.3 TB / 7.2 TB (87.3%)\u{1b}[0m███     ▏"
+            "\u{1b}[31m/ (ro)\u{1b}[0m \u{1b}[31m▕\u{1b}[0m\u{1b}[31m████\u{1b}[0m\u{1b}[7;31m500 B / 100\u{1b}[0m\u{1b}[31m0 B (50.0%)\u{1b}[0m\u{1b}[31m     \u{1b}[0m\u{1b}[31m▏\u{1b}[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_is_type_excluded() {
+        let cfg = config::FsConfig::default();
+        assert!(!is_type_excluded(&cfg, "ext4"));
+        assert!(is_type_excluded(&cfg, "squashfs"));
+
+        let cfg = config::FsConfig {
+            show_read_only_images: true,
+            mount_type_blacklist: vec![regex::Regex::new("^tmpfs$").unwrap()],
+            ..config::FsConfig::default()
+        };
+        assert!(!is_type_excluded(&cfg, "squashfs"));
+        assert!(is_type_excluded(&cfg, "tmpfs"));
+    }
+
+    #[test]
+    fn test_is_path_excluded() {
+        let cfg = config::FsConfig::default();
+        assert!(!is_path_excluded(&cfg, "/"));
+
+        let cfg = config::FsConfig {
+            mount_path_whitelist: vec![regex::Regex::new("^/(home|srv)?$").unwrap()],
+            mount_path_blacklist: vec![regex::Regex::new("^/srv$").unwrap()],
+            ..config::FsConfig::default()
+        };
+        assert!(!is_path_excluded(&cfg, "/"));
+        assert!(!is_path_excluded(&cfg, "/home"));
+        assert!(is_path_excluded(&cfg, "/srv"));
+        assert!(is_path_excluded(&cfg, "/var/lib/docker"));
+    }
+
+    #[test]
+    fn test_usage_thresholds() {
+        let cfg = config::FsConfig {
+            thresholds: vec![config::FsThresholds {
+                mount_path: regex::Regex::new("^/data$").unwrap(),
+                warning: 97.0,
+                critical: 99.0,
+            }],
+            ..config::FsConfig::default()
+        };
+        assert_eq!(usage_thresholds(&cfg, Path::new("/")), (85.0, 95.0));
+        assert_eq!(usage_thresholds(&cfg, Path::new("/data")), (97.0, 99.0));
+    }
+
+    #[test]
+    fn test_mount_alias() {
+        let aliases = [
+            config::FsAlias {
+                mount_path: regex::Regex::new("^/var/lib/docker$").unwrap(),
+                name: "Docker data".to_owned(),
+            },
+            config::FsAlias {
+                mount_path: regex::Regex::new("^/srv/([^/]+)$").unwrap(),
+                name: "Share $1".to_owned(),
+            },
+        ];
+        assert_eq!(
+            mount_alias(&aliases, Path::new("/var/lib/docker")),
+            Some("Docker data".to_owned())
+        );
+        assert_eq!(
+            mount_alias(&aliases, Path::new("/srv/media")),
+            Some("Share media".to_owned())
         );
+        assert_eq!(mount_alias(&aliases, Path::new("/var/lib")), None);
+    }
+
+    #[test]
+    fn test_get_fs_bar_reserved() {
         assert_eq!(
             get_fs_bar(
                 &FsMountInfo {
-                    mount_path: PathBuf::from("/foo/bar"),
-                    used_bytes: 7_891_011_000_000,
-                    total_bytes: 7_891_011_000_000
+                    reserved_bytes: 50,
+                    ..mount("/", 900, 1000)
                 },
                 40,
-                Style::new()
+                Style::new(),
+                config::FsDisplay::Used
             ),
-            "▕███████\u{1b}[7m7.2 TB / 7.2 TB (100.0%)\u{1b}[0m███████▏"
+            "▕████████\u{1b}[7m900 B / 1000 B (94.7%)\u{1b}[0m████\u{1b}[2m██\u{1b}[0m  ▏"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_output_fs_stalled() {
+        TERM_COLUMNS.store(40, Ordering::SeqCst);
+        assert_eq!(
+            format!(
+                "{}",
+                FsInfo {
+                    layout: FsLayoutOptions::default(),
+                    mounts: vec![
+                        FsMountInfo::without_usage(
+                            PathBuf::from("/mnt/nfs"),
+                            Some(FsProblem::Stalled)
+                        ),
+                        FsMountInfo::without_usage(PathBuf::from("/mnt/usb"), None),
+                    ]
+                },
+            ),
+            "\u{1b}[31m/mnt/nfs (stalled)\u{1b}[0m\n/mnt/usb\n"
         );
     }
 
     #[test]
-    fn test_ellipsis() {
-        assert_eq!(ellipsis("", 3), "…");
-        assert_eq!(ellipsis("", 4), "");
-        assert_eq!(ellipsis("", 5), "");
+    #[serial]
+    fn test_output_fs_layout() {
+        TERM_COLUMNS.store(40, Ordering::SeqCst);
+        let mounts = || vec![mount("/", 500, 1000), mount("/boot", 100, 1000)];
+
+        assert_eq!(
+            format!(
+                "{}",
+                FsInfo {
+                    mounts: mounts(),
+                    layout: FsLayoutOptions {
+                        layout: config::FsLayout::TwoLines,
+                        ..FsLayoutOptions::default()
+                    },
+                }
+            ),
+            "/\n▕████████\u{1b}[7m500 B / 100\u{1b}[0m0 B (50.0%)        ▏\n/boot\n▕███▊    100 B / 1000 B (10.0%)        ▏\n"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                FsInfo {
+                    mounts: mounts(),
+                    layout: FsLayoutOptions {
+                        align_bars: false,
+                        ..FsLayoutOptions::default()
+                    },
+                }
+            ),
+            "/ ▕███████\u{1b}[7m500 B / 100\u{1b}[0m0 B (50.0%)       ▏\n/boot ▕███▎ 100 B / 1000 B (10.0%)     ▏\n"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                FsInfo {
+                    mounts: mounts(),
+                    layout: FsLayoutOptions {
+                        label_width: Some(3),
+                        ..FsLayoutOptions::default()
+                    },
+                }
+            ),
+            "/   ▕██████\u{1b}[7m500 B / 100\u{1b}[0m0 B (50.0%)      ▏\n/b… ▕███▍  100 B / 1000 B (10.0%)      ▏\n"
+        );
+    }
+
+    #[test]
+    fn test_fill_history() {
+        let history = parse_fill_history("/\t2000\t300\n/data\t1000\t50\n/\t1000\t100\ninvalid\n");
+        assert_eq!(
+            history,
+            BTreeMap::from([
+                ("/".to_owned(), vec![(1000, 100), (2000, 300)]),
+                ("/data".to_owned(), vec![(1000, 50)]),
+            ])
+        );
+        assert_eq!(
+            serialize_fill_history(&history),
+            "/\t1000\t100\n/\t2000\t300\n/data\t1000\t50\n"
+        );
+    }
+
+    #[test]
+    fn test_estimate_days_until_full() {
+        let day = 24 * 60 * 60;
+        let mount_info = FsMountInfo {
+            reserved_bytes: 100,
+            ..mount("/", 600, 1000)
+        };
+        assert_eq!(estimate_days_until_full(&[], 10 * day, &mount_info), None);
+        assert_eq!(
+            estimate_days_until_full(&[(10 * day - 60, 500)], 10 * day, &mount_info),
+            None
+        );
+        assert_eq!(
+            estimate_days_until_full(&[(8 * day, 700)], 10 * day, &mount_info),
+            None
+        );
+        assert_eq!(
+            estimate_days_until_full(&[(8 * day, 500), (9 * day, 550)], 10 * day, &mount_info),
+            Some(6)
+        );
+    }
+
+    #[test]
+    fn test_get_fs_bar_available() {
+        assert_eq!(
+            get_fs_bar(
+                &mount("/", 343_597_383_680, 499_289_948_160),
+                40,
+                Style::new(),
+                config::FsDisplay::Available
+            ),
+            "▕██████\u{1b}[7m145.0 GB free of 465\u{1b}[0m.0 GB       ▏"
+        );
     }
 }
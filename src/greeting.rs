@@ -0,0 +1,200 @@
+//! Greeting line with user, hostname and previous login
+
+use std::{
+    env,
+    ffi::CStr,
+    fs, io, mem,
+    path::Path,
+    ptr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+
+use crate::{banner, config, fmt};
+
+/// Login records file
+const WTMP_FILEPATH: &str = "/var/log/wtmp";
+
+/// Size of a utmp record, on all glibc platforms
+const UTMP_RECORD_SIZE: usize = 384;
+
+/// utmp record type for user logins
+const USER_PROCESS: i16 = 7;
+
+/// Login session
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+struct Login {
+    /// Unix timestamp
+    time: u64,
+    /// Elapsed time, like "2d ago"
+    ago: String,
+    /// Terminal line
+    line: String,
+    /// Remote host, empty for local logins
+    host: String,
+}
+
+/// Values available to the greeting template
+#[derive(serde::Serialize)]
+struct GreetingContext {
+    user: String,
+    hostname: String,
+    last_login: Option<Login>,
+}
+
+/// Get NUL terminated string from a fixed size field
+fn c_field(field: &[u8]) -> String {
+    let len = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..len]).into_owned()
+}
+
+/// Get logins of a user from utmp records, oldest first
+fn user_logins(records: &[u8], user: &str) -> Vec<(u64, String, String)> {
+    records
+        .chunks_exact(UTMP_RECORD_SIZE)
+        .filter(|r| i16::from_ne_bytes([r[0], r[1]]) == USER_PROCESS)
+        .filter(|r| c_field(&r[44..76]) == user)
+        .map(|r| {
+            let time = u32::from_ne_bytes([r[340], r[341], r[342], r[343]]);
+            (u64::from(time), c_field(&r[8..40]), c_field(&r[76..332]))
+        })
+        .collect()
+}
+
+/// Format elapsed time with the largest unit
+fn format_ago(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs >= 60 {
+        format!("{} ago", fmt::format_duration(secs))
+    } else {
+        "just now".to_owned()
+    }
+}
+
+/// Get previous login of a user, the most recent one being the current session
+fn previous_login(wtmp_filepath: &Path, user: &str) -> anyhow::Result<Option<Login>> {
+    let records = match fs::read(wtmp_filepath) {
+        Ok(records) => records,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to read {}", wtmp_filepath.display()))
+        }
+    };
+    let logins = user_logins(&records, user);
+    let Some((time, line, host)) = logins.into_iter().rev().nth(1) else {
+        return Ok(None);
+    };
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH + Duration::from_secs(time))
+        .unwrap_or_default();
+    Ok(Some(Login {
+        time,
+        ago: format_ago(elapsed),
+        line,
+        host,
+    }))
+}
+
+/// Get user name from passwd database
+fn passwd_user(uid: libc::uid_t) -> Option<String> {
+    // SAFETY: libc call arg
+    let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut result = ptr::null_mut();
+    // SAFETY: libc call, buffer size is passed
+    let rc = unsafe {
+        libc::getpwuid_r(
+            uid,
+            &raw mut passwd,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &raw mut result,
+        )
+    };
+    if rc != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: name is a NUL terminated string in buf, if call succeeded
+    let name = unsafe { CStr::from_ptr(passwd.pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Get invoking user name
+fn user() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("LOGNAME"))
+        .ok()
+        .filter(|u| !u.is_empty())
+        .unwrap_or_else(|| {
+            // SAFETY: libc call without arguments, that can not fail
+            let uid = unsafe { libc::getuid() };
+            passwd_user(uid).unwrap_or_else(|| uid.to_string())
+        })
+}
+
+/// Render greeting line
+pub(crate) fn render(cfg: &config::GreetingConfig) -> anyhow::Result<String> {
+    let user = user();
+    let context = GreetingContext {
+        last_login: previous_login(Path::new(WTMP_FILEPATH), &user)?,
+        user,
+        hostname: banner::hostname()?,
+    };
+    let env = minijinja::Environment::new();
+    let line = env
+        .render_str(&cfg.template, context)
+        .context("Failed to render greeting template")?;
+    Ok(format!("{}\n", line.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    /// Build a utmp record
+    fn record(kind: i16, user: &str, host: &str, time: u32) -> Vec<u8> {
+        let mut r = vec![0; UTMP_RECORD_SIZE];
+        r[0..2].copy_from_slice(&kind.to_ne_bytes());
+        r[8..13].copy_from_slice(b"pts/0");
+        r[44..44 + user.len()].copy_from_slice(user.as_bytes());
+        r[76..76 + host.len()].copy_from_slice(host.as_bytes());
+        r[340..344].copy_from_slice(&time.to_ne_bytes());
+        r
+    }
+
+    #[test]
+    fn test_previous_login() {
+        let filepath = env::temp_dir().join(format!("motd-wtmp-test-{}", process::id()));
+        let records = [
+            record(USER_PROCESS, "alice", "10.0.0.4", 1000),
+            record(USER_PROCESS, "bob", "10.0.0.9", 2000),
+            record(8, "alice", "", 2500),
+            record(USER_PROCESS, "alice", "10.0.0.5", 3000),
+            record(USER_PROCESS, "alice", "", 4000),
+        ]
+        .concat();
+        fs::write(&filepath, records).unwrap();
+
+        let login = previous_login(&filepath, "alice").unwrap().unwrap();
+        assert_eq!(login.time, 3000);
+        assert_eq!(login.line, "pts/0");
+        assert_eq!(login.host, "10.0.0.5");
+        assert!(previous_login(&filepath, "bob").unwrap().is_none());
+        assert!(previous_login(&filepath.with_extension("missing"), "alice")
+            .unwrap()
+            .is_none());
+
+        fs::remove_file(&filepath).unwrap();
+    }
+
+    #[test]
+    fn test_format_ago() {
+        assert_eq!(format_ago(Duration::from_secs(59)), "just now");
+        assert_eq!(format_ago(Duration::from_mins(5)), "5min ago");
+        assert_eq!(format_ago(Duration::from_hours(3)), "3h ago");
+        assert_eq!(format_ago(Duration::from_hours(48)), "2d ago");
+    }
+}
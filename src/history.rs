@@ -0,0 +1,503 @@
+//! Recent metric samples stored between runs, displayed as sparklines, and long term metric store
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    io::{self, Read as _, Seek as _, SeekFrom, Write as _},
+    path::PathBuf,
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context as _;
+
+use crate::{cache, config, module::ModuleData, prometheus, style::Severity};
+
+/// Sparkline glyphs, from lowest to highest value
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Minimum count of recorded samples of a metric to compute its baseline
+const BASELINE_MIN_SAMPLES: usize = 12;
+
+/// Length of the most recent part of the metric store that baselines are computed from, so that
+/// computing them does not get slower as the store grows
+const BASELINE_WINDOW_LEN: u64 = 4 * 1024 * 1024;
+
+/// Interval between removals of expired metric store records, in watch and daemon modes
+pub(crate) const PRUNE_INTERVAL: Duration = Duration::from_hours(1);
+
+/// Marker starting each metric store record
+const RECORD_MAGIC: [u8; 2] = [0xa5, b'M'];
+
+/// Length of metric store record fields before the key: magic, key length, timestamp and value
+const RECORD_HEADER_LEN: usize = 20;
+
+/// Length of metric store record checksum, after the key
+const RECORD_CHECKSUM_LEN: usize = 4;
+
+/// Get state filepath for a group of metrics
+fn state_filepath(group: &str, cfg: &config::HistoryConfig) -> anyhow::Result<PathBuf> {
+    let filename = format!("history_{group}");
+    if let Some(state_dir) = cfg.state_dir.as_ref() {
+        return Ok(state_dir.join(filename));
+    }
+    let binary_name = env!("CARGO_PKG_NAME");
+    let xdg_dirs = xdg::BaseDirectories::with_prefix(binary_name)?;
+    Ok(xdg_dirs.place_state_file(filename)?)
+}
+
+/// Get current Unix timestamp
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Parse history state, made of tab separated timestamp, metric key and value lines
+fn parse_state(state: &str) -> Vec<(u64, &str, f32)> {
+    state
+        .lines()
+        .filter_map(|l| {
+            let mut tokens = l.split('\t');
+            let ts = tokens.next()?.parse().ok()?;
+            let key = tokens.next()?;
+            let val = tokens.next()?.parse().ok()?;
+            Some((ts, key, val))
+        })
+        .collect()
+}
+
+/// Merge current samples into stored ones, and get recent values of each metric, oldest first
+///
+/// Samples are only stored if the minimum interval has elapsed since the last stored ones,
+/// but current values are always the last returned ones.
+fn merge_samples(
+    state: &str,
+    samples: &[(&str, f32)],
+    now: u64,
+    cfg: &config::HistoryConfig,
+) -> (String, BTreeMap<String, Vec<f32>>) {
+    let mut stored = parse_state(state);
+    let last_ts = stored.iter().map(|(ts, _, _)| *ts).max();
+    let store_current =
+        last_ts.is_none_or(|ts| now.saturating_sub(ts) >= cfg.min_interval_minutes * 60);
+    if store_current {
+        stored.extend(samples.iter().map(|(k, v)| (now, *k, *v)));
+    }
+
+    // Only keep the most recent samples of current metrics
+    let mut values: BTreeMap<String, Vec<(u64, f32)>> = BTreeMap::new();
+    for (ts, key, val) in stored {
+        if samples.iter().any(|(k, _)| *k == key) {
+            values.entry(key.to_owned()).or_default().push((ts, val));
+        }
+    }
+    let mut new_state = String::new();
+    for (key, key_values) in &mut values {
+        let drop_count = key_values.len().saturating_sub(cfg.samples);
+        key_values.drain(..drop_count);
+        for (ts, val) in key_values.iter() {
+            let _ = writeln!(new_state, "{ts}\t{key}\t{val}");
+        }
+    }
+
+    let mut recent: BTreeMap<String, Vec<f32>> = values
+        .into_iter()
+        .map(|(k, v)| (k, v.into_iter().map(|(_, val)| val).collect()))
+        .collect();
+    if !store_current {
+        for (key, val) in samples {
+            let key_values = recent.entry((*key).to_owned()).or_default();
+            if key_values.len() >= cfg.samples {
+                key_values.remove(0);
+            }
+            key_values.push(*val);
+        }
+    }
+    (new_state, recent)
+}
+
+/// Store current samples of a group of metrics, and get their recent values, oldest first
+pub(crate) fn update(
+    group: &str,
+    samples: &[(&str, f32)],
+    cfg: &config::HistoryConfig,
+) -> BTreeMap<String, Vec<f32>> {
+    let Ok(filepath) = state_filepath(group, cfg) else {
+        return BTreeMap::new();
+    };
+    let state = fs::read_to_string(&filepath).unwrap_or_default();
+    let (new_state, recent) = merge_samples(&state, samples, now(), cfg);
+    // Failing to save history is not worth failing the whole section
+    let _ = fs::write(&filepath, new_state);
+    recent
+}
+
+/// Draw values as a sparkline, scaled from 0 to the maximum value
+pub(crate) fn sparkline(values: &[f32]) -> String {
+    let max = values.iter().copied().fold(0.0, f32::max);
+    values
+        .iter()
+        .map(|v| {
+            let level = if max > 0.0 {
+                (v / max * (SPARK_CHARS.len() - 1) as f32).round() as usize
+            } else {
+                0
+            };
+            SPARK_CHARS[level.min(SPARK_CHARS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Sample of the metric store
+#[derive(Debug, PartialEq)]
+pub(crate) struct Record {
+    /// Unix timestamp
+    pub ts: u64,
+    /// Metric name, with Prometheus labels
+    pub key: String,
+    /// Metric value
+    pub value: f64,
+}
+
+/// Append only binary log of all metric samples, recorded in watch and daemon modes
+///
+/// Each record is made of a magic marker, a little endian u16 key length, u64 timestamp, f64 value,
+/// the key bytes, and a u32 checksum of all of them, so that reading can resume at the next record
+/// after a corrupt or truncated one.
+pub(crate) struct Store {
+    /// Log file path
+    filepath: PathBuf,
+}
+
+impl Store {
+    /// Open store in the history state directory
+    pub(crate) fn open(cfg: &config::HistoryConfig) -> anyhow::Result<Self> {
+        let filepath = state_filepath("store", cfg)?;
+        Ok(Self { filepath })
+    }
+
+    /// Append samples of all metrics of a module
+    pub(crate) fn record(&self, data: &ModuleData, ts: u64) -> anyhow::Result<()> {
+        let mut metrics = String::new();
+        data.write_prometheus(&mut metrics)?;
+        let mut buf = Vec::new();
+        for (key, value) in prometheus::parse_samples(&metrics) {
+            encode_record(&mut buf, ts, key, value);
+        }
+        // Single write so that concurrent appends from other sections do not interleave
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.filepath)
+            .and_then(|mut f| f.write_all(&buf))
+            .with_context(|| format!("Failed to write {}", self.filepath.display()))
+    }
+
+    /// Read all records, oldest first
+    pub(crate) fn read(&self) -> anyhow::Result<Vec<Record>> {
+        match fs::read(&self.filepath) {
+            Ok(data) => Ok(decode_records(&data)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => {
+                Err(err).with_context(|| format!("Failed to read {}", self.filepath.display()))
+            }
+        }
+    }
+
+    /// Read records of the last bytes of the store, oldest first
+    #[expect(clippy::verbose_file_reads)]
+    fn read_recent(&self, max_len: u64) -> anyhow::Result<Vec<Record>> {
+        let mut file = match fs::File::open(&self.filepath) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to open {}", self.filepath.display()));
+            }
+        };
+        let mut data = Vec::new();
+        file.seek(SeekFrom::End(0))
+            .and_then(|len| file.seek(SeekFrom::Start(len.saturating_sub(max_len))))
+            .and_then(|_| file.read_to_end(&mut data))
+            .with_context(|| format!("Failed to read {}", self.filepath.display()))?;
+        // The first record may be cut, and is skipped like a corrupt one
+        Ok(decode_records(&data))
+    }
+
+    /// Get samples of a metric more recent than a timestamp, oldest first
+    pub(crate) fn query(&self, key: &str, since: u64) -> anyhow::Result<Vec<(u64, f64)>> {
+        Ok(self
+            .read()?
+            .into_iter()
+            .filter(|r| (r.key == key) && (r.ts >= since))
+            .map(|r| (r.ts, r.value))
+            .collect())
+    }
+
+    /// Compute baseline of each metric, from the most recent recorded samples
+    pub(crate) fn baselines(&self, factor: f64) -> anyhow::Result<Baselines> {
+        let mut values: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+        for record in self.read_recent(BASELINE_WINDOW_LEN)? {
+            values.entry(record.key).or_default().push(record.value);
+        }
+        let medians = values
+            .into_iter()
+            .filter(|(_, v)| v.len() >= BASELINE_MIN_SAMPLES)
+            .map(|(k, mut v)| {
+                v.sort_unstable_by(f64::total_cmp);
+                (k, v[v.len() / 2])
+            })
+            .collect();
+        Ok(Baselines { medians, factor })
+    }
+
+    /// Remove records older than a timestamp
+    pub(crate) fn prune(&self, before: u64) -> anyhow::Result<()> {
+        let records = self.read()?;
+        if records.first().is_none_or(|r| r.ts >= before) {
+            return Ok(());
+        }
+        let mut buf = Vec::new();
+        for record in records.iter().filter(|r| r.ts >= before) {
+            encode_record(&mut buf, record.ts, &record.key, record.value);
+        }
+        cache::write_atomic(&self.filepath, &buf)
+            .with_context(|| format!("Failed to write {}", self.filepath.display()))
+    }
+}
+
+/// Usual values of metrics on this host
+#[derive(Debug)]
+pub(crate) struct Baselines {
+    /// Median value of each metric
+    medians: BTreeMap<String, f64>,
+    /// Ratio to the median above which a value is unusual
+    factor: f64,
+}
+
+impl Baselines {
+    /// Whether a metric value is unusually high compared to its median
+    fn is_anomaly(&self, key: &str, value: f64) -> bool {
+        self.medians
+            .get(key)
+            .is_some_and(|m| (*m > 0.0) && (value >= m * self.factor))
+    }
+}
+
+/// Global metric baselines, set once at startup if history is available
+static BASELINES: OnceLock<Baselines> = OnceLock::new();
+
+/// Set global metric baselines
+pub(crate) fn set_baselines(baselines: Baselines) {
+    let _ = BASELINES.set(baselines);
+}
+
+/// Raise severity of a normal metric value to warning if it is unusually high for this host
+pub(crate) fn baseline_severity(severity: Severity, key: &str, value: f64) -> Severity {
+    if (severity == Severity::Ok) && BASELINES.get().is_some_and(|b| b.is_anomaly(key, value)) {
+        Severity::Warning
+    } else {
+        severity
+    }
+}
+
+/// FNV-1a hash of record bytes
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c_9dc5, |hash, b| {
+        (hash ^ u32::from(*b)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Append binary record to a buffer
+fn encode_record(buf: &mut Vec<u8>, ts: u64, key: &str, value: f64) {
+    let key = &key.as_bytes()[..key.len().min(u16::MAX.into())];
+    let start = buf.len();
+    buf.extend_from_slice(&RECORD_MAGIC);
+    buf.extend_from_slice(&(key.len() as u16).to_le_bytes());
+    buf.extend_from_slice(&ts.to_le_bytes());
+    buf.extend_from_slice(&value.to_le_bytes());
+    buf.extend_from_slice(key);
+    let sum = checksum(&buf[start..]);
+    buf.extend_from_slice(&sum.to_le_bytes());
+}
+
+/// Decode binary record at the start of data, and get its length, if it is valid
+fn decode_record(data: &[u8]) -> Option<(Record, usize)> {
+    let header = data.first_chunk::<RECORD_HEADER_LEN>()?;
+    if header[..2] != RECORD_MAGIC {
+        return None;
+    }
+    let key_len = usize::from(u16::from_le_bytes([header[2], header[3]]));
+    let len = RECORD_HEADER_LEN + key_len;
+    let sum = data.get(len..len + RECORD_CHECKSUM_LEN)?;
+    if checksum(&data[..len]).to_le_bytes() != sum {
+        return None;
+    }
+    let record = Record {
+        ts: u64::from_le_bytes(header[4..12].try_into().unwrap()),
+        value: f64::from_le_bytes(header[12..20].try_into().unwrap()),
+        key: String::from_utf8_lossy(&data[RECORD_HEADER_LEN..len]).into_owned(),
+    };
+    Some((record, len + RECORD_CHECKSUM_LEN))
+}
+
+/// Decode binary records, skipping corrupt or truncated ones up to the next valid record
+fn decode_records(mut data: &[u8]) -> Vec<Record> {
+    let mut records = Vec::new();
+    while !data.is_empty() {
+        if let Some((record, len)) = decode_record(data) {
+            records.push(record);
+            data = &data[len..];
+        } else {
+            // Resume at the next marker
+            let next = data
+                .windows(RECORD_MAGIC.len())
+                .skip(1)
+                .position(|w| w == RECORD_MAGIC)
+                .map_or(data.len(), |i| i + 1);
+            data = &data[next..];
+        }
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[0.0, 0.0]), "▁▁");
+        assert_eq!(sparkline(&[0.0, 1.0, 3.5, 7.0]), "▁▂▅█");
+    }
+
+    #[test]
+    fn test_merge_samples() {
+        let cfg = config::HistoryConfig {
+            sparklines: true,
+            samples: 3,
+            min_interval_minutes: 60,
+            ..config::HistoryConfig::default()
+        };
+
+        let (state, recent) = merge_samples("", &[("load", 1.0)], 1000, &cfg);
+        assert_eq!(state, "1000\tload\t1\n");
+        assert_eq!(recent["load"], vec![1.0]);
+
+        // Too early to store, but current value is returned
+        let (early_state, early_recent) = merge_samples(&state, &[("load", 2.0)], 2000, &cfg);
+        assert_eq!(early_state, "1000\tload\t1\n");
+        assert_eq!(early_recent["load"], vec![1.0, 2.0]);
+
+        let full_state = "1000\tload\t1\n1000\tgone\t5\n4600\tload\t2\n8200\tload\t3\n";
+        let (trimmed_state, trimmed_recent) =
+            merge_samples(full_state, &[("load", 4.0)], 11800, &cfg);
+        assert_eq!(
+            trimmed_state,
+            "4600\tload\t2\n8200\tload\t3\n11800\tload\t4\n"
+        );
+        assert_eq!(trimmed_recent["load"], vec![2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_baselines() {
+        let mut medians = BTreeMap::new();
+        medians.insert("motd_load_average{period=\"1m\"}".to_owned(), 0.5);
+        medians.insert("motd_tasks".to_owned(), 0.0);
+        let baselines = Baselines {
+            medians,
+            factor: 5.0,
+        };
+        assert!(!baselines.is_anomaly("motd_load_average{period=\"1m\"}", 2.4));
+        assert!(baselines.is_anomaly("motd_load_average{period=\"1m\"}", 2.5));
+        assert!(!baselines.is_anomaly("motd_load_average{period=\"5m\"}", 10.0));
+        assert!(!baselines.is_anomaly("motd_tasks", 10.0));
+    }
+
+    #[test]
+    fn test_store() {
+        let dir = env::temp_dir().join(format!("motd-store-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cfg = config::HistoryConfig {
+            state_dir: Some(dir.clone()),
+            ..config::HistoryConfig::default()
+        };
+        let store = Store::open(&cfg).unwrap();
+        assert!(store.read().unwrap().is_empty());
+
+        let mut buf = Vec::new();
+        encode_record(&mut buf, 1000, "motd_tasks", 12.0);
+        encode_record(&mut buf, 2000, "motd_load_average{period=\"1m\"}", 0.5);
+        encode_record(&mut buf, 3000, "motd_tasks", 14.0);
+        // Truncated record, from an interrupted write
+        buf.extend_from_slice(&[1, 2, 3]);
+        fs::write(dir.join("history_store"), &buf).unwrap();
+
+        assert_eq!(store.read().unwrap().len(), 3);
+        assert_eq!(
+            store.query("motd_tasks", 0).unwrap(),
+            vec![(1000, 12.0), (3000, 14.0)]
+        );
+        assert_eq!(store.query("motd_tasks", 2000).unwrap(), vec![(3000, 14.0)]);
+
+        let mut last = Vec::new();
+        encode_record(&mut last, 3000, "motd_tasks", 14.0);
+        let recent = store.read_recent(last.len() as u64 + 3 + 5).unwrap();
+        assert_eq!(recent.iter().map(|r| r.ts).collect::<Vec<_>>(), vec![3000]);
+        assert_eq!(store.read_recent(u64::MAX).unwrap().len(), 3);
+
+        let baselines = store.baselines(5.0).unwrap();
+        assert!(baselines.medians.is_empty());
+        assert!((baselines.factor - 5.0).abs() < f64::EPSILON);
+
+        store.prune(2000).unwrap();
+        assert_eq!(
+            store.read().unwrap(),
+            vec![
+                Record {
+                    ts: 2000,
+                    key: "motd_load_average{period=\"1m\"}".to_owned(),
+                    value: 0.5
+                },
+                Record {
+                    ts: 3000,
+                    key: "motd_tasks".to_owned(),
+                    value: 14.0
+                }
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_decode_records() {
+        let mut buf = Vec::new();
+        encode_record(&mut buf, 1000, "motd_tasks", 12.0);
+        let mut truncated = Vec::new();
+        encode_record(&mut truncated, 2000, "motd_tasks", 13.0);
+        // Record cut in the middle of its key, followed by later appends
+        buf.extend_from_slice(&truncated[..RECORD_HEADER_LEN + 3]);
+        encode_record(&mut buf, 3000, "motd_tasks", 14.0);
+        // Garbage and partial marker
+        buf.extend_from_slice(&[0, RECORD_MAGIC[0], RECORD_MAGIC[1], 7]);
+        encode_record(&mut buf, 4000, "motd_tasks", 15.0);
+        // Truncated last record
+        buf.extend_from_slice(&truncated[..5]);
+
+        assert_eq!(
+            decode_records(&buf)
+                .into_iter()
+                .map(|r| (r.ts, r.value))
+                .collect::<Vec<_>>(),
+            vec![(1000, 12.0), (3000, 14.0), (4000, 15.0)]
+        );
+        assert!(decode_records(&[]).is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+//! Localized section titles and labels
+
+use std::{env, sync::RwLock};
+
+/// Supported locales
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Locale {
+    /// English
+    #[default]
+    En,
+    /// French
+    Fr,
+    /// German
+    De,
+    /// Spanish
+    Es,
+}
+
+impl Locale {
+    /// Get locale from a POSIX locale name like `fr_FR.UTF-8`, `None` if unsupported
+    fn from_posix(name: &str) -> Option<Self> {
+        let lang = name.split(['_', '.', '@']).next().unwrap_or_default();
+        match lang {
+            "en" => Some(Self::En),
+            "fr" => Some(Self::Fr),
+            "de" => Some(Self::De),
+            "es" => Some(Self::Es),
+            _ => None,
+        }
+    }
+}
+
+/// Text displayed to the user
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Label {
+    /// Load section title
+    LoadTitle,
+    /// Memory section title
+    MemoryTitle,
+    /// Swap section title
+    SwapTitle,
+    /// Filesystem section title
+    FsTitle,
+    /// Temperature section title
+    TempTitle,
+    /// Network section title
+    NetTitle,
+    /// Systemd failed units section title
+    SystemdTitle,
+    /// Admin messages section title
+    MessageTitle,
+    /// Load averages line prefix
+    LoadAvg,
+    /// Task count
+    Tasks,
+    /// Used memory or swap
+    Used,
+    /// Cached memory
+    Cached,
+    /// Free memory
+    Free,
+    /// Free swap
+    SwapFree,
+    /// Systemd system units
+    System,
+    /// Systemd user units
+    User,
+    /// Exit status of failed units
+    ExitStatus,
+    /// Active units count
+    Active,
+    /// Failed units count
+    Failed,
+    /// Loaded units count
+    Units,
+    /// Deleted shared libraries of services to restart
+    DeletedLibraries,
+    /// Network connectivity
+    Connectivity,
+}
+
+/// Global locale, set at startup and replaced on reload
+static LOCALE: RwLock<Option<Locale>> = RwLock::new(None);
+
+/// Set global locale
+pub(crate) fn set_locale(locale: Locale) {
+    *LOCALE.write().unwrap() = Some(locale);
+}
+
+/// Get locale from `LC_ALL`, `LC_MESSAGES` or `LANG` environment variables, English if unset or unsupported
+pub(crate) fn env_locale() -> Locale {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|v| env::var(v).ok())
+        .find(|v| !v.is_empty())
+        .and_then(|v| Locale::from_posix(&v))
+        .unwrap_or_default()
+}
+
+/// Get label text in global locale
+pub(crate) fn tr(label: Label) -> &'static str {
+    translate(LOCALE.read().unwrap().unwrap_or_default(), label)
+}
+
+/// Get label text in a locale
+fn translate(locale: Locale, label: Label) -> &'static str {
+    match locale {
+        Locale::En => match label {
+            Label::LoadTitle => "Load",
+            Label::MemoryTitle => "Memory usage",
+            Label::SwapTitle => "Swap usage",
+            Label::FsTitle => "Filesystem usage",
+            Label::TempTitle => "Hardware temperatures",
+            Label::NetTitle => "Network",
+            Label::SystemdTitle => "Systemd failed units",
+            Label::MessageTitle => "Messages",
+            Label::LoadAvg => "Load avg",
+            Label::Tasks => "Tasks",
+            Label::Used => "Used",
+            Label::Cached => "Cached",
+            Label::Free => "Free",
+            Label::SwapFree => "Swap free",
+            Label::System => "System",
+            Label::User => "User",
+            Label::ExitStatus => "exit status",
+            Label::Active => "active",
+            Label::Failed => "failed",
+            Label::Units => "units",
+            Label::DeletedLibraries => "deleted libraries",
+            Label::Connectivity => "Connectivity",
+        },
+        Locale::Fr => match label {
+            Label::LoadTitle => "Charge",
+            Label::MemoryTitle => "Utilisation mémoire",
+            Label::SwapTitle => "Utilisation swap",
+            Label::FsTitle => "Utilisation des systèmes de fichiers",
+            Label::TempTitle => "Températures matérielles",
+            Label::NetTitle => "Réseau",
+            Label::SystemdTitle => "Unités Systemd en échec",
+            Label::MessageTitle => "Messages",
+            Label::LoadAvg => "Charge moy.",
+            Label::Tasks => "Tâches",
+            Label::Used => "Utilisé",
+            Label::Cached => "Cache",
+            Label::Free => "Libre",
+            Label::SwapFree => "Swap libre",
+            Label::System => "Système",
+            Label::User => "Utilisateur",
+            Label::ExitStatus => "code de sortie",
+            Label::Active => "actives",
+            Label::Failed => "en échec",
+            Label::Units => "unités",
+            Label::DeletedLibraries => "bibliothèques supprimées",
+            Label::Connectivity => "Connectivité",
+        },
+        Locale::De => match label {
+            Label::LoadTitle => "Last",
+            Label::MemoryTitle => "Speichernutzung",
+            Label::SwapTitle => "Swap-Nutzung",
+            Label::FsTitle => "Dateisystemnutzung",
+            Label::TempTitle => "Hardwaretemperaturen",
+            Label::NetTitle => "Netzwerk",
+            Label::SystemdTitle => "Fehlgeschlagene Systemd-Units",
+            Label::MessageTitle => "Mitteilungen",
+            Label::LoadAvg => "Durchschn. Last",
+            Label::Tasks => "Tasks",
+            Label::Used => "Belegt",
+            Label::Cached => "Cache",
+            Label::Free => "Frei",
+            Label::SwapFree => "Swap frei",
+            Label::System => "System",
+            Label::User => "Benutzer",
+            Label::ExitStatus => "Exit-Status",
+            Label::Active => "aktiv",
+            Label::Failed => "fehlgeschlagen",
+            Label::Units => "Units",
+            Label::DeletedLibraries => "gelöschte Bibliotheken",
+            Label::Connectivity => "Konnektivität",
+        },
+        Locale::Es => match label {
+            Label::LoadTitle => "Carga",
+            Label::MemoryTitle => "Uso de memoria",
+            Label::SwapTitle => "Uso de swap",
+            Label::FsTitle => "Uso de sistemas de archivos",
+            Label::TempTitle => "Temperaturas del hardware",
+            Label::NetTitle => "Red",
+            Label::SystemdTitle => "Unidades Systemd fallidas",
+            Label::MessageTitle => "Mensajes",
+            Label::LoadAvg => "Carga media",
+            Label::Tasks => "Tareas",
+            Label::Used => "Usado",
+            Label::Cached => "Caché",
+            Label::Free => "Libre",
+            Label::SwapFree => "Swap libre",
+            Label::System => "Sistema",
+            Label::User => "Usuario",
+            Label::ExitStatus => "código de salida",
+            Label::Active => "activas",
+            Label::Failed => "fallidas",
+            Label::Units => "unidades",
+            Label::DeletedLibraries => "bibliotecas eliminadas",
+            Label::Connectivity => "Conectividad",
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_posix() {
+        assert_eq!(Locale::from_posix("fr_FR.UTF-8"), Some(Locale::Fr));
+        assert_eq!(Locale::from_posix("de_DE@euro"), Some(Locale::De));
+        assert_eq!(Locale::from_posix("es"), Some(Locale::Es));
+        assert_eq!(Locale::from_posix("C.UTF-8"), None);
+        assert_eq!(Locale::from_posix(""), None);
+    }
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(translate(Locale::En, Label::Free), "Free");
+        assert_eq!(translate(Locale::Fr, Label::Free), "Libre");
+        assert_eq!(tr(Label::Tasks), "Tasks");
+    }
+}
@@ -0,0 +1,71 @@
+//! Nerd Font icons
+
+use std::sync::RwLock;
+
+use crate::config::IconsConfig;
+
+/// Icon, prefixing section titles and entries
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Icon {
+    Load,
+    Memory,
+    Swap,
+    Disk,
+    Thermometer,
+    Network,
+    Ethernet,
+    Wifi,
+    Systemd,
+    Message,
+    Plugin,
+}
+
+impl Icon {
+    /// Nerd Font glyph
+    fn glyph(self) -> &'static str {
+        match self {
+            Self::Load => "\u{f04c5}",
+            Self::Memory => "\u{f035b}",
+            Self::Swap => "\u{f04e1}",
+            Self::Disk => "\u{f02ca}",
+            Self::Thermometer => "\u{f050f}",
+            Self::Network => "\u{f06f3}",
+            Self::Ethernet => "\u{f0200}",
+            Self::Wifi => "\u{f05a9}",
+            Self::Systemd => "\u{f0028}",
+            Self::Message => "\u{f0369}",
+            Self::Plugin => "\u{f0431}",
+        }
+    }
+
+    /// Whether the section this icon belongs to has icons enabled
+    fn is_enabled(self, cfg: IconsConfig) -> bool {
+        match self {
+            Self::Load => cfg.load,
+            Self::Memory => cfg.memory,
+            Self::Swap => cfg.swap,
+            Self::Disk => cfg.fs,
+            Self::Thermometer => cfg.temp,
+            Self::Network | Self::Ethernet | Self::Wifi => cfg.net,
+            Self::Systemd => cfg.systemd,
+            Self::Message => cfg.message,
+            Self::Plugin => cfg.plugin,
+        }
+    }
+}
+
+/// Global icons config, set at startup and replaced on reload
+static ICONS: RwLock<Option<IconsConfig>> = RwLock::new(None);
+
+/// Set global icons config
+pub(crate) fn set_config(cfg: IconsConfig) {
+    *ICONS.write().unwrap() = Some(cfg);
+}
+
+/// Get prefix for icon, empty if icons are disabled for its section
+pub(crate) fn prefix(icon: Icon) -> String {
+    match *ICONS.read().unwrap() {
+        Some(cfg) if icon.is_enabled(cfg) => format!("{} ", icon.glyph()),
+        _ => String::new(),
+    }
+}
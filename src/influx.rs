@@ -0,0 +1,89 @@
+//! Influx line protocol output, for the Telegraf exec input
+
+use std::fmt;
+
+use crate::prometheus;
+
+/// Write a line for each sample of metrics in Prometheus text format, with timestamp in seconds
+pub(crate) fn write_samples(f: &mut dyn fmt::Write, metrics: &str, ts: u64) -> fmt::Result {
+    for (key, value) in prometheus::parse_samples(metrics) {
+        let (name, labels) = match key.split_once('{') {
+            Some((name, labels)) => (name, labels.strip_suffix('}').unwrap_or(labels)),
+            None => (key, ""),
+        };
+        write!(f, "{}", escape(name, &[',', ' ']))?;
+        for (label, label_val) in parse_labels(labels) {
+            // Empty tag values are invalid in line protocol
+            if !label_val.is_empty() {
+                write!(
+                    f,
+                    ",{}={}",
+                    escape(label, &[',', '=', ' ']),
+                    escape(&label_val, &[',', '=', ' '])
+                )?;
+            }
+        }
+        writeln!(f, " value={value} {}", u128::from(ts) * 1_000_000_000)?;
+    }
+    Ok(())
+}
+
+/// Parse Prometheus labels, like `a="x",b="y \"z\""`, unescaping values
+fn parse_labels(labels: &str) -> Vec<(&str, String)> {
+    let mut parsed = Vec::new();
+    let mut rest = labels;
+    while let Some((label, after)) = rest.split_once("=\"") {
+        let mut val = String::new();
+        let mut chars = after.char_indices();
+        let mut end = after.len();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => val.push('\n'),
+                    Some((_, e)) => val.push(e),
+                    None => {}
+                },
+                '"' => {
+                    end = i + 1;
+                    break;
+                }
+                c => val.push(c),
+            }
+        }
+        parsed.push((label.trim_start_matches(','), val));
+        rest = after.get(end..).unwrap_or_default();
+    }
+    parsed
+}
+
+/// Escape characters with a backslash
+fn escape(s: &str, chars: &[char]) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '\\' || chars.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(if c == '\n' { ' ' } else { c });
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_samples() {
+        let mut s = String::new();
+        write_samples(
+            &mut s,
+            "# HELP motd_tasks Task count\n# TYPE motd_tasks gauge\nmotd_tasks 12\nmotd_filesystem_used_bytes{mountpoint=\"/mnt/a b,\\\"c\\\"\",device=\"\"} 1000\nmotd_load_average{period=\"1m\",x=\"y\"} 0.5\n",
+            1000,
+        )
+        .unwrap();
+        assert_eq!(
+            s,
+            "motd_tasks value=12 1000000000000\nmotd_filesystem_used_bytes,mountpoint=/mnt/a\\ b\\,\"c\" value=1000 1000000000000\nmotd_load_average,period=1m,x=y value=0.5 1000000000000\n"
+        );
+    }
+}
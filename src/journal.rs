@@ -0,0 +1,95 @@
+//! Structured log entries, sent to journald with its native protocol, or to syslog
+
+use std::{io, os::unix::net::UnixDatagram, process};
+
+use crate::{config::LogTarget, style::Severity};
+
+/// Journald native protocol socket
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Syslog socket
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Syslog facility for system daemons
+const SYSLOG_FACILITY_DAEMON: u8 = 3;
+
+/// Identifier of log entries
+const IDENTIFIER: &str = "motd";
+
+/// Get syslog priority of severity
+fn priority(severity: Severity) -> u8 {
+    match severity {
+        Severity::Ok => 5,
+        Severity::Warning => 4,
+        Severity::Critical => 2,
+    }
+}
+
+/// Send log entry, with extra fields for journald
+pub(crate) fn send(
+    target: LogTarget,
+    severity: Severity,
+    message: &str,
+    fields: &[(&str, &str)],
+) -> io::Result<()> {
+    let (socket_path, payload) = match target {
+        LogTarget::Journald => (JOURNALD_SOCKET, journald_payload(severity, message, fields)),
+        LogTarget::Syslog => (SYSLOG_SOCKET, syslog_payload(severity, message)),
+    };
+    let socket = UnixDatagram::unbound()?;
+    socket.send_to(&payload, socket_path)?;
+    Ok(())
+}
+
+/// Build journald native protocol datagram
+fn journald_payload(severity: Severity, message: &str, fields: &[(&str, &str)]) -> Vec<u8> {
+    let priority = priority(severity).to_string();
+    let mut payload = Vec::new();
+    for (name, value) in [
+        ("MESSAGE", message),
+        ("PRIORITY", &priority),
+        ("SYSLOG_IDENTIFIER", IDENTIFIER),
+    ]
+    .iter()
+    .chain(fields)
+    {
+        payload.extend_from_slice(name.as_bytes());
+        if value.contains('\n') {
+            // Multiline values are prefixed by their little endian 64-bit length
+            payload.push(b'\n');
+            payload.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            payload.push(b'=');
+        }
+        payload.extend_from_slice(value.as_bytes());
+        payload.push(b'\n');
+    }
+    payload
+}
+
+/// Build syslog datagram
+fn syslog_payload(severity: Severity, message: &str) -> Vec<u8> {
+    format!(
+        "<{}>{IDENTIFIER}[{}]: {message}",
+        SYSLOG_FACILITY_DAEMON * 8 + priority(severity),
+        process::id()
+    )
+    .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journald_payload() {
+        assert_eq!(
+            journald_payload(
+                Severity::Critical,
+                "Filesystem usage is in critical state",
+                &[("MOTD_SECTION", "fs"), ("MOTD_SUMMARY", "a\nb")]
+            ),
+            b"MESSAGE=Filesystem usage is in critical state\nPRIORITY=2\nSYSLOG_IDENTIFIER=motd\nMOTD_SECTION=fs\nMOTD_SUMMARY\n\x03\0\0\0\0\0\0\0a\nb\n"
+        );
+    }
+}
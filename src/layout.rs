@@ -0,0 +1,84 @@
+//! Multi column layout for wide terminals
+
+use crate::{config::LayoutConfig, fmt::pad_ansi};
+
+/// Space between columns
+const COLUMN_GAP: usize = 2;
+
+/// Maximum number of side by side sections for terminal width
+pub(crate) fn max_columns(cfg: &LayoutConfig, term_columns: usize) -> usize {
+    ((term_columns + COLUMN_GAP) / (cfg.min_column_width.max(1) + COLUMN_GAP))
+        .min(cfg.max_columns)
+        .max(1)
+}
+
+/// Width of each column, when splitting terminal width in `count` columns
+pub(crate) fn column_width(term_columns: usize, count: usize) -> usize {
+    term_columns.saturating_sub(COLUMN_GAP * count.saturating_sub(1)) / count.max(1)
+}
+
+/// Place multi line texts side by side, padding each to `width`
+pub(crate) fn merge_columns(columns: &[String], width: usize) -> String {
+    let column_lines: Vec<Vec<&str>> = columns.iter().map(|c| c.lines().collect()).collect();
+    let line_count = column_lines.iter().map(Vec::len).max().unwrap_or(0);
+    let gap = " ".repeat(COLUMN_GAP);
+    let mut merged = String::new();
+    for i in 0..line_count {
+        let mut line = String::new();
+        for (j, lines) in column_lines.iter().enumerate() {
+            let col_line = lines.get(i).copied().unwrap_or("");
+            if j > 0 {
+                line.push_str(&gap);
+            }
+            if j + 1 < column_lines.len() {
+                line.push_str(&pad_ansi(col_line, width));
+            } else {
+                line.push_str(col_line);
+            }
+        }
+        merged.push_str(line.trim_end());
+        merged.push('\n');
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_columns() {
+        let cfg = LayoutConfig {
+            side_by_side: Vec::new(),
+            min_column_width: 50,
+            max_columns: 3,
+        };
+        assert_eq!(max_columns(&cfg, 80), 1);
+        assert_eq!(max_columns(&cfg, 102), 2);
+        assert_eq!(max_columns(&cfg, 153), 2);
+        assert_eq!(max_columns(&cfg, 154), 3);
+        assert_eq!(max_columns(&cfg, 300), 3);
+    }
+
+    #[test]
+    fn test_column_width() {
+        assert_eq!(column_width(80, 1), 80);
+        assert_eq!(column_width(102, 2), 50);
+        assert_eq!(column_width(200, 3), 65);
+    }
+
+    #[test]
+    fn test_merge_columns() {
+        assert_eq!(
+            merge_columns(
+                &[
+                    "a\nbb\n".to_owned(),
+                    "\u{1b}[31mc\u{1b}[0m\n".to_owned(),
+                    "d\ne\nf\n".to_owned()
+                ],
+                4
+            ),
+            "a     \u{1b}[31mc\u{1b}[0m     d\nbb          e\n            f\n"
+        );
+    }
+}
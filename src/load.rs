@@ -1,11 +1,18 @@
 use std::{fmt, fs, str::FromStr, sync::atomic::Ordering};
 
-use ansi_term::Colour::{Red, Yellow};
-
-use crate::module::{ModuleData, CPU_COUNT};
+use crate::{
+    config, history,
+    i18n::{self, Label},
+    icons,
+    module::{BuiltinModule, Module, CPU_COUNT},
+    prometheus,
+    provider::Providers,
+    style::{self, Severity},
+    sysroot,
+};
 
 /// Names of failed Systemd units
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 pub(crate) struct LoadInfo {
     /// Load average 1 minute
     load_avg_1m: f32,
@@ -15,12 +22,15 @@ pub(crate) struct LoadInfo {
     load_avg_15m: f32,
     /// Total task count
     task_count: u32,
+    /// Recent load average 1 minute sparkline
+    #[serde(skip)]
+    sparkline: Option<String>,
 }
 
 /// Fetch load information from /proc/loadavg
 #[expect(clippy::similar_names)]
-pub(crate) fn fetch() -> anyhow::Result<ModuleData> {
-    let line = fs::read_to_string("/proc/loadavg")?;
+pub(crate) fn fetch() -> anyhow::Result<LoadInfo> {
+    let line = fs::read_to_string(sysroot::path("/proc/loadavg"))?;
 
     let mut tokens_it = line.split(' ');
     let load_avg_1m = f32::from_str(
@@ -48,40 +58,121 @@ pub(crate) fn fetch() -> anyhow::Result<ModuleData> {
             .ok_or_else(|| anyhow::anyhow!("Failed to parse task count"))?,
     )?;
 
-    Ok(ModuleData::Load(LoadInfo {
+    Ok(LoadInfo {
         load_avg_1m,
         load_avg_5m,
         load_avg_15m,
         task_count,
-    }))
+        sparkline: None,
+    })
 }
 
 impl fmt::Display for LoadInfo {
     /// Output load information
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let cpu_count = CPU_COUNT.load(Ordering::SeqCst);
-        writeln!(
+        write!(
             f,
-            "Load avg 1min: {}, 5 min: {}, 15 min: {}",
-            colorize_load(self.load_avg_1m, cpu_count),
-            colorize_load(self.load_avg_5m, cpu_count),
-            colorize_load(self.load_avg_15m, cpu_count)
+            "{} 1min: {}, 5 min: {}, 15 min: {}",
+            i18n::tr(Label::LoadAvg),
+            colorize_load(self.load_avg_1m, "1m", cpu_count),
+            colorize_load(self.load_avg_5m, "5m", cpu_count),
+            colorize_load(self.load_avg_15m, "15m", cpu_count)
         )?;
-        writeln!(f, "Tasks: {}", self.task_count)
+        if let Some(sparkline) = self.sparkline.as_ref() {
+            write!(f, "  {sparkline}")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{}: {}", i18n::tr(Label::Tasks), self.task_count)
+    }
+}
+
+impl BuiltinModule for LoadInfo {
+    const LETTER: &'static str = "l";
+    const KEY: &'static str = "load";
+    const TITLE: Label = Label::LoadTitle;
+    const ICON: icons::Icon = icons::Icon::Load;
+
+    fn max_lines(cfg: &config::MaxLinesConfig) -> Option<usize> {
+        cfg.load
+    }
+
+    fn timeout(cfg: &config::TimeoutsConfig) -> Option<u64> {
+        cfg.load
+    }
+
+    fn fetch(_cfg: &config::Config, _providers: &Providers) -> anyhow::Result<Self> {
+        fetch()
     }
 }
 
-/// Colorize load string
-fn colorize_load(load: f32, cpu_count: usize) -> String {
+impl Module for LoadInfo {
+    /// Store load average in history, and set its sparkline
+    fn update_history(&mut self, cfg: &config::HistoryConfig) {
+        let recent = history::update("load", &[("load_avg_1m", self.load_avg_1m)], cfg);
+        self.sparkline = recent.get("load_avg_1m").map(|v| history::sparkline(v));
+    }
+
+    /// Whether any load average is above warning threshold
+    fn retain_alerts(&mut self) -> bool {
+        let warning = CPU_COUNT.load(Ordering::SeqCst) as f32 * 0.8;
+        [self.load_avg_1m, self.load_avg_5m, self.load_avg_15m]
+            .iter()
+            .any(|l| *l >= warning)
+    }
+
+    /// Highest severity of load averages
+    fn severity(&self) -> Severity {
+        let cpu_count = CPU_COUNT.load(Ordering::SeqCst);
+        [self.load_avg_1m, self.load_avg_5m, self.load_avg_15m]
+            .into_iter()
+            .map(|l| load_severity(l, cpu_count))
+            .max()
+            .unwrap_or(Severity::Ok)
+    }
+
+    /// Single line summary
+    fn compact(&self) -> String {
+        format!(
+            "load {}",
+            colorize_load(self.load_avg_1m, "1m", CPU_COUNT.load(Ordering::SeqCst))
+        )
+    }
+
+    /// Write load information as Prometheus metrics
+    fn write_prometheus(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        prometheus::write_gauge(f, "load_average", "System load average")?;
+        for (period, load_avg) in [
+            ("1m", self.load_avg_1m),
+            ("5m", self.load_avg_5m),
+            ("15m", self.load_avg_15m),
+        ] {
+            prometheus::write_sample(f, "load_average", &[("period", period)], load_avg)?;
+        }
+        prometheus::write_gauge(f, "tasks", "Total task count")?;
+        prometheus::write_sample(f, "tasks", &[], self.task_count)
+    }
+}
+
+/// Get severity of a load average from CPU count
+fn load_severity(load: f32, cpu_count: usize) -> Severity {
     if load >= cpu_count as f32 {
-        Red.paint(load.to_string()).to_string()
+        Severity::Critical
     } else if load >= cpu_count as f32 * 0.8 {
-        Yellow.paint(load.to_string()).to_string()
+        Severity::Warning
     } else {
-        load.to_string()
+        Severity::Ok
     }
 }
 
+/// Colorize load string, also highlighting load unusually high for this host
+fn colorize_load(load: f32, period: &str, cpu_count: usize) -> String {
+    let severity = load_severity(load, cpu_count);
+    let key = prometheus::sample_key("load_average", &[("period", period)]);
+    let severity = history::baseline_severity(severity, &key, f64::from(load));
+    style::theme().paint(&load.to_string(), severity)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,6 +188,7 @@ mod tests {
                     load_avg_5m: 2.9,
                     load_avg_15m: 3.1,
                     task_count: 12345,
+                    sparkline: None,
                 },
             ),
             "Load avg 1min: 1.1, 5 min: \u{1b}[33m2.9\u{1b}[0m, 15 min: \u{1b}[31m3.1\u{1b}[0m\nTasks: 12345\n"
@@ -105,11 +197,11 @@ mod tests {
 
     #[test]
     fn test_colorize_load() {
-        assert_eq!(colorize_load(7.9, 10), "7.9");
-        assert_eq!(colorize_load(8.0, 10), "\u{1b}[33m8\u{1b}[0m");
-        assert_eq!(colorize_load(8.1, 10), "\u{1b}[33m8.1\u{1b}[0m");
-        assert_eq!(colorize_load(9.9, 10), "\u{1b}[33m9.9\u{1b}[0m");
-        assert_eq!(colorize_load(10.0, 10), "\u{1b}[31m10\u{1b}[0m");
-        assert_eq!(colorize_load(10.1, 10), "\u{1b}[31m10.1\u{1b}[0m");
+        assert_eq!(colorize_load(7.9, "1m", 10), "7.9");
+        assert_eq!(colorize_load(8.0, "1m", 10), "\u{1b}[33m8\u{1b}[0m");
+        assert_eq!(colorize_load(8.1, "1m", 10), "\u{1b}[33m8.1\u{1b}[0m");
+        assert_eq!(colorize_load(9.9, "1m", 10), "\u{1b}[33m9.9\u{1b}[0m");
+        assert_eq!(colorize_load(10.0, "1m", 10), "\u{1b}[31m10\u{1b}[0m");
+        assert_eq!(colorize_load(10.1, "1m", 10), "\u{1b}[31m10.1\u{1b}[0m");
     }
 }
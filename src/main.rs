@@ -1,46 +1,262 @@
 //! MOTD banner generator
 
-use std::{cmp, iter::Iterator, path::Path, str::FromStr, sync::atomic::Ordering, thread};
+use std::{
+    cmp,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    env,
+    io::{self, IsTerminal as _, Write as _},
+    iter::Iterator,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    sync::{atomic::Ordering, mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
 
-use ansi_term::Colour::Red;
 use anyhow::Context;
-use clap::{App, Arg};
+use clap::{App, Arg, ArgGroup, ArgMatches};
 use itertools::Itertools;
 
-use crate::module::ModuleData;
+use crate::{
+    config::{ColorMode, OutputFormat},
+    module::{ModuleData, Section},
+};
 
+mod alert;
+mod banner;
+mod cache;
 mod config;
+mod csv;
+mod curl;
+mod dbus;
+mod dylib;
+mod fleet;
 mod fmt;
 mod fs;
+mod greeting;
+mod history;
+mod i18n;
+mod icons;
+mod influx;
+mod journal;
+mod layout;
 mod load;
 mod mem;
+mod message;
 mod module;
+mod mqtt;
+mod nagios;
 mod net;
+mod netlink;
+mod plugin;
+mod prometheus;
+mod provider;
+mod reload;
+mod restart;
+mod server;
+mod service;
+mod style;
+mod sysroot;
 mod systemd;
 mod temp;
-
-/// Output section
-#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
-enum Section {
-    Load,
-    Mem,
-    Swap,
-    FS,
-    Temps,
-    Network,
-    SDFailedUnits,
-}
+mod template;
+mod timings;
 
 /// Parsed command line arguments
+#[derive(Clone)]
+#[expect(clippy::struct_excessive_bools)]
 struct CLArgs {
     /// Maximum terminal columns to use
     term_columns: usize,
 
     /// Sections to display
-    sections: Vec<Section>,
+    sections: Vec<&'static Section>,
+
+    /// Position of plugin sections in displayed sections, if enabled
+    plugins_position: Option<usize>,
 
     /// Whether or not to display each section title
     show_section_titles: bool,
+
+    /// Temperature unit, overriding config
+    temp_unit: Option<config::TempUnit>,
+
+    /// Filesystem bar text mode, overriding config
+    fs_display: Option<config::FsDisplay>,
+
+    /// Output format
+    format: OutputFormat,
+
+    /// When to use colors
+    color: ColorMode,
+
+    /// Template file, overriding config
+    template: Option<PathBuf>,
+
+    /// Output a single summary line
+    compact: bool,
+
+    /// Only output entries above warning thresholds, overriding config
+    quiet: bool,
+
+    /// Refresh output at this interval
+    watch: Option<Duration>,
+
+    /// Output file to atomically replace, instead of stdout
+    output: Option<PathBuf>,
+
+    /// Regenerate output file at this interval
+    daemon: Option<Duration>,
+
+    /// Install systemd units instead of generating output
+    install_service: Option<InstallServiceArgs>,
+
+    /// Output recorded metric history instead of generating output
+    history: Option<HistoryArgs>,
+
+    /// Output config file with current values instead of generating output
+    dump_config: bool,
+
+    /// Serve output over HTTP instead of generating it once
+    serve: Option<ServeArgs>,
+
+    /// Display overview of these hosts data URLs instead of local sections, config hosts if empty
+    fleet: Option<Vec<String>>,
+
+    /// Store output in cache instead of stdout
+    update_cache: bool,
+
+    /// Output cached output if any
+    cached: bool,
+
+    /// Maximum age of cached output to output it
+    cached_max_age: Duration,
+
+    /// Refresh cache in a detached background process, after outputting cached output
+    refresh: bool,
+
+    /// Publish section data to MQTT broker, instead of writing to stdout
+    mqtt: bool,
+
+    /// Print section fetch and render durations to stderr
+    timings: bool,
+
+    /// Directory to resolve procfs and sysfs paths under
+    sysroot: Option<PathBuf>,
+}
+
+/// Consumers of fetched section data, besides output
+#[derive(Default, Clone, Copy)]
+struct Observers<'a> {
+    /// Metric store to record samples to
+    store: Option<&'a history::Store>,
+
+    /// Alerter to notify section state changes to
+    alerter: Option<&'a alert::Alerter>,
+
+    /// Publisher to send section data to
+    publisher: Option<&'a mqtt::Publisher>,
+}
+
+/// Consumers of fetched section data in watch and daemon modes, rebuilt on config reload
+#[derive(Default)]
+struct OwnedObservers {
+    /// Metric store to record samples to
+    store: Option<history::Store>,
+
+    /// Alerter to notify section state changes to
+    alerter: Option<alert::Alerter>,
+
+    /// Publisher to send section data to
+    publisher: Option<mqtt::Publisher>,
+}
+
+impl OwnedObservers {
+    /// Build observers enabled by arguments and config
+    fn new(cl_args: &CLArgs, cfg: &config::Config) -> anyhow::Result<Self> {
+        let mut observers = Self::default();
+        observers.reload(cl_args, cfg)?;
+        Ok(observers)
+    }
+
+    /// Rebuild observers from reloaded arguments and config, keeping section states of the
+    /// alerter, and keeping all current observers on error
+    fn reload(&mut self, cl_args: &CLArgs, cfg: &config::Config) -> anyhow::Result<()> {
+        let store = if cfg.history.store {
+            Some(history::Store::open(&cfg.history)?)
+        } else {
+            None
+        };
+        let publisher = if cl_args.mqtt {
+            Some(mqtt::Publisher::new(&cfg.mqtt)?)
+        } else {
+            None
+        };
+        if cl_args.daemon.is_some() && (!cfg.alert.webhooks.is_empty() || cfg.alert.log.is_some()) {
+            match &mut self.alerter {
+                Some(alerter) => alerter.reconfigure(&cfg.alert),
+                None => self.alerter = Some(alert::Alerter::new(&cfg.alert)?),
+            }
+        } else {
+            self.alerter = None;
+        }
+        self.store = store;
+        self.publisher = publisher;
+        Ok(())
+    }
+
+    /// Borrow observers for an output
+    fn get(&self) -> Observers<'_> {
+        Observers {
+            store: self.store.as_ref(),
+            alerter: self.alerter.as_ref(),
+            publisher: self.publisher.as_ref(),
+        }
+    }
+}
+
+/// Parsed `serve` subcommand arguments
+#[derive(Clone)]
+struct ServeArgs {
+    /// Address to listen on
+    listen: SocketAddr,
+
+    /// Duration to reuse rendered responses for
+    cache: Duration,
+}
+
+/// Parsed `history` subcommand arguments
+#[derive(Clone)]
+struct HistoryArgs {
+    /// Metric to output samples of, all metrics are summarized if unset
+    metric: Option<String>,
+
+    /// Output samples of all metrics as CSV
+    export: bool,
+
+    /// Only output samples more recent than this
+    since: Duration,
+}
+
+/// Parsed `install-service` subcommand arguments
+#[derive(Clone)]
+struct InstallServiceArgs {
+    /// Install user units instead of system ones
+    user: bool,
+
+    /// File to regenerate, overriding default
+    output: Option<PathBuf>,
+
+    /// Update cache instead of a file
+    cache: bool,
+
+    /// Regeneration interval
+    interval: Duration,
+
+    /// Directory to write units to, overriding default
+    units_dir: Option<PathBuf>,
 }
 
 /// Fallback terminal column count (width), if it could not be detected
@@ -49,77 +265,654 @@ const FALLBACK_TERM_COLUMNS: usize = 80;
 /// Message shown when there is a delay
 const LOADING_MSG: &str = "Loading…";
 
-/// Output section header to stdout
-fn output_title(title: &str, columns: usize) {
-    println!("{:─^width$}", format!(" {title} "), width = columns);
+/// Format section header line, with a state after the title
+fn format_title(title: &str, icon: icons::Icon, suffix: &str, columns: usize) -> String {
+    paint_title(fmt::title_line(
+        title,
+        &icons::prefix(icon),
+        &header_suffix(suffix),
+        columns,
+    ))
+}
+
+/// Prepare state shown after a title for header line, restoring title style after its colors
+fn header_suffix(suffix: &str) -> String {
+    if !module::COLOR.load(Ordering::SeqCst) {
+        fmt::strip_ansi(suffix)
+    } else if suffix.is_empty() {
+        String::new()
+    } else {
+        format!("{suffix}{}", style::theme().title.prefix())
+    }
+}
+
+/// Format separator line, without title
+fn format_separator(columns: usize) -> String {
+    paint_title(fmt::separator_line(columns))
+}
+
+/// Apply title style to header or separator line
+fn paint_title(line: String) -> String {
+    if module::COLOR.load(Ordering::SeqCst) {
+        style::theme().title.paint(line).to_string()
+    } else {
+        line
+    }
 }
 
 /// Output section title and lines
 fn output_section(
     title: &str,
-    lines: Result<String, String>,
+    icon: icons::Icon,
+    text: Result<SectionText, String>,
+    show_title: bool,
+    columns: usize,
+    out: &mut dyn io::Write,
+) -> io::Result<()> {
+    match text {
+        Ok(text) => {
+            if !text.is_empty() {
+                if show_title {
+                    writeln!(
+                        out,
+                        "{}",
+                        format_title(title, icon, &text.title_suffix, columns)
+                    )?;
+                }
+                if module::COLOR.load(Ordering::SeqCst) {
+                    write!(out, "{}", text.lines)?;
+                } else {
+                    write!(out, "{}", fmt::strip_ansi(&text.lines))?;
+                }
+            }
+        }
+        Err(err) => {
+            output_section_error(title, &err);
+        }
+    }
+    Ok(())
+}
+
+/// Output sections under a single header joining their titles
+fn output_merged_sections(
+    sections: &[(&Section, SectionText)],
+    columns: usize,
+    out: &mut dyn io::Write,
+) -> io::Result<()> {
+    if sections.is_empty() {
+        return Ok(());
+    }
+    let parts: Vec<(String, String)> = sections
+        .iter()
+        .map(|(s, t)| (icons::prefix(s.icon), header_suffix(&t.title_suffix)))
+        .collect();
+    let titles: Vec<(&str, &str, &str)> = sections
+        .iter()
+        .zip(&parts)
+        .map(|((s, _), (prefix, suffix))| (s.title(), prefix.as_str(), suffix.as_str()))
+        .collect();
+    writeln!(
+        out,
+        "{}",
+        paint_title(fmt::merged_title_line(&titles, columns))
+    )?;
+    for (_, text) in sections {
+        if module::COLOR.load(Ordering::SeqCst) {
+            write!(out, "{}", text.lines)?;
+        } else {
+            write!(out, "{}", fmt::strip_ansi(&text.lines))?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a block of section output, preceded by blank lines if something was already written
+fn write_block(
+    block: &[u8],
+    spacing: usize,
+    started: &mut bool,
+    out: &mut dyn io::Write,
+) -> io::Result<()> {
+    if block.is_empty() {
+        return Ok(());
+    }
+    if *started {
+        write!(out, "{}", "\n".repeat(spacing))?;
+    }
+    *started = true;
+    out.write_all(block)
+}
+
+/// Output placeholder of a section still being fetched
+fn output_placeholder(
+    section: &Section,
     show_title: bool,
-    delayed: bool,
     columns: usize,
-) {
+    out: &mut dyn io::Write,
+) -> io::Result<()> {
+    if show_title {
+        writeln!(
+            out,
+            "{}",
+            format_title(section.title(), section.icon, "", columns)
+        )?;
+    }
+    if module::COLOR.load(Ordering::SeqCst) {
+        writeln!(out, "{}", style::theme().secondary.paint(LOADING_MSG))
+    } else {
+        writeln!(out, "{LOADING_MSG}")
+    }
+}
+
+/// Count terminal rows of written output lines, including those wrapped by the terminal
+fn count_rows(block: &[u8], columns: usize) -> usize {
+    String::from_utf8_lossy(block)
+        .split_terminator('\n')
+        .map(|l| fmt::ansi_width(l).div_ceil(columns.max(1)).max(1))
+        .sum()
+}
+
+/// Section of the live region
+struct LiveEntry<'scope> {
+    /// Index of section, as reported by its fetching thread when it finishes
+    index: usize,
+    /// Section to output
+    section: &'static Section,
+    /// Fetching thread, until it finished
+    fut: Option<thread::ScopedJoinHandle<'scope, anyhow::Result<ModuleData>>>,
+    /// Section text or error, once fetched
+    lines: Option<Result<SectionText, String>>,
+}
+
+/// Sections at the bottom of terminal output, with placeholders for sections still being fetched
+/// that are redrawn in place as they finish
+struct LiveRegion<'scope> {
+    /// Sections not yet definitively written, the first one still being fetched
+    entries: VecDeque<LiveEntry<'scope>>,
+    /// Indexes of sections whose fetching thread finished
+    finished_rx: mpsc::Receiver<usize>,
+    /// Indexes of sections reported as finished, that may not be in the region yet
+    finished: BTreeSet<usize>,
+    /// Number of terminal rows currently drawn
+    drawn_rows: usize,
+    /// Maximum number of rows to draw, so that the region does not scroll out of reach
+    max_rows: usize,
+    /// Actual terminal columns, where lines wrap
+    term_columns: usize,
+    /// Whether or not to display each section title
+    show_title: bool,
+    /// Terminal columns
+    columns: usize,
+    /// Blank lines between sections
+    spacing: usize,
+    /// Only output entries above warning thresholds
+    quiet: bool,
+}
+
+impl<'scope> LiveRegion<'scope> {
+    /// Create empty region
+    fn new(
+        finished_rx: mpsc::Receiver<usize>,
+        show_title: bool,
+        columns: usize,
+        spacing: usize,
+        quiet: bool,
+    ) -> Self {
+        let size = termsize::get();
+        let rows = size.as_ref().map_or(0, |s| usize::from(s.rows));
+        Self {
+            entries: VecDeque::new(),
+            finished_rx,
+            finished: BTreeSet::new(),
+            drawn_rows: 0,
+            max_rows: rows.saturating_sub(1),
+            term_columns: size.map_or(columns, |s| usize::from(s.cols)),
+            show_title,
+            columns,
+            spacing,
+            quiet,
+        }
+    }
+
+    /// Add section, and redraw
+    fn push(
+        &mut self,
+        index: usize,
+        section: &'static Section,
+        fut: thread::ScopedJoinHandle<'scope, anyhow::Result<ModuleData>>,
+        started: &mut bool,
+        out: &mut dyn io::Write,
+    ) -> anyhow::Result<()> {
+        self.entries.push_back(LiveEntry {
+            index,
+            section,
+            fut: Some(fut),
+            lines: None,
+        });
+        self.join_finished()?;
+        self.redraw(started, out)
+    }
+
+    /// Wait for all sections, redrawing as each finishes
+    fn finish(&mut self, started: &mut bool, out: &mut dyn io::Write) -> anyhow::Result<()> {
+        while !self.entries.is_empty() {
+            // Scoped threads can not be waited for together, so they report when they finish
+            while !self
+                .entries
+                .iter()
+                .any(|e| e.fut.is_some() && self.is_finished(e))
+            {
+                match self.finished_rx.recv() {
+                    Ok(index) => {
+                        self.finished.insert(index);
+                    }
+                    // All threads ended, including any that panicked without reporting
+                    Err(mpsc::RecvError) => {
+                        self.finished.extend(self.entries.iter().map(|e| e.index));
+                    }
+                }
+            }
+            self.join_finished()?;
+            self.redraw(started, out)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the fetching thread of an entry finished, or is about to
+    fn is_finished(&self, entry: &LiveEntry) -> bool {
+        self.finished.contains(&entry.index)
+            || entry
+                .fut
+                .as_ref()
+                .is_some_and(thread::ScopedJoinHandle::is_finished)
+    }
+
+    /// Get lines of sections that finished fetching
+    fn join_finished(&mut self) -> anyhow::Result<()> {
+        for entry in &mut self.entries {
+            let finished = self.finished.contains(&entry.index);
+            if let Some(fut) = entry.fut.take_if(|f| finished || f.is_finished()) {
+                let result = fut
+                    .join()
+                    .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))?;
+                entry.lines = Some(section_lines(entry.section, result, self.quiet));
+            }
+        }
+        Ok(())
+    }
+
+    /// Erase drawn lines, definitively write leading fetched sections, and draw the others
+    ///
+    /// Only definitive sections go to `out`, the temporary region is drawn on stderr.
+    fn redraw(&mut self, started: &mut bool, out: &mut dyn io::Write) -> anyhow::Result<()> {
+        let mut err_out = io::stderr().lock();
+        if self.drawn_rows > 0 {
+            // Move cursor to the first drawn row, and clear until the end of screen
+            write!(err_out, "\x1b[{}F\x1b[J", self.drawn_rows)?;
+            err_out.flush()?;
+        }
+        while let Some(lines) = self.entries.front_mut().and_then(|e| e.lines.take()) {
+            let section = self.entries.pop_front().unwrap().section;
+            match lines {
+                Ok(lines) => {
+                    let mut block = Vec::new();
+                    output_section(
+                        section.title(),
+                        section.icon,
+                        Ok(lines),
+                        self.show_title,
+                        self.columns,
+                        &mut block,
+                    )?;
+                    write_block(&block, self.spacing, started, out)?;
+                }
+                Err(err) => {
+                    out.flush()?;
+                    output_section_error(section.title(), &err);
+                }
+            }
+        }
+        // Errors of following sections are reported once they are definitively written
+        let mut region = Vec::new();
+        let mut region_started = *started;
+        for entry in &self.entries {
+            let mut block = Vec::new();
+            match &entry.lines {
+                Some(Ok(lines)) => output_section(
+                    entry.section.title(),
+                    entry.section.icon,
+                    Ok(lines.clone()),
+                    self.show_title,
+                    self.columns,
+                    &mut block,
+                )?,
+                Some(Err(_)) => {}
+                None => {
+                    output_placeholder(entry.section, self.show_title, self.columns, &mut block)?;
+                }
+            }
+            let mut entry_started = region_started;
+            let mut drawn = Vec::new();
+            write_block(&block, self.spacing, &mut entry_started, &mut drawn)?;
+            // Sections that do not fit are drawn once previous ones are definitively written
+            if !region.is_empty()
+                && (count_rows(&region, self.term_columns) + count_rows(&drawn, self.term_columns)
+                    > self.max_rows)
+            {
+                break;
+            }
+            region_started = entry_started;
+            region.extend(drawn);
+        }
+        out.flush()?;
+        err_out.write_all(&region)?;
+        err_out.flush()?;
+        self.drawn_rows = count_rows(&region, self.term_columns);
+        Ok(())
+    }
+}
+
+/// Drop data with nothing above warning thresholds in quiet mode
+fn filter_alerts(mut data: ModuleData, quiet: bool) -> Option<ModuleData> {
+    (!quiet || data.retain_alerts()).then_some(data)
+}
+
+/// Rendered section
+#[derive(Clone, Default)]
+struct SectionText {
+    /// Section lines
+    lines: String,
+    /// State shown in the header after the title
+    title_suffix: String,
+}
+
+impl SectionText {
+    /// Whether there is nothing to show, not even a header
+    fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.title_suffix.is_empty()
+    }
+}
+
+/// Format fetched section lines, or error
+fn section_lines(
+    section: &Section,
+    result: anyhow::Result<ModuleData>,
+    quiet: bool,
+) -> Result<SectionText, String> {
+    result
+        .map(|d| {
+            filter_alerts(d, quiet)
+                .map(|d| SectionText {
+                    lines: limit_lines(section, format!("{d}")),
+                    title_suffix: d.title_suffix(),
+                })
+                .unwrap_or_default()
+        })
+        .map_err(|e| format!("{e}"))
+}
+
+/// Format section data for a given width
+fn format_section(data: &ModuleData, columns: usize) -> String {
+    // Modules use the global terminal width
+    let term_columns = module::TERM_COLUMNS.swap(columns, Ordering::SeqCst);
+    let lines = data.to_string();
+    module::TERM_COLUMNS.store(term_columns, Ordering::SeqCst);
+    lines
+}
+
+/// Output sections side by side, in rows of at most `max_columns` sections
+fn output_columns(
+    section_futs: Vec<thread::ScopedJoinHandle<anyhow::Result<ModuleData>>>,
+    sections: &[&Section],
+    show_title: bool,
+    columns: usize,
+    max_columns: usize,
+    quiet: bool,
+    out: &mut dyn io::Write,
+) -> anyhow::Result<()> {
+    let delayed = section_futs.iter().any(|f| !f.is_finished()) && io::stderr().is_terminal();
+    if delayed {
+        eprint!("{LOADING_MSG}");
+    }
+    let results = section_futs
+        .into_iter()
+        .map(|f| {
+            f.join()
+                .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
     if delayed {
         eprint!("\r{}\r", " ".repeat(LOADING_MSG.len()));
     }
-    match lines {
-        Ok(lines) => {
-            if !lines.is_empty() {
-                if show_title {
-                    output_title(title, columns);
+    let mut row_data = Vec::with_capacity(sections.len());
+    for (result, section) in results.into_iter().zip(sections) {
+        match result {
+            // Sections with nothing to show do not take a column
+            Ok(d) => {
+                if let Some(d) = filter_alerts(d, quiet)
+                    .filter(|d| !(d.to_string().is_empty() && d.title_suffix().is_empty()))
+                {
+                    row_data.push((d, section));
                 }
-                print!("{lines}");
             }
+            Err(err) => output_section_error(section.title(), &format!("{err}")),
         }
-        Err(err) => {
-            eprintln!(
-                "{}",
-                Red.paint(format!("Failed to get data for '{title}' section: {err}"))
-            );
+    }
+    for row in row_data.chunks(max_columns) {
+        let width = layout::column_width(columns, row.len());
+        let mut texts = Vec::with_capacity(row.len());
+        for (d, section) in row {
+            let mut lines = limit_lines(section, format_section(d, width));
+            if !module::COLOR.load(Ordering::SeqCst) {
+                lines = fmt::strip_ansi(&lines);
+            }
+            if show_title {
+                lines.insert(0, '\n');
+                lines.insert_str(
+                    0,
+                    &format_title(section.title(), section.icon, &d.title_suffix(), width),
+                );
+            }
+            texts.push(lines);
+        }
+        write!(out, "{}", layout::merge_columns(&texts, width))?;
+    }
+    Ok(())
+}
+
+/// Split sections in groups, consecutive side by side sections share a group
+fn layout_groups(sections: &[&Section], cfg: &config::LayoutConfig) -> Vec<usize> {
+    let mut groups = Vec::new();
+    let mut i = 0;
+    while i < sections.len() {
+        let run = sections
+            .iter()
+            .skip(i)
+            .take_while(|s| cfg.side_by_side.iter().any(|l| l == s.letter))
+            .count()
+            .max(1);
+        groups.push(run);
+        i += run;
+    }
+    groups
+}
+
+/// Whether output goes to a terminal, where sections can be redrawn in place as they finish
+///
+/// Loading placeholders are drawn on stderr, so both must be the same terminal.
+fn live_output(cl_args: &CLArgs) -> bool {
+    cl_args.output.is_none()
+        && !cl_args.update_cache
+        && !cl_args.mqtt
+        && cl_args.serve.is_none()
+        && io::stdout().is_terminal()
+        && io::stderr().is_terminal()
+}
+
+/// Output section fetch error to stderr
+fn output_section_error(title: &str, err: &str) {
+    let msg = format!("Failed to get data for '{title}' section: {err}");
+    if module::COLOR.load(Ordering::SeqCst) {
+        eprintln!("{}", style::theme().critical.paint(msg));
+    } else {
+        eprintln!("{msg}");
+    }
+}
+
+/// Decide if colors should be used, honoring the `NO_COLOR` convention in auto mode
+fn use_color(mode: ColorMode, to_stdout: bool) -> bool {
+    match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                && to_stdout
+                && io::stdout().is_terminal()
+        }
+    }
+}
+
+/// Wait for all sections data, reporting errors of failed sections
+fn join_sections(
+    section_futs: Vec<thread::ScopedJoinHandle<anyhow::Result<ModuleData>>>,
+    sections: &[&Section],
+    quiet: bool,
+) -> anyhow::Result<module::SectionsData> {
+    let mut data = Vec::with_capacity(sections.len());
+    for (section_fut, section) in section_futs.into_iter().zip(sections) {
+        match section_fut
+            .join()
+            .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))?
+        {
+            Ok(d) => {
+                if let Some(d) = filter_alerts(d, quiet) {
+                    data.push((section.key, d));
+                }
+            }
+            Err(err) => output_section_error(section.title(), &format!("{err}")),
+        }
+    }
+    Ok(module::SectionsData(data))
+}
+
+/// Output data of all sections in a structured format
+fn output_structured(
+    section_futs: Vec<thread::ScopedJoinHandle<anyhow::Result<ModuleData>>>,
+    sections: &[&Section],
+    format: OutputFormat,
+    quiet: bool,
+    out: &mut dyn io::Write,
+) -> anyhow::Result<()> {
+    let data = join_sections(section_futs, sections, quiet)?;
+    match format {
+        OutputFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(&data)?)?,
+        OutputFormat::Yaml => write!(out, "{}", serde_yaml_ng::to_string(&data)?)?,
+        OutputFormat::Prometheus => {
+            let mut metrics = String::new();
+            for (_, d) in &data.0 {
+                d.write_prometheus(&mut metrics)?;
+            }
+            write!(out, "{metrics}")?;
+        }
+        OutputFormat::Csv => {
+            let now = history::now();
+            let mut rows = String::new();
+            csv::write_header(&mut rows)?;
+            for (_, d) in &data.0 {
+                let mut metrics = String::new();
+                d.write_prometheus(&mut metrics)?;
+                csv::write_samples(&mut rows, &metrics, now)?;
+            }
+            write!(out, "{rows}")?;
+        }
+        OutputFormat::Influx => {
+            let now = history::now();
+            let mut lines = String::new();
+            for (_, d) in &data.0 {
+                let mut metrics = String::new();
+                d.write_prometheus(&mut metrics)?;
+                influx::write_samples(&mut lines, &metrics, now)?;
+            }
+            write!(out, "{lines}")?;
         }
+        OutputFormat::Text | OutputFormat::Nagios => unreachable!(),
     }
+    Ok(())
 }
 
-/// Get Section from letter
-fn section_to_letter(section: Section) -> &'static str {
-    match section {
-        Section::Load => "l",
-        Section::Mem => "m",
-        Section::Swap => "s",
-        Section::FS => "f",
-        Section::Temps => "t",
-        Section::Network => "n",
-        Section::SDFailedUnits => "u",
+/// Output Nagios plugin status line for all sections, and get plugin exit code
+fn output_nagios(
+    section_futs: Vec<thread::ScopedJoinHandle<anyhow::Result<ModuleData>>>,
+    sections: &[&Section],
+    out: &mut dyn io::Write,
+) -> anyhow::Result<i32> {
+    let mut results = Vec::with_capacity(sections.len());
+    for (section_fut, section) in section_futs.into_iter().zip(sections) {
+        let result = section_fut
+            .join()
+            .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))?
+            .map_err(|e| e.to_string());
+        results.push((section.title(), result));
     }
+    nagios::write_status(out, &results)
 }
 
-/// Get letter from Section
-fn pretty_section_name(section: &Section) -> &str {
-    match section {
-        Section::Load => "Load",
-        Section::Mem => "Memory usage",
-        Section::Swap => "Swap usage",
-        Section::FS => "Filesystem usage",
-        Section::Temps => "Hardware temperatures",
-        Section::Network => "Network",
-        Section::SDFailedUnits => "Systemd failed units",
+/// Output all sections summaries on a single line
+fn output_compact(
+    section_futs: Vec<thread::ScopedJoinHandle<anyhow::Result<ModuleData>>>,
+    sections: &[&Section],
+    quiet: bool,
+    out: &mut dyn io::Write,
+) -> anyhow::Result<()> {
+    let data = join_sections(section_futs, sections, quiet)?;
+    let line = data
+        .0
+        .iter()
+        .map(|(_, d)| d.compact())
+        .filter(|s| !s.is_empty())
+        .join(" | ");
+    if module::COLOR.load(Ordering::SeqCst) {
+        writeln!(out, "{line}")?;
+    } else {
+        writeln!(out, "{}", fmt::strip_ansi(&line))?;
     }
+    Ok(())
 }
 
-/// Get Section from letter
-fn letter_to_section(letter: &str) -> Section {
-    match letter {
-        "l" => Section::Load,
-        "m" => Section::Mem,
-        "s" => Section::Swap,
-        "f" => Section::FS,
-        "t" => Section::Temps,
-        "n" => Section::Network,
-        "u" => Section::SDFailedUnits,
-        _ => unreachable!(), // validated by clap
+/// Output all sections using a user template
+fn output_template(
+    section_futs: Vec<thread::ScopedJoinHandle<anyhow::Result<ModuleData>>>,
+    sections: &[&Section],
+    template_filepath: &Path,
+    quiet: bool,
+    out: &mut dyn io::Write,
+) -> anyhow::Result<()> {
+    let template = std::fs::read_to_string(template_filepath)
+        .with_context(|| format!("Failed to read template {}", template_filepath.display()))?;
+    let data = join_sections(section_futs, sections, quiet)?;
+    let color = module::COLOR.load(Ordering::SeqCst);
+    let texts = data
+        .0
+        .iter()
+        .map(|(k, d)| {
+            let text = d.to_string();
+            (*k, if color { text } else { fmt::strip_ansi(&text) })
+        })
+        .collect();
+    let output = template::render(&template, &data, texts).context("Failed to render template")?;
+    if color {
+        write!(out, "{output}")?;
+    } else {
+        write!(out, "{}", fmt::strip_ansi(&output))?;
+    }
+    Ok(())
+}
+
+/// Truncate section lines to the configured maximum
+fn limit_lines(section: &Section, lines: String) -> String {
+    match (section.max_lines)(&fmt::display().max_lines) {
+        Some(max) => fmt::truncate_lines(&lines, max),
+        None => lines,
     }
 }
 
@@ -131,22 +924,24 @@ fn validator_isize(s: &str) -> Result<(), String> {
     }
 }
 
+/// Validate a u64 integer string for Clap usage
+fn validator_u64(s: &str) -> Result<(), String> {
+    match u64::from_str(s) {
+        Ok(_) => Ok(()),
+        Err(_) => Err("Not a valid positive integer value".to_owned()),
+    }
+}
+
 /// Parse and validate command line arguments
-fn parse_cl_args() -> CLArgs {
+#[expect(clippy::too_many_lines)]
+fn cl_matches() -> ArgMatches {
     // Default values
     let default_term_columns_string = format!("-{FALLBACK_TERM_COLUMNS}");
-    let sections_str: Vec<&'static str> = [
-        Section::Load,
-        Section::Mem,
-        Section::Swap,
-        Section::FS,
-        Section::Temps,
-        Section::Network,
-        Section::SDFailedUnits,
-    ]
-    .into_iter()
-    .map(section_to_letter)
-    .collect();
+    let sections_str: Vec<&'static str> = module::SECTIONS
+        .iter()
+        .map(|s| s.letter)
+        .chain([plugin::LETTER])
+        .collect();
     let default_sections_string = sections_str
         .iter()
         .filter(|l| {
@@ -159,7 +954,7 @@ fn parse_cl_args() -> CLArgs {
         .join(",");
 
     // Clap arg matching
-    let matches = App::new("motd")
+    App::new("motd")
         .version(env!("CARGO_PKG_VERSION"))
         .about("Show dynamic summary of system information")
         .author("desbma")
@@ -180,7 +975,9 @@ fn parse_cl_args() -> CLArgs {
                      f: Filesystem usage. \
                      t: Hardware temperatures. \
                      n: Network interface stats. \
-                     u: Systemd failed units."
+                     u: Systemd failed units. \
+                     a: Admin messages. \
+                     p: User defined plugin sections."
                 ),
         )
         .arg(
@@ -199,16 +996,260 @@ fn parse_cl_args() -> CLArgs {
                 .default_value(&default_term_columns_string)
                 .help("Maximum terminal columns to use. Set to 0 to autotetect. -X to use autodetected value or X, whichever is lower."),
         )
-        .get_matches();
-
-    // Post Clap parsing
-    let sections = matches
-        .values_of("SECTIONS")
-        .unwrap()
-        .map(letter_to_section)
-        .unique()
+        .arg(
+            Arg::with_name("TEMP_UNIT")
+                .short('u')
+                .long("temp-unit")
+                .takes_value(true)
+                .possible_values(["celsius", "fahrenheit"])
+                .help("Temperature unit, overrides config file."),
+        )
+        .arg(
+            Arg::with_name("FS_DISPLAY")
+                .short('f')
+                .long("fs-display")
+                .takes_value(true)
+                .possible_values(["used", "available"])
+                .help("Show used or available space in filesystem bars, overrides config file."),
+        )
+        .arg(
+            Arg::with_name("FORMAT")
+                .long("format")
+                .takes_value(true)
+                .possible_values(["text", "json", "yaml", "prometheus", "csv", "influx", "nagios"])
+                .default_value("text")
+                .help("Output format. Structured formats output raw values, without section titles."),
+        )
+        .arg(
+            Arg::with_name("COLOR")
+                .long("color")
+                .takes_value(true)
+                .possible_values(["auto", "always", "never"])
+                .default_value("auto")
+                .help("When to use colors. In auto mode, colors are used if output is a terminal and NO_COLOR is not set."),
+        )
+        .arg(
+            Arg::with_name("COMPACT")
+                .long("compact")
+                .help("Output a single line summary of all sections, for status bars or prompts."),
+        )
+        .arg(
+            Arg::with_name("WATCH")
+                .short('w')
+                .long("watch")
+                .takes_value(true)
+                .min_values(0)
+                .max_values(1)
+                .default_missing_value("2")
+                .validator(validator_u64)
+                .value_name("INTERVAL")
+                .help("Clear and refresh output every INTERVAL seconds (default 2), like a lightweight monitor."),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short('o')
+                .long("output")
+                .takes_value(true)
+                .conflicts_with("WATCH")
+                .help("Atomically replace this file instead of writing to stdout, without colors by default."),
+        )
+        .arg(
+            Arg::with_name("UPDATE_CACHE")
+                .long("update-cache")
+                .conflicts_with_all(&["OUTPUT", "WATCH"])
+                .help("Store output with and without colors in cache (/run/motd as root, user cache directory otherwise), for later display with --cached."),
+        )
+        .arg(
+            Arg::with_name("CACHED")
+                .long("cached")
+                .conflicts_with("UPDATE_CACHE")
+                .help("Display output stored by --update-cache, or generate it if there is none."),
+        )
+        .arg(
+            Arg::with_name("CACHED_MAX_AGE")
+                .long("cached-max-age")
+                .takes_value(true)
+                .validator(validator_u64)
+                .default_value("3600")
+                .value_name("SECONDS")
+                .help("With --cached, generate output instead of displaying cached output older than this."),
+        )
+        .arg(
+            Arg::with_name("REFRESH")
+                .long("refresh")
+                .requires("CACHED")
+                .help("With --cached, also update the cache in a detached background process, so that the next output is at most one run old."),
+        )
+        .arg(
+            Arg::with_name("MQTT")
+                .long("mqtt")
+                .conflicts_with("WATCH")
+                .help("Publish section data as JSON to the MQTT broker of the config file, instead of writing to stdout."),
+        )
+        .group(
+            ArgGroup::with_name("DESTINATION")
+                .args(&["OUTPUT", "UPDATE_CACHE", "MQTT"])
+                .multiple(true),
+        )
+        .arg(
+            Arg::with_name("DAEMON")
+                .long("daemon")
+                .takes_value(true)
+                .min_values(0)
+                .max_values(1)
+                .default_missing_value("300")
+                .validator(validator_u64)
+                .value_name("INTERVAL")
+                .requires("DESTINATION")
+                .help("Regenerate output file or cache every INTERVAL seconds (default 300), for example for /etc/motd."),
+        )
+        .arg(
+            Arg::with_name("QUIET")
+                .short('q')
+                .long("quiet")
+                .help("Only display entries above warning thresholds, and nothing if all is fine."),
+        )
+        .arg(
+            Arg::with_name("SYSROOT")
+                .long("sysroot")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Read procfs and sysfs files under this directory instead of /, for example to inspect a tree captured from another machine."),
+        )
+        .arg(
+            Arg::with_name("TIMINGS")
+                .long("timings")
+                .help("Print fetch and render duration of each section, and total duration, to stderr, to find slow sections."),
+        )
+        .arg(
+            Arg::with_name("TEMPLATE")
+                .short('t')
+                .long("template")
+                .takes_value(true)
+                .help("Template file to render instead of the fixed section layout, overrides config file."),
+        )
+        .subcommand(
+            App::new("install-service")
+                .about("Install systemd service and timer units periodically regenerating a MOTD file")
+                .arg(
+                    Arg::with_name("USER")
+                        .long("user")
+                        .help("Install user units instead of system ones."),
+                )
+                .arg(
+                    Arg::with_name("OUTPUT")
+                        .short('o')
+                        .long("output")
+                        .takes_value(true)
+                        .help("File to regenerate. Defaults to /run/motd.d/motd, shown by pam_motd, or motd in the user cache directory with --user."),
+                )
+                .arg(
+                    Arg::with_name("CACHE")
+                        .long("cache")
+                        .conflicts_with("OUTPUT")
+                        .help("Update cache for --cached instead of a file."),
+                )
+                .arg(
+                    Arg::with_name("INTERVAL")
+                        .short('i')
+                        .long("interval")
+                        .takes_value(true)
+                        .validator(validator_u64)
+                        .default_value("300")
+                        .help("Regeneration interval in seconds."),
+                )
+                .arg(
+                    Arg::with_name("UNITS_DIR")
+                        .long("units-dir")
+                        .takes_value(true)
+                        .help("Directory to write units to. Defaults to /etc/systemd/system, or systemd/user in the user config directory with --user."),
+                ),
+        )
+        .subcommand(
+            App::new("fleet")
+                .about("Display a condensed table of several hosts, from the JSON data they serve with 'motd serve'")
+                .arg(
+                    Arg::with_name("URLS")
+                        .multiple_values(true)
+                        .help("URLs of host JSON data, like http://host:8080/json. Defaults to hosts of the [fleet] config section."),
+                ),
+        )
+        .subcommand(
+            App::new("serve")
+                .about("Serve section data over HTTP: JSON at /json, Prometheus metrics at /metrics, and text output at /html")
+                .arg(
+                    Arg::with_name("LISTEN")
+                        .short('l')
+                        .long("listen")
+                        .takes_value(true)
+                        .validator(|s| SocketAddr::from_str(s).map(|_| ()).map_err(|e| e.to_string()))
+                        .default_value("127.0.0.1:8080")
+                        .help("Address and port to listen on."),
+                )
+                .arg(
+                    Arg::with_name("CACHE")
+                        .long("cache")
+                        .takes_value(true)
+                        .validator(validator_u64)
+                        .default_value("0")
+                        .help("Reuse responses for this number of seconds, instead of fetching data for each request."),
+                ),
+        )
+        .subcommand(
+            App::new("dump-config")
+                .about("Output a config file with all available keys and their current values"),
+        )
+        .subcommand(
+            App::new("history")
+                .about("Output metric samples recorded in watch and daemon modes")
+                .arg(
+                    Arg::with_name("METRIC")
+                        .help("Metric to output samples of, with its labels. If unset, summarize all metrics."),
+                )
+                .arg(
+                    Arg::with_name("HOURS")
+                        .long("hours")
+                        .takes_value(true)
+                        .global(true)
+                        .validator(validator_u64)
+                        .default_value("24")
+                        .help("Only output samples of the last HOURS hours."),
+                )
+                .subcommand(
+                    App::new("export")
+                        .about("Output samples of all metrics as CSV rows of metric name, labels, value and timestamp"),
+                ),
+        )
+        .get_matches()
+}
+
+/// Get command line arguments, with defaults from the general config for display choices not
+/// given on the command line
+#[expect(clippy::too_many_lines)]
+fn parse_cl_args(matches: &ArgMatches, general: &config::GeneralConfig) -> anyhow::Result<CLArgs> {
+    let letters: Vec<&str> = match &general.sections {
+        Some(letters) if matches.occurrences_of("SECTIONS") == 0 => {
+            if let Some(letter) = letters
+                .iter()
+                .find(|l| (*l != plugin::LETTER) && Section::from_letter(l).is_none())
+            {
+                anyhow::bail!("Invalid section {letter:?} in config");
+            }
+            letters.iter().map(String::as_str).unique().collect()
+        }
+        _ => matches.values_of("SECTIONS").unwrap().unique().collect(),
+    };
+    let plugins_position = letters.iter().position(|l| *l == plugin::LETTER);
+    let sections = letters
+        .into_iter()
+        // Letters are validated by clap
+        .filter_map(Section::from_letter)
         .collect();
-    let term_columns: usize = match isize::from_str(matches.value_of("COLUMNS").unwrap()).unwrap() {
+    let columns = match general.columns {
+        Some(columns) if matches.occurrences_of("COLUMNS") == 0 => columns,
+        _ => isize::from_str(matches.value_of("COLUMNS").unwrap()).unwrap(),
+    };
+    let term_columns: usize = match columns {
         0 => {
             // Autodetect
             termsize::get()
@@ -235,66 +1276,666 @@ fn parse_cl_args() -> CLArgs {
         // Passthrough
         v => v as usize,
     };
-    let show_section_titles = !matches.is_present("NO_TITLES");
+    let show_section_titles = general.titles && !matches.is_present("NO_TITLES");
+    let temp_unit = matches.value_of("TEMP_UNIT").map(|u| match u {
+        "fahrenheit" => config::TempUnit::Fahrenheit,
+        _ => config::TempUnit::Celsius,
+    });
+    let fs_display = matches.value_of("FS_DISPLAY").map(|d| match d {
+        "available" => config::FsDisplay::Available,
+        _ => config::FsDisplay::Used,
+    });
+    let format = match general.format {
+        Some(format) if matches.occurrences_of("FORMAT") == 0 => format,
+        _ => match matches.value_of("FORMAT").unwrap() {
+            "json" => OutputFormat::Json,
+            "yaml" => OutputFormat::Yaml,
+            "prometheus" => OutputFormat::Prometheus,
+            "csv" => OutputFormat::Csv,
+            "influx" => OutputFormat::Influx,
+            "nagios" => OutputFormat::Nagios,
+            _ => OutputFormat::Text,
+        },
+    };
+    let color = match general.color {
+        Some(color) if matches.occurrences_of("COLOR") == 0 => color,
+        _ => match matches.value_of("COLOR").unwrap() {
+            "always" => ColorMode::Always,
+            "never" => ColorMode::Never,
+            _ => ColorMode::Auto,
+        },
+    };
+    // A Nagios check exits with its status code after a single output
+    anyhow::ensure!(
+        format != OutputFormat::Nagios
+            || !(matches.is_present("WATCH") || matches.is_present("DAEMON")),
+        "Nagios format can not be used in watch or daemon mode"
+    );
 
-    CLArgs {
+    Ok(CLArgs {
         term_columns,
         sections,
+        plugins_position,
         show_section_titles,
+        temp_unit,
+        fs_display,
+        format,
+        color,
+        template: matches.value_of("TEMPLATE").map(PathBuf::from),
+        compact: general.compact || matches.is_present("COMPACT"),
+        quiet: matches.is_present("QUIET"),
+        watch: matches
+            .value_of("WATCH")
+            .map(|i| Duration::from_secs(u64::from_str(i).unwrap().max(1))),
+        output: matches.value_of("OUTPUT").map(PathBuf::from),
+        daemon: matches
+            .value_of("DAEMON")
+            .map(|i| Duration::from_secs(u64::from_str(i).unwrap().max(1))),
+        update_cache: matches.is_present("UPDATE_CACHE"),
+        cached: matches.is_present("CACHED"),
+        cached_max_age: Duration::from_secs(
+            u64::from_str(matches.value_of("CACHED_MAX_AGE").unwrap()).unwrap(),
+        ),
+        refresh: matches.is_present("REFRESH"),
+        mqtt: matches.is_present("MQTT"),
+        timings: matches.is_present("TIMINGS"),
+        sysroot: matches.value_of("SYSROOT").map(PathBuf::from),
+        install_service: matches.subcommand_matches("install-service").map(|m| {
+            InstallServiceArgs {
+                user: m.is_present("USER"),
+                output: m.value_of("OUTPUT").map(PathBuf::from),
+                cache: m.is_present("CACHE"),
+                interval: Duration::from_secs(
+                    u64::from_str(m.value_of("INTERVAL").unwrap())
+                        .unwrap()
+                        .max(1),
+                ),
+                units_dir: m.value_of("UNITS_DIR").map(PathBuf::from),
+            }
+        }),
+        fleet: matches.subcommand_matches("fleet").map(|m| {
+            m.values_of("URLS")
+                .map(|v| v.map(ToOwned::to_owned).collect())
+                .unwrap_or_default()
+        }),
+        serve: matches.subcommand_matches("serve").map(|m| ServeArgs {
+            listen: SocketAddr::from_str(m.value_of("LISTEN").unwrap()).unwrap(),
+            cache: Duration::from_secs(u64::from_str(m.value_of("CACHE").unwrap()).unwrap()),
+        }),
+        history: matches.subcommand_matches("history").map(|m| HistoryArgs {
+            metric: m.value_of("METRIC").map(ToOwned::to_owned),
+            export: m.subcommand_matches("export").is_some(),
+            since: Duration::from_hours(u64::from_str(m.value_of("HOURS").unwrap()).unwrap()),
+        }),
+        dump_config: matches.subcommand_matches("dump-config").is_some(),
+    })
+}
+
+/// Install systemd units, and explain how to enable them
+fn install_service(args: &InstallServiceArgs) -> anyhow::Result<()> {
+    let opts = service::ServiceOptions {
+        user: args.user,
+        output: if args.cache {
+            None
+        } else if let Some(output) = &args.output {
+            Some(output.clone())
+        } else {
+            Some(service::default_output_filepath(args.user)?)
+        },
+        interval: args.interval,
+        units_dir: match &args.units_dir {
+            Some(units_dir) => units_dir.clone(),
+            None => service::default_units_dir(args.user)?,
+        },
+    };
+    for unit_filepath in service::install(&opts).context("Failed to write units")? {
+        println!("Written {}", unit_filepath.display());
     }
+    let systemctl = if args.user {
+        "systemctl --user"
+    } else {
+        "systemctl"
+    };
+    println!("Enable with: {systemctl} daemon-reload && {systemctl} enable --now motd.timer");
+    Ok(())
+}
+
+/// Output recorded samples of a metric, or a summary of all metrics
+fn output_history(args: &HistoryArgs, cfg: &config::HistoryConfig) -> anyhow::Result<()> {
+    let store = history::Store::open(cfg)?;
+    let since = history::now().saturating_sub(args.since.as_secs());
+    let mut out = io::stdout().lock();
+    if args.export {
+        let mut rows = String::new();
+        csv::write_header(&mut rows)?;
+        for record in store.read()?.into_iter().filter(|r| r.ts >= since) {
+            csv::write_row(&mut rows, &record.key, record.value, record.ts)?;
+        }
+        write!(out, "{rows}")?;
+        return Ok(());
+    }
+    if let Some(metric) = args.metric.as_deref() {
+        for (ts, value) in store.query(metric, since)? {
+            writeln!(out, "{ts}\t{value}")?;
+        }
+        return Ok(());
+    }
+    let mut metrics: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for record in store.read()?.into_iter().filter(|r| r.ts >= since) {
+        metrics.entry(record.key).or_default().push(record.value);
+    }
+    for (metric, values) in metrics {
+        let recent: Vec<f32> = values
+            .iter()
+            .skip(values.len().saturating_sub(cfg.samples))
+            .map(|v| *v as f32)
+            .collect();
+        writeln!(
+            out,
+            "{metric}\t{} samples\tlast {}\t{}",
+            values.len(),
+            values.last().copied().unwrap_or_default(),
+            history::sparkline(&recent)
+        )?;
+    }
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
-    let cl_args = parse_cl_args();
+    let matches = cl_matches();
     let cfg = config::parse_config().context("Failed to parse config file")?;
+    let mut cl_args = parse_cl_args(&matches, &cfg.general)?;
+    if let Some(install_args) = &cl_args.install_service {
+        return install_service(install_args);
+    }
+    if cl_args.dump_config {
+        print!("{}", config::dump(&cfg)?);
+        return Ok(());
+    }
+    if cl_args.cached {
+        let output = cache::read(use_color(cl_args.color, true), cl_args.cached_max_age);
+        if let Some(output) = &output {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(output)?;
+            stdout.flush()?;
+        }
+        // No thread was started yet, so the process can be forked
+        if cl_args.refresh && cache::fork_refresher()? {
+            cl_args.cached = false;
+            cl_args.update_cache = true;
+        } else if output.is_some() {
+            return Ok(());
+        }
+    }
+    set_config_globals(&cfg);
+    if let Some(sysroot) = &cl_args.sysroot {
+        sysroot::set_root(sysroot.clone());
+    }
+    let (cl_args, cfg) = apply_cl_args(cl_args, cfg);
+    if let Some(history_args) = &cl_args.history {
+        return output_history(history_args, &cfg.history);
+    }
 
     module::CPU_COUNT.store(num_cpus::get(), Ordering::SeqCst);
+    set_output_globals(&cl_args);
+
+    if cfg.history.store && (cfg.history.anomaly_factor > 0.0) {
+        let store = history::Store::open(&cfg.history)?;
+        history::set_baselines(store.baselines(cfg.history.anomaly_factor)?);
+    }
+
+    if let Some(urls) = &cl_args.fleet {
+        return output_fleet(urls, &cfg);
+    }
+
+    let providers: &'static provider::Providers =
+        Box::leak(Box::new(provider::Providers::default()));
+    if let Some(serve_args) = &cl_args.serve {
+        reload::install_handler()?;
+        return serve(&matches, (cl_args.clone(), cfg), serve_args, providers);
+    }
+    let Some(interval) = cl_args.watch.or(cl_args.daemon) else {
+        // State is not kept between Nagios checks, so sections that are not OK are logged at each
+        // check, and webhooks are not notified to avoid repeated notifications
+        let alerter = if cl_args.format == OutputFormat::Nagios && cfg.alert.log.is_some() {
+            Some(alert::Alerter::new(&cfg.alert)?.without_webhooks())
+        } else {
+            None
+        };
+        let publisher = if cl_args.mqtt {
+            Some(mqtt::Publisher::new(&cfg.mqtt)?)
+        } else {
+            None
+        };
+        let observers = Observers {
+            alerter: alerter.as_ref(),
+            publisher: publisher.as_ref(),
+            ..Observers::default()
+        };
+        let exit_code = output_once(&cl_args, &cfg, providers, observers)?;
+        if exit_code != 0 {
+            // Exiting skips destructors
+            drop(alerter);
+            drop(publisher);
+            process::exit(exit_code);
+        }
+        return Ok(());
+    };
+    let mut observers = OwnedObservers::new(&cl_args, &cfg)?;
+    reload::install_handler()?;
+    let mut state = (cl_args, cfg);
+    let mut last_prune: Option<Instant> = None;
+    loop {
+        if reload_if_requested(&matches, &mut state) {
+            if let Err(err) = observers.reload(&state.0, &state.1) {
+                eprintln!("Failed to apply reloaded config, keeping previous outputs: {err:#}");
+            }
+        }
+        if let Some(store) = &observers.store {
+            if last_prune.is_none_or(|ts| ts.elapsed() >= history::PRUNE_INTERVAL) {
+                let retention = Duration::from_hours(state.1.history.retention_days * 24);
+                if let Err(err) = store.prune(history::now().saturating_sub(retention.as_secs())) {
+                    eprintln!("Failed to prune metric store: {err:#}");
+                }
+                last_prune = Some(Instant::now());
+            }
+        }
+        output_once(&state.0, &state.1, providers, observers.get())?;
+        thread::sleep(interval);
+    }
+}
+
+/// Set output settings that depend on config only, shared by all sections
+fn set_config_globals(cfg: &config::Config) {
+    style::set_theme(cfg.theme.clone());
+    icons::set_config(cfg.icons);
+    fmt::set_display(cfg.display);
+    i18n::set_locale(cfg.locale.unwrap_or_else(i18n::env_locale));
+}
+
+/// Apply command line overrides to config, and insert plugin sections
+fn apply_cl_args(mut cl_args: CLArgs, mut cfg: config::Config) -> (CLArgs, Arc<config::Config>) {
+    if let Some(temp_unit) = cl_args.temp_unit {
+        cfg.temp.unit = temp_unit;
+    }
+    if let Some(fs_display) = cl_args.fs_display {
+        cfg.fs.display = fs_display;
+    }
+    if let Some(template) = &cl_args.template {
+        cfg.template = Some(template.clone());
+    }
+    if cl_args.quiet {
+        cfg.quiet = true;
+    }
+    // Sections that time out keep being fetched in the background, so the config must outlive
+    // any single run, and a reload
+    let cfg = Arc::new(cfg);
+    plugin::insert_sections(
+        &mut cl_args.sections,
+        cl_args.plugins_position,
+        &cfg.plugins,
+    );
+    (cl_args, cfg)
+}
+
+/// Set output settings shared by all sections
+fn set_output_globals(cl_args: &CLArgs) {
     module::TERM_COLUMNS.store(cl_args.term_columns, Ordering::SeqCst);
+    // Cache always stores color output, plain variant is derived from it
+    module::COLOR.store(
+        cl_args.update_cache || use_color(cl_args.color, cl_args.output.is_none()),
+        Ordering::SeqCst,
+    );
+}
+
+/// Reload config and arguments depending on it if requested with SIGHUP, keeping the current
+/// ones if the new config is invalid, and return whether they were replaced
+fn reload_if_requested(matches: &ArgMatches, state: &mut (CLArgs, Arc<config::Config>)) -> bool {
+    if !reload::requested() {
+        return false;
+    }
+    let reloaded = config::parse_config()
+        .context("Failed to parse config file")
+        .and_then(|cfg| Ok((parse_cl_args(matches, &cfg.general)?, cfg)));
+    match reloaded {
+        Ok((cl_args, cfg)) => {
+            set_config_globals(&cfg);
+            *state = apply_cl_args(cl_args, cfg);
+            set_output_globals(&state.0);
+            eprintln!("Config reloaded");
+            true
+        }
+        Err(err) => {
+            eprintln!("Failed to reload config, keeping the previous one: {err:#}");
+            false
+        }
+    }
+}
 
-    thread::scope(|scope| -> anyhow::Result<_> {
+/// Output fleet overview table
+fn output_fleet(urls: &[String], cfg: &config::Config) -> anyhow::Result<()> {
+    let url_hosts: Vec<config::FleetHost> = urls
+        .iter()
+        .map(|u| config::FleetHost {
+            name: None,
+            url: u.clone(),
+        })
+        .collect();
+    let hosts = if url_hosts.is_empty() {
+        &cfg.fleet.hosts
+    } else {
+        &url_hosts
+    };
+    anyhow::ensure!(
+        !hosts.is_empty(),
+        "No hosts on command line or in the [fleet] config section"
+    );
+    let mut table = fleet::fetch(hosts, &cfg.fs).to_string();
+    if !module::COLOR.load(Ordering::SeqCst) {
+        table = fmt::strip_ansi(&table);
+    }
+    io::stdout().write_all(table.as_bytes())?;
+    Ok(())
+}
+
+/// Serve sections over HTTP, without colors
+fn serve(
+    matches: &ArgMatches,
+    state: (CLArgs, Arc<config::Config>),
+    serve_args: &ServeArgs,
+    providers: &'static provider::Providers,
+) -> anyhow::Result<()> {
+    module::COLOR.store(false, Ordering::SeqCst);
+    // Responses are rendered one at a time
+    let state = Mutex::new(state);
+    server::serve(serve_args.listen, serve_args.cache, |endpoint| {
+        let (cl_args, cfg) = {
+            let mut state = state.lock().unwrap();
+            reload_if_requested(matches, &mut state);
+            state.clone()
+        };
+        module::COLOR.store(false, Ordering::SeqCst);
+        let format = match endpoint {
+            server::Endpoint::Json => OutputFormat::Json,
+            server::Endpoint::Metrics => OutputFormat::Prometheus,
+            server::Endpoint::Html => OutputFormat::Text,
+        };
+        let endpoint_args = CLArgs { format, ..cl_args };
+        let mut body = Vec::new();
+        output_all(
+            &endpoint_args,
+            &cfg,
+            providers,
+            Observers::default(),
+            &mut body,
+        )?;
+        Ok(body)
+    })
+    .with_context(|| format!("Failed to serve on {}", serve_args.listen))
+}
+
+/// Output all sections to stdout, or atomically replace output or cache files, and get the
+/// process exit code
+fn output_once(
+    cl_args: &CLArgs,
+    cfg: &Arc<config::Config>,
+    providers: &'static provider::Providers,
+    observers: Observers,
+) -> anyhow::Result<i32> {
+    if let Some(output_filepath) = cl_args.output.as_deref() {
+        let mut buffer = Vec::new();
+        let exit_code = output_all(cl_args, cfg, providers, observers, &mut buffer)?;
+        cache::write_atomic(output_filepath, &buffer)
+            .with_context(|| format!("Failed to write {}", output_filepath.display()))?;
+        Ok(exit_code)
+    } else if cl_args.update_cache {
+        let mut buffer = Vec::new();
+        let exit_code = output_all(cl_args, cfg, providers, observers, &mut buffer)?;
+        cache::write(&buffer).context("Failed to write cache")?;
+        Ok(exit_code)
+    } else if cl_args.mqtt {
+        output_all(cl_args, cfg, providers, observers, &mut io::sink())
+    } else {
+        // Single buffered writer, so that output is not split in many small writes
+        let mut out = io::BufWriter::new(io::stdout().lock());
+        if cl_args.watch.is_some() {
+            // Clear screen and move cursor to top left
+            write!(out, "\x1b[2J\x1b[H")?;
+        }
+        let exit_code = output_all(cl_args, cfg, providers, observers, &mut out)?;
+        out.flush()?;
+        Ok(exit_code)
+    }
+}
+
+/// Store fetched data in metric history if sparklines are enabled, and in metric store if any
+fn with_history(
+    data: anyhow::Result<ModuleData>,
+    cfg: &config::HistoryConfig,
+    store: Option<&history::Store>,
+) -> anyhow::Result<ModuleData> {
+    let mut data = data?;
+    if cfg.sparklines {
+        data.update_history(cfg);
+    }
+    if let Some(store) = store {
+        store.record(&data, history::now())?;
+    }
+    Ok(data)
+}
+
+/// Fetch and output all sections, and get the process exit code, that is only not 0 for Nagios
+/// checks
+fn output_all(
+    cl_args: &CLArgs,
+    cfg: &Arc<config::Config>,
+    providers: &'static provider::Providers,
+    observers: Observers,
+    out: &mut dyn io::Write,
+) -> anyhow::Result<i32> {
+    let timings = cl_args.timings.then(timings::Timings::new);
+    let timings = timings.as_ref();
+    let report_timings = || {
+        if let Some(timings) = timings {
+            eprint!("{}", timings.report(&cl_args.sections));
+        }
+    };
+    providers.refresh(cfg, &cl_args.sections);
+    // Each section is fetched on its own thread rather than as an async task: fetchers only make
+    // blocking calls (procfs and sysfs reads, statvfs, netlink and D-Bus sockets, commands), that
+    // an async runtime would also run on a blocking thread each, without being able to cancel them
+    let result = thread::scope(|scope| -> anyhow::Result<_> {
         let mut section_futs: Vec<thread::ScopedJoinHandle<anyhow::Result<ModuleData>>> =
             Vec::with_capacity(cl_args.sections.len());
+        let (finished_tx, finished_rx) = mpsc::channel();
 
-        for section in &cl_args.sections {
-            let section_fut = match section {
-                Section::Load => scope.spawn(load::fetch),
-                Section::Mem => scope.spawn(mem::fetch),
-                Section::Swap => scope.spawn(|| {
-                    // TODO fetch only once?
-                    let mi = mem::fetch()?;
-                    if let ModuleData::Memory(mi) = mi {
-                        Ok(ModuleData::Swap(mem::SwapInfo::from(mi)))
-                    } else {
-                        unreachable!();
+        for (index, &section) in cl_args.sections.iter().enumerate() {
+            let finished_tx = finished_tx.clone();
+            let section_fut = scope.spawn(move || {
+                let fetch_start = Instant::now();
+                let fetched = section.fetch_with_timeout(cfg, providers);
+                if let Some(timings) = timings {
+                    timings.record(section, fetch_start.elapsed(), fetched.as_ref().ok());
+                }
+                let data = with_history(fetched, &cfg.history, observers.store);
+                if let Ok(data) = &data {
+                    if let Some(alerter) = observers.alerter {
+                        alerter.check(section.key, section.title(), data);
                     }
-                }),
-                Section::FS => scope.spawn(|| fs::fetch(&cfg.fs)),
-                Section::Temps => scope.spawn(|| temp::fetch(&cfg.temp)),
-                Section::SDFailedUnits => scope.spawn(systemd::fetch),
-                Section::Network => scope.spawn(net::fetch),
-            };
+                    if let Some(publisher) = observers.publisher {
+                        publisher.publish(section.key, data);
+                    }
+                }
+                // Only live text output waits for this, receiver may be gone
+                let _ = finished_tx.send(index);
+                data
+            });
             section_futs.push(section_fut);
         }
+        // Receiving fails once all threads ended
+        drop(finished_tx);
 
-        for (section_fut, section) in section_futs.into_iter().zip(cl_args.sections.iter()) {
-            let delayed = !section_fut.is_finished();
-            if delayed {
-                eprint!("{LOADING_MSG}");
+        if cl_args.format == OutputFormat::Nagios {
+            return output_nagios(section_futs, &cl_args.sections, out);
+        }
+
+        if cl_args.format != OutputFormat::Text {
+            output_structured(
+                section_futs,
+                &cl_args.sections,
+                cl_args.format,
+                cfg.quiet,
+                out,
+            )?;
+        } else if cl_args.compact {
+            output_compact(section_futs, &cl_args.sections, cfg.quiet, out)?;
+        } else if let Some(template_filepath) = cfg.template.as_deref() {
+            output_template(
+                section_futs,
+                &cl_args.sections,
+                template_filepath,
+                cfg.quiet,
+                out,
+            )?;
+        } else {
+            output_text(section_futs, finished_rx, cl_args, cfg, out)?;
+        }
+        Ok(0)
+    });
+    // Report after output, so that it does not interleave with it
+    out.flush()?;
+    report_timings();
+    result
+}
+
+/// Output sections as text, consecutive side by side sections in columns
+#[expect(clippy::too_many_lines)]
+fn output_text(
+    section_futs: Vec<thread::ScopedJoinHandle<anyhow::Result<ModuleData>>>,
+    finished_rx: mpsc::Receiver<usize>,
+    cl_args: &CLArgs,
+    cfg: &config::Config,
+    out: &mut dyn io::Write,
+) -> anyhow::Result<()> {
+    let display = &cfg.display;
+    let show_title = cl_args.show_section_titles;
+    let mut section_futs = section_futs.into_iter();
+    let mut sections = cl_args.sections.as_slice();
+    let max_columns = layout::max_columns(&cfg.layout, cl_args.term_columns);
+    let mut started = false;
+    if cfg.banner.enabled {
+        let mut banner = banner::render(&cfg.banner, cl_args.term_columns)?;
+        if let Some(banner_style) = cfg.banner.style {
+            if module::COLOR.load(Ordering::SeqCst) {
+                banner = banner_style.paint(banner).to_string();
             }
-            let lines = section_fut
-                .join()
-                .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))?
-                .map(|d| format!("{d}"))
-                .map_err(|e| format!("{e}"));
-            output_section(
-                pretty_section_name(section),
-                lines,
-                cl_args.show_section_titles,
-                delayed,
+        }
+        write_block(
+            banner.as_bytes(),
+            display.section_spacing,
+            &mut started,
+            out,
+        )?;
+    }
+    if cfg.greeting.enabled {
+        let greeting = greeting::render(&cfg.greeting)?;
+        write_block(
+            greeting.as_bytes(),
+            display.section_spacing,
+            &mut started,
+            out,
+        )?;
+    }
+    // Consecutive sections waiting to be output under a single header
+    let mut merged = Vec::new();
+    // Merged headers need all their sections, so they can not be drawn as sections finish
+    let mut live = (live_output(cl_args) && !(show_title && display.merge_headers)).then(|| {
+        LiveRegion::new(
+            finished_rx,
+            show_title,
+            cl_args.term_columns,
+            display.section_spacing,
+            cfg.quiet,
+        )
+    });
+    for group in layout_groups(sections, &cfg.layout) {
+        let index = cl_args.sections.len() - sections.len();
+        let (group_sections, next_sections) = sections.split_at(group);
+        sections = next_sections;
+        let mut block = Vec::new();
+        if group > 1 {
+            if let Some(live) = live.as_mut() {
+                live.finish(&mut started, out)?;
+            }
+            output_merged_sections(&merged, cl_args.term_columns, &mut block)?;
+            write_block(&block, display.section_spacing, &mut started, out)?;
+            merged.clear();
+            block.clear();
+            // Show previous sections while waiting for the group
+            out.flush()?;
+            output_columns(
+                section_futs.by_ref().take(group).collect(),
+                group_sections,
+                show_title,
                 cl_args.term_columns,
-            );
+                max_columns,
+                cfg.quiet,
+                &mut block,
+            )?;
+            write_block(&block, display.section_spacing, &mut started, out)?;
+            continue;
         }
+        let section = group_sections[0];
+        let Some(section_fut) = section_futs.next() else {
+            break;
+        };
+        if let Some(live) = live.as_mut() {
+            live.push(index, section, section_fut, &mut started, out)?;
+            continue;
+        }
+        let delayed = !section_fut.is_finished() && io::stderr().is_terminal();
+        if delayed {
+            // Show previous sections while waiting
+            out.flush()?;
+            eprint!("{LOADING_MSG}");
+        }
+        let lines = section_lines(
+            section,
+            section_fut
+                .join()
+                .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))?,
+            cfg.quiet,
+        );
+        if delayed {
+            eprint!("\r{}\r", " ".repeat(LOADING_MSG.len()));
+        }
+        if show_title && display.merge_headers {
+            match lines {
+                Ok(lines) if !lines.is_empty() => merged.push((section, lines)),
+                Ok(_) => {}
+                Err(err) => output_section_error(section.title(), &err),
+            }
+            continue;
+        }
+        output_section(
+            section.title(),
+            section.icon,
+            lines,
+            show_title,
+            cl_args.term_columns,
+            &mut block,
+        )?;
+        write_block(&block, display.section_spacing, &mut started, out)?;
+    }
+    if let Some(live) = live.as_mut() {
+        live.finish(&mut started, out)?;
+    }
+    let mut block = Vec::new();
+    output_merged_sections(&merged, cl_args.term_columns, &mut block)?;
+    write_block(&block, display.section_spacing, &mut started, out)?;
+    if started && display.trailing_separator {
+        writeln!(out, "{}", format_separator(cl_args.term_columns))?;
+    }
 
-        Ok(())
-    })
+    Ok(())
 }
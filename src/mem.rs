@@ -8,21 +8,64 @@ use std::{
 };
 
 use ansi_term::Style;
+use serde::ser::SerializeMap as _;
 
 use crate::{
-    fmt::format_kmgt,
-    module::{ModuleData, TERM_COLUMNS},
+    config,
+    fmt::{ansi_width, display, format_size, partial_bar_char, text_width},
+    history,
+    i18n::{self, Label},
+    icons,
+    module::{BuiltinModule, Module, TERM_COLUMNS},
+    prometheus,
+    provider::{Providers, Source},
+    style::{self, Severity},
+    sysroot,
 };
 
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) struct MemInfo {
     /// Map of memory usage info, unit is kB or page count
     vals: HashMap<String, u64>,
+    /// Recent usage sparkline
+    sparkline: Option<String>,
 }
 
 pub(crate) struct SwapInfo {
     mem: MemInfo,
 }
 
+impl serde::Serialize for MemInfo {
+    /// Serialize main memory values, in bytes
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let total = self.vals["MemTotal"] * 1024;
+        let free = self.vals["MemFree"] * 1024;
+        let cached = self.vals["Cached"] * 1024;
+        let buffers = self.vals["Buffers"] * 1024;
+        let mut map = serializer.serialize_map(Some(6))?;
+        map.serialize_entry("total", &total)?;
+        map.serialize_entry("used", &(total - cached - buffers - free))?;
+        map.serialize_entry("free", &free)?;
+        map.serialize_entry("dirty", &(self.vals["Dirty"] * 1024))?;
+        map.serialize_entry("cached", &cached)?;
+        map.serialize_entry("buffers", &buffers)?;
+        map.end()
+    }
+}
+
+impl serde::Serialize for SwapInfo {
+    /// Serialize swap values, in bytes
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let total = self.mem.vals["SwapTotal"] * 1024;
+        let free = self.mem.vals["SwapFree"] * 1024;
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("total", &total)?;
+        map.serialize_entry("used", &(total - free))?;
+        map.serialize_entry("free", &free)?;
+        map.end()
+    }
+}
+
 impl From<MemInfo> for SwapInfo {
     fn from(mi: MemInfo) -> Self {
         Self { mem: mi }
@@ -30,9 +73,9 @@ impl From<MemInfo> for SwapInfo {
 }
 
 /// Fetch memory usage info from procfs
-pub(crate) fn fetch() -> anyhow::Result<ModuleData> {
+pub(crate) fn fetch() -> anyhow::Result<MemInfo> {
     let mut vals = HashMap::new();
-    let file = File::open("/proc/meminfo")?;
+    let file = File::open(sysroot::path("/proc/meminfo"))?;
     let reader = BufReader::new(file);
     for line in reader.lines() {
         // Parse line
@@ -57,27 +100,51 @@ pub(crate) fn fetch() -> anyhow::Result<ModuleData> {
         vals.insert(key, val);
     }
 
-    Ok(ModuleData::Memory(MemInfo { vals }))
+    Ok(MemInfo {
+        vals,
+        sparkline: None,
+    })
 }
 
-/// Memory bar section
-struct BarPart {
+/// Bar section
+pub(crate) struct BarPart {
     /// Section text
-    label: Vec<String>,
+    pub label: Vec<String>,
     /// Percentage of full bar this section should fill
-    prct: f32,
+    pub prct: f32,
     /// Bar text style
-    text_style: Style,
+    pub text_style: Style,
     /// Bar fill char style
-    fill_style: Style,
+    pub fill_style: Style,
     /// Char to use to fill bar
-    bar_char: char,
+    pub bar_char: char,
+}
+
+/// Format mebibytes as gigabytes or gibibytes, or exact bytes, depending on display config, with
+/// leading space
+fn format_gb(mb: u64) -> String {
+    let cfg = display();
+    let gb = match cfg.units {
+        config::SizeUnits::Binary => mb as f32 / 1024.0,
+        config::SizeUnits::Si => mb as f32 * 1024.0 * 1024.0 / 1_000_000_000.0,
+        config::SizeUnits::Exact => return format!(" {}", format_size(mb * 1024 * 1024, 0)),
+    };
+    format!(" {gb:.*}GB", cfg.precision)
 }
 
 /// Print memory bar
 fn display_bar(parts: &[BarPart], f: &mut dyn fmt::Write) -> fmt::Result {
+    write_bar(parts, TERM_COLUMNS.load(Ordering::SeqCst), f)?;
+    writeln!(f)
+}
+
+/// Write bar of a given length (including borders), without line return
+pub(crate) fn write_bar(
+    parts: &[BarPart],
+    term_columns: usize,
+    f: &mut dyn fmt::Write,
+) -> fmt::Result {
     // Compute part lengths and handle rounding
-    let term_columns = TERM_COLUMNS.load(Ordering::SeqCst);
     let mut part_lens_int: Vec<usize> = parts
         .iter()
         .map(|part| ((term_columns - 2) as f32 * part.prct / 100.0) as usize)
@@ -105,13 +172,37 @@ fn display_bar(parts: &[BarPart], f: &mut dyn fmt::Write) -> fmt::Result {
             .1 += 1;
     }
 
-    write!(f, "▕")?;
+    // Draw partial blocks where a filled part is followed by an empty one, in the cell containing
+    // their exact boundary, at the end of the filled part or the start of the empty one
+    let bar = display().bar;
+    let mut heads: Vec<Option<(char, Style)>> = vec![None; parts.len()];
+    let mut tails: Vec<Option<char>> = vec![None; parts.len()];
+    let mut boundary = 0.0;
+    let mut boundary_int = 0;
+    for (i, (part, next_part)) in parts.iter().zip(parts.iter().skip(1)).enumerate() {
+        boundary += (term_columns - 2) as f32 * part.prct / 100.0;
+        boundary_int += part_lens_int[i];
+        if part.bar_char != bar.fill || next_part.bar_char != bar.empty {
+            continue;
+        }
+        let Some(partial) = partial_bar_char(boundary.fract()) else {
+            continue;
+        };
+        let cell = boundary as usize;
+        if cell == boundary_int {
+            heads[i + 1] = Some((partial, part.fill_style));
+        } else if cell + 1 == boundary_int {
+            tails[i] = Some(partial);
+        }
+    }
 
-    for (part, part_len) in parts.iter().zip(part_lens_int) {
+    write!(f, "{}", bar.left)?;
+
+    for (((part, part_len), head), tail) in parts.iter().zip(part_lens_int).zip(heads).zip(tails) {
         // Build longest label that fits
         let mut label = String::new();
         for label_part in &part.label {
-            if label.len() + label_part.len() <= part_len {
+            if ansi_width(&label) + ansi_width(label_part) <= part_len {
                 label += label_part;
             } else {
                 break;
@@ -119,53 +210,139 @@ fn display_bar(parts: &[BarPart], f: &mut dyn fmt::Write) -> fmt::Result {
         }
 
         // Center bar text inside fill chars
-        let label_len = label.len();
-        let fill_count_before = (part_len - label_len) / 2;
-        let fill_count_after = if (part_len - label_len) % 2 == 1 {
+        let label_len = ansi_width(&label);
+        let mut fill_count_before = (part_len - label_len) / 2;
+        let mut fill_count_after = if (part_len - label_len) % 2 == 1 {
             fill_count_before + 1
         } else {
             fill_count_before
         };
-        write!(
+        if let Some((partial, style)) = head.filter(|_| fill_count_before > 0) {
+            write_painted(f, style, &partial.to_string())?;
+            fill_count_before -= 1;
+        }
+        let tail = tail.filter(|_| fill_count_after > 0);
+        if tail.is_some() {
+            fill_count_after -= 1;
+        }
+        write_painted(
             f,
-            "{}",
-            &part
-                .fill_style
-                .paint(part.bar_char.to_string().repeat(fill_count_before))
+            part.fill_style,
+            &part.bar_char.to_string().repeat(fill_count_before),
         )?;
-        write!(f, "{}", &part.text_style.paint(&label))?;
-        write!(
+        write_painted(f, part.text_style, &label)?;
+        write_painted(
             f,
-            "{}",
-            &part
-                .fill_style
-                .paint(part.bar_char.to_string().repeat(fill_count_after))
+            part.fill_style,
+            &part.bar_char.to_string().repeat(fill_count_after),
         )?;
+        if let Some(partial) = tail {
+            write_painted(f, part.fill_style, &partial.to_string())?;
+        }
     }
 
-    writeln!(f, "▏")?;
+    write!(f, "{}", bar.right)?;
 
     Ok(())
 }
 
+/// Write styled text, or nothing if it is empty, to avoid escape sequences around nothing
+fn write_painted(f: &mut dyn fmt::Write, style: Style, text: &str) -> fmt::Result {
+    if text.is_empty() {
+        Ok(())
+    } else {
+        write!(f, "{}", style.paint(text))
+    }
+}
+
+impl BuiltinModule for MemInfo {
+    const LETTER: &'static str = "m";
+    const KEY: &'static str = "memory";
+    const TITLE: Label = Label::MemoryTitle;
+    const ICON: icons::Icon = icons::Icon::Memory;
+    const SOURCES: &'static [Source] = &[Source::MemInfo];
+
+    fn max_lines(cfg: &config::MaxLinesConfig) -> Option<usize> {
+        cfg.memory
+    }
+
+    fn timeout(cfg: &config::TimeoutsConfig) -> Option<u64> {
+        cfg.memory
+    }
+
+    fn fetch(_cfg: &config::Config, providers: &Providers) -> anyhow::Result<Self> {
+        providers.meminfo()
+    }
+}
+
+impl Module for MemInfo {
+    /// Store memory usage percentage in history, and set its sparkline
+    fn update_history(&mut self, cfg: &config::HistoryConfig) {
+        let total = self.vals["MemTotal"];
+        let used = total
+            .saturating_sub(self.vals["Cached"])
+            .saturating_sub(self.vals["Buffers"])
+            .saturating_sub(self.vals["MemFree"]);
+        let used_prct = 100.0 * used as f32 / total as f32;
+        let recent = history::update("memory", &[("used_prct", used_prct)], cfg);
+        self.sparkline = recent.get("used_prct").map(|v| history::sparkline(v));
+    }
+
+    /// Memory usage has no warning threshold
+    fn retain_alerts(&mut self) -> bool {
+        false
+    }
+
+    /// Memory usage has no warning threshold
+    fn severity(&self) -> Severity {
+        Severity::Ok
+    }
+
+    /// Single line summary
+    fn compact(&self) -> String {
+        let total = self.vals["MemTotal"];
+        let used = total - self.vals["Cached"] - self.vals["Buffers"] - self.vals["MemFree"];
+        format!("mem {:.0}%", 100.0 * used as f32 / total as f32)
+    }
+
+    /// Write memory usage as Prometheus metrics
+    fn write_prometheus(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        prometheus::write_gauge(f, "memory_bytes", "Memory usage")?;
+        let total = self.vals["MemTotal"] * 1024;
+        let free = self.vals["MemFree"] * 1024;
+        let cached = self.vals["Cached"] * 1024;
+        let buffers = self.vals["Buffers"] * 1024;
+        for (kind, val) in [
+            ("total", total),
+            ("used", total - cached - buffers - free),
+            ("free", free),
+            ("dirty", self.vals["Dirty"] * 1024),
+            ("cached", cached),
+            ("buffers", buffers),
+        ] {
+            prometheus::write_sample(f, "memory_bytes", &[("type", kind)], val)?;
+        }
+        Ok(())
+    }
+}
+
 impl MemInfo {
     /// Print memory stat numbers
     fn display_stats(&self, keys: &[&str], total_key: &str, f: &mut dyn fmt::Write) -> fmt::Result {
-        let max_key_len = keys.iter().map(|x| x.len()).max().unwrap();
-        let mac_size_str_len = keys
+        let max_key_len = keys.iter().map(|x| text_width(x)).max().unwrap();
+        let max_size_str_len = keys
             .iter()
-            .map(|&x| format_kmgt(self.vals[x] * 1024, "B").len())
+            .map(|&x| text_width(&format_size(self.vals[x] * 1024, 0)))
             .max()
             .unwrap();
 
         for &key in keys {
-            let size_str = format_kmgt(self.vals[key] * 1024, "B");
             write!(
                 f,
                 "{}: {}{}",
                 key,
-                " ".repeat(max_key_len - key.len() + mac_size_str_len - size_str.len()),
-                size_str
+                " ".repeat(max_key_len - text_width(key)),
+                format_size(self.vals[key] * 1024, max_size_str_len)
             )?;
             if key != total_key {
                 write!(
@@ -201,36 +378,36 @@ impl fmt::Display for MemInfo {
 
         let used_prct = 100.0 * used_mem_mb as f32 / total_mem_mb as f32;
         let used_bar_text: Vec<String> = vec![
-            "Used".to_owned(),
-            format!(" {:.1}GB", used_mem_mb as f32 / 1024.0),
+            i18n::tr(Label::Used).to_owned(),
+            format_gb(used_mem_mb),
             format!(" ({used_prct:.1}%)"),
         ];
         mem_bar_parts.push(BarPart {
             label: used_bar_text,
             prct: used_prct,
-            text_style: Style::new().reverse(),
-            fill_style: Style::new(),
-            bar_char: '█',
+            text_style: style::theme().bar.reverse(),
+            fill_style: style::theme().bar,
+            bar_char: display().bar.fill,
         });
 
         let cached_prct = 100.0 * (cache_mem_mb + buffer_mem_mb) as f32 / total_mem_mb as f32;
         let cached_bar_text: Vec<String> = vec![
-            "Cached".to_owned(),
-            format!(" {:.1}GB", (cache_mem_mb + buffer_mem_mb) as f32 / 1024.0),
+            i18n::tr(Label::Cached).to_owned(),
+            format_gb(cache_mem_mb + buffer_mem_mb),
             format!(" ({cached_prct:.1}%)"),
         ];
         mem_bar_parts.push(BarPart {
             label: cached_bar_text,
             prct: cached_prct,
-            text_style: Style::new().dimmed().reverse(),
-            fill_style: Style::new().dimmed(),
-            bar_char: '█',
+            text_style: style::theme().secondary.reverse(),
+            fill_style: style::theme().secondary,
+            bar_char: display().bar.fill,
         });
 
         let free_prct = 100.0 * free_mem_mb as f32 / total_mem_mb as f32;
         let free_bar_text: Vec<String> = vec![
-            "Free".to_owned(),
-            format!(" {:.1}GB", free_mem_mb as f32 / 1024.0),
+            i18n::tr(Label::Free).to_owned(),
+            format_gb(free_mem_mb),
             format!(" ({free_prct:.1}%)"),
         ];
         mem_bar_parts.push(BarPart {
@@ -238,15 +415,76 @@ impl fmt::Display for MemInfo {
             prct: free_prct,
             text_style: Style::new(),
             fill_style: Style::new(),
-            bar_char: ' ',
+            bar_char: display().bar.empty,
         });
 
-        display_bar(&mem_bar_parts, f)?;
+        if let Some(sparkline) = self.sparkline.as_ref() {
+            let bar_len = TERM_COLUMNS
+                .load(Ordering::SeqCst)
+                .saturating_sub(text_width(sparkline) + 2);
+            write_bar(&mem_bar_parts, bar_len.max(3), f)?;
+            writeln!(f, "  {sparkline}")?;
+        } else {
+            display_bar(&mem_bar_parts, f)?;
+        }
 
         Ok(())
     }
 }
 
+impl BuiltinModule for SwapInfo {
+    const LETTER: &'static str = "s";
+    const KEY: &'static str = "swap";
+    const TITLE: Label = Label::SwapTitle;
+    const ICON: icons::Icon = icons::Icon::Swap;
+    const SOURCES: &'static [Source] = &[Source::MemInfo];
+
+    fn max_lines(cfg: &config::MaxLinesConfig) -> Option<usize> {
+        cfg.swap
+    }
+
+    fn timeout(cfg: &config::TimeoutsConfig) -> Option<u64> {
+        cfg.swap
+    }
+
+    fn fetch(_cfg: &config::Config, providers: &Providers) -> anyhow::Result<Self> {
+        Ok(Self::from(providers.meminfo()?))
+    }
+}
+
+impl Module for SwapInfo {
+    /// Swap usage has no warning threshold
+    fn retain_alerts(&mut self) -> bool {
+        false
+    }
+
+    /// Swap usage has no warning threshold
+    fn severity(&self) -> Severity {
+        Severity::Ok
+    }
+
+    /// Single line summary, empty if there is no swap
+    fn compact(&self) -> String {
+        let total = self.mem.vals["SwapTotal"];
+        if total == 0 {
+            return String::new();
+        }
+        let used = total - self.mem.vals["SwapFree"];
+        format!("swap {:.0}%", 100.0 * used as f32 / total as f32)
+    }
+
+    /// Write swap usage as Prometheus metrics
+    fn write_prometheus(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        prometheus::write_gauge(f, "swap_bytes", "Swap usage")?;
+        let total = self.mem.vals["SwapTotal"] * 1024;
+        let free = self.mem.vals["SwapFree"] * 1024;
+        for (kind, val) in [("total", total), ("used", total - free), ("free", free)] {
+            prometheus::write_sample(f, "swap_bytes", &[("type", kind)], val)?;
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for SwapInfo {
     /// Output swap info
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -262,22 +500,22 @@ impl fmt::Display for SwapInfo {
 
             let used_prct = 100.0 * used_swap_mb as f32 / total_swap_mb as f32;
             let used_bar_text: Vec<String> = vec![
-                "Used".to_owned(),
-                format!(" {:.1}GB", used_swap_mb as f32 / 1024.0),
+                i18n::tr(Label::Used).to_owned(),
+                format_gb(used_swap_mb),
                 format!(" ({used_prct:.1}%)"),
             ];
             swap_bar_parts.push(BarPart {
                 label: used_bar_text,
                 prct: used_prct,
-                text_style: Style::new().reverse(),
-                fill_style: Style::new(),
-                bar_char: '█',
+                text_style: style::theme().bar.reverse(),
+                fill_style: style::theme().bar,
+                bar_char: display().bar.fill,
             });
 
             let free_prct = 100.0 * free_swap_mb as f32 / total_swap_mb as f32;
             let free_bar_text: Vec<String> = vec![
-                "Swap free".to_owned(),
-                format!(" {:.1}GB", free_swap_mb as f32 / 1024.0),
+                i18n::tr(Label::SwapFree).to_owned(),
+                format_gb(free_swap_mb),
                 format!(" ({free_prct:.1}%)"),
             ];
             swap_bar_parts.push(BarPart {
@@ -285,7 +523,7 @@ impl fmt::Display for SwapInfo {
                 prct: free_prct,
                 text_style: Style::new(),
                 fill_style: Style::new(),
-                bar_char: ' ',
+                bar_char: display().bar.empty,
             });
 
             display_bar(&swap_bar_parts, f)?;
@@ -703,7 +941,7 @@ mod tests {
         .unwrap();
         assert_eq!(
             f,
-            "▕\u{1b}[4;31m#\u{1b}[0m\u{1b}[1;31mpart1\u{1b}[0m\u{1b}[4;31m#\u{1b}[0m\u{1b}[3;33m\u{1b}[0m\u{1b}[2;33mpart2PART2\u{1b}[0m\u{1b}[3;33m\u{1b}[0m\u{1b}[5;34m%%%%%%\u{1b}[0m\u{1b}[7;34mpart3PART3P_A_R_T_3\u{1b}[0m\u{1b}[5;34m%%%%%%\u{1b}[0m▏\n"
+            "▕\u{1b}[4;31m#\u{1b}[0m\u{1b}[1;31mpart1\u{1b}[0m\u{1b}[4;31m#\u{1b}[0m\u{1b}[2;33mpart2PART2\u{1b}[0m\u{1b}[5;34m%%%%%%\u{1b}[0m\u{1b}[7;34mpart3PART3P_A_R_T_3\u{1b}[0m\u{1b}[5;34m%%%%%%\u{1b}[0m▏\n"
         );
     }
 
@@ -714,7 +952,10 @@ mod tests {
         vals.insert("stat22222222".to_owned(), 1_234_567);
         vals.insert("stat3333".to_owned(), 123_456_789);
         vals.insert("itsatrap".to_owned(), 999);
-        let mem_info = MemInfo { vals };
+        let mem_info = MemInfo {
+            vals,
+            sparkline: None,
+        };
 
         let mut f = String::new();
         mem_info
@@ -736,18 +977,21 @@ mod tests {
         vals.insert("Cached".to_owned(), 3124);
         vals.insert("Buffers".to_owned(), 4321);
         vals.insert("itsatrap".to_owned(), 1024);
-        let mem_info = MemInfo { vals };
+        let mem_info = MemInfo {
+            vals,
+            sparkline: None,
+        };
 
         TERM_COLUMNS.store(80, Ordering::SeqCst);
         assert_eq!(
             format!("{}", &mem_info),
-            "MemTotal: 12.1 MB\nMemFree:   1.2 MB (10.0%)\nDirty:     2.1 MB (17.3%)\nCached:    3.1 MB (25.3%)\nBuffers:   4.2 MB (35.0%)\n▕████\u{1b}[7mUsed 0.0GB (33.3%)\u{1b}[0m████\u{1b}[2m█████████████\u{1b}[0m\u{1b}[2;7mCached 0.0GB (58.3%)\u{1b}[0m\u{1b}[2m█████████████\u{1b}[0m Free ▏\n"
+            "MemTotal: 12.1 MB\nMemFree:   1.2 MB (10.0%)\nDirty:     2.1 MB (17.3%)\nCached:    3.1 MB (25.3%)\nBuffers:   4.2 MB (35.0%)\n▕████\u{1b}[7mUsed 0.0GB (33.3%)\u{1b}[0m████\u{1b}[2m█████████████\u{1b}[0m\u{1b}[2;7mCached 0.0GB (58.3%)\u{1b}[0m\u{1b}[2m████████████\u{1b}[0m\u{1b}[2m▌\u{1b}[0m Free ▏\n"
         );
 
         TERM_COLUMNS.store(30, Ordering::SeqCst);
         assert_eq!(
             format!("{}", &mem_info),
-            "MemTotal: 12.1 MB\nMemFree:   1.2 MB (10.0%)\nDirty:     2.1 MB (17.3%)\nCached:    3.1 MB (25.3%)\nBuffers:   4.2 MB (35.0%)\n▕██\u{1b}[7mUsed\u{1b}[0m███\u{1b}[2m██\u{1b}[0m\u{1b}[2;7mCached 0.0GB\u{1b}[0m\u{1b}[2m██\u{1b}[0m   ▏\n"
+            "MemTotal: 12.1 MB\nMemFree:   1.2 MB (10.0%)\nDirty:     2.1 MB (17.3%)\nCached:    3.1 MB (25.3%)\nBuffers:   4.2 MB (35.0%)\n▕██\u{1b}[7mUsed\u{1b}[0m███\u{1b}[2m██\u{1b}[0m\u{1b}[2;7mCached 0.0GB\u{1b}[0m\u{1b}[2m██\u{1b}[0m\u{1b}[2m▋\u{1b}[0m  ▏\n"
         );
     }
 
@@ -758,7 +1002,10 @@ mod tests {
         vals.insert("SwapTotal".to_owned(), 12_345_678);
         vals.insert("SwapFree".to_owned(), 2_345_678);
         vals.insert("itsatrap".to_owned(), 1024);
-        let mem_info = MemInfo { vals };
+        let mem_info = MemInfo {
+            vals,
+            sparkline: None,
+        };
         let swap_info = SwapInfo::from(mem_info);
 
         TERM_COLUMNS.store(80, Ordering::SeqCst);
@@ -770,14 +1017,17 @@ mod tests {
         TERM_COLUMNS.store(30, Ordering::SeqCst);
         assert_eq!(
             format!("{}", &swap_info),
-            "SwapTotal: 11.8 GB\nSwapFree:   2.2 GB (19.0%)\n▕██\u{1b}[7mUsed 9.5GB (81.0%)\u{1b}[0m███     ▏\n"
+            "SwapTotal: 11.8 GB\nSwapFree:   2.2 GB (19.0%)\n▕██\u{1b}[7mUsed 9.5GB (81.0%)\u{1b}[0m██▋     ▏\n"
         );
 
         let mut vals = HashMap::new();
         vals.insert("SwapTotal".to_owned(), 0);
         vals.insert("SwapFree".to_owned(), 0);
         vals.insert("itsatrap".to_owned(), 1024);
-        let mem_info = MemInfo { vals };
+        let mem_info = MemInfo {
+            vals,
+            sparkline: None,
+        };
         let swap_info = SwapInfo::from(mem_info);
 
         assert!(format!("{}", &swap_info).is_empty());
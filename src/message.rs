@@ -0,0 +1,177 @@
+//! Admin messages from files or command output
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+
+use crate::{
+    config, i18n, icons,
+    module::{BuiltinModule, Module},
+    prometheus,
+    provider::Providers,
+    style::Severity,
+};
+
+/// Message text, with where it comes from
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct Message {
+    /// File path or command
+    source: String,
+    /// Message text
+    text: String,
+}
+
+/// Admin messages
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct Messages {
+    messages: Vec<Message>,
+}
+
+/// Read messages from configured files and command
+pub(crate) fn fetch(cfg: &config::MessageConfig) -> anyhow::Result<Messages> {
+    let max_age = cfg
+        .max_age_days
+        .map(|d| Duration::from_secs(u64::from(d) * 24 * 60 * 60));
+    let now = SystemTime::now();
+
+    let mut messages = Vec::new();
+    for path in &cfg.paths {
+        for filepath in message_files(path)? {
+            if let Some(max_age) = max_age {
+                let mtime = fs::metadata(&filepath)?.modified()?;
+                if now.duration_since(mtime).unwrap_or_default() > max_age {
+                    continue;
+                }
+            }
+            let text = fs::read_to_string(&filepath)
+                .with_context(|| format!("Failed to read {}", filepath.display()))?;
+            messages.push(Message {
+                source: filepath.to_string_lossy().into_owned(),
+                text,
+            });
+        }
+    }
+
+    if let Some(command) = cfg.command.as_ref() {
+        let output = Command::new("sh")
+            .args(["-c", command])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()?;
+        anyhow::ensure!(output.status.success(), "Message command failed");
+        messages.push(Message {
+            source: command.to_owned(),
+            text: String::from_utf8_lossy(&output.stdout).into_owned(),
+        });
+    }
+
+    messages.retain(|m| !m.text.trim().is_empty());
+    Ok(Messages { messages })
+}
+
+/// Get message files for a path: the file itself, or the files of a directory sorted by name
+fn message_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(if path.exists() {
+            vec![path.to_owned()]
+        } else {
+            Vec::new()
+        });
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            files.push(entry.path());
+        }
+    }
+    files.sort_unstable();
+    Ok(files)
+}
+
+impl BuiltinModule for Messages {
+    const LETTER: &'static str = "a";
+    const KEY: &'static str = "messages";
+    const TITLE: i18n::Label = i18n::Label::MessageTitle;
+    const ICON: icons::Icon = icons::Icon::Message;
+
+    fn max_lines(cfg: &config::MaxLinesConfig) -> Option<usize> {
+        cfg.message
+    }
+
+    fn timeout(cfg: &config::TimeoutsConfig) -> Option<u64> {
+        cfg.message
+    }
+
+    fn fetch(cfg: &config::Config, _providers: &Providers) -> anyhow::Result<Self> {
+        fetch(&cfg.message)
+    }
+}
+
+impl Module for Messages {
+    /// Messages are always worth showing
+    fn retain_alerts(&mut self) -> bool {
+        !self.messages.is_empty()
+    }
+
+    /// Messages are informational
+    fn severity(&self) -> Severity {
+        Severity::Ok
+    }
+
+    /// Single line summary, with the message count
+    fn compact(&self) -> String {
+        match self.messages.len() {
+            0 => String::new(),
+            1 => "1 message".to_owned(),
+            n => format!("{n} messages"),
+        }
+    }
+
+    /// Write message count as Prometheus metrics
+    fn write_prometheus(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        prometheus::write_gauge(f, "messages", "Count of admin messages")?;
+        prometheus::write_sample(f, "messages", &[], self.messages.len())
+    }
+}
+
+impl fmt::Display for Messages {
+    /// Output message texts
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for message in &self.messages {
+            writeln!(f, "{}", message.text.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn test_fetch() {
+        let dir = env::temp_dir().join(format!("motd-message-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("10-maintenance.txt"), "Maintenance on sunday\n\n").unwrap();
+        fs::write(dir.join("20-empty.txt"), "").unwrap();
+        let cfg = config::MessageConfig {
+            paths: vec![dir.clone(), dir.join("missing")],
+            command: Some("echo hello".to_owned()),
+            max_age_days: Some(1),
+        };
+
+        let messages = fetch(&cfg).unwrap();
+        assert_eq!(messages.to_string(), "Maintenance on sunday\nhello\n");
+        assert_eq!(messages.compact(), "2 messages");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,42 +1,268 @@
 //! Module common stuff
 
-use std::{fmt, sync::atomic::AtomicUsize};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
 
 use crate::{
-    fs::FsInfo,
-    load::LoadInfo,
-    mem::{MemInfo, SwapInfo},
-    net::NetworkStats,
-    systemd::FailedUnits,
-    temp::HardwareTemps,
+    config, fs, i18n, icons, load, mem, message, net, plugin,
+    provider::{Providers, Source},
+    style::Severity,
+    systemd, temp,
 };
 
-pub(crate) enum ModuleData {
-    Load(LoadInfo),
-    Memory(MemInfo),
-    Swap(SwapInfo),
-    Fs(FsInfo),
-    HardwareTemps(HardwareTemps),
-    Systemd(FailedUnits),
-    Network(NetworkStats),
-}
-
-// TODO use enum dispatch
-impl fmt::Display for ModuleData {
-    /// Output load information
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Self::Load(i) => i.fmt(f),
-            Self::Memory(i) => i.fmt(f),
-            Self::Swap(i) => i.fmt(f),
-            Self::Fs(i) => i.fmt(f),
-            Self::HardwareTemps(i) => i.fmt(f),
-            Self::Systemd(i) => i.fmt(f),
-            Self::Network(i) => i.fmt(f),
+/// Section data, fetched at each run, and rendered as text with [`fmt::Display`]
+pub(crate) trait Module: fmt::Display + erased_serde::Serialize + Send + Sync {
+    /// Store current values in metric history, and set sparklines
+    fn update_history(&mut self, _cfg: &config::HistoryConfig) {}
+
+    /// Only keep entries above warning thresholds, return false if nothing is left to show
+    fn retain_alerts(&mut self) -> bool;
+
+    /// Highest severity of section values
+    fn severity(&self) -> Severity;
+
+    /// Single line summary, empty if there is nothing to show
+    fn compact(&self) -> String;
+
+    /// State shown in the section header after its title, that may contain ANSI colors
+    fn title_suffix(&self) -> String {
+        String::new()
+    }
+
+    /// Write data as Prometheus metrics
+    fn write_prometheus(&self, f: &mut dyn fmt::Write) -> fmt::Result;
+}
+
+erased_serde::serialize_trait_object!(Module);
+
+/// Data of any section
+pub(crate) type ModuleData = Box<dyn Module>;
+
+/// Data of a built-in section, that defines how the section is selected, presented and fetched
+pub(crate) trait BuiltinModule: Module + serde::Serialize + Sized + 'static {
+    /// Letter selecting the section on the command line and in layout config
+    const LETTER: &'static str;
+    /// Key in structured output
+    const KEY: &'static str;
+    /// Title
+    const TITLE: i18n::Label;
+    /// Title icon
+    const ICON: icons::Icon;
+    /// Shared data sources used by the fetcher
+    const SOURCES: &'static [Source] = &[];
+
+    /// Get configured maximum number of lines
+    fn max_lines(cfg: &config::MaxLinesConfig) -> Option<usize>;
+
+    /// Get configured fetch timeout in milliseconds, if any
+    fn timeout(cfg: &config::TimeoutsConfig) -> Option<u64>;
+
+    /// Fetch section data
+    fn fetch(cfg: &config::Config, providers: &Providers) -> anyhow::Result<Self>;
+}
+
+/// Output section, with its data fetcher
+pub(crate) struct Section {
+    /// Letter selecting the section on the command line and in layout config
+    pub letter: &'static str,
+    /// Key in structured output
+    pub key: &'static str,
+    /// Title
+    pub title: Title,
+    /// Title icon
+    pub icon: icons::Icon,
+    /// Get configured maximum number of lines
+    pub max_lines: fn(&config::MaxLinesConfig) -> Option<usize>,
+    /// Get configured fetch timeout in milliseconds, if any
+    pub timeout: fn(&config::TimeoutsConfig) -> Option<u64>,
+    /// Shared data sources used by the fetcher
+    pub sources: &'static [Source],
+    /// Section data fetcher
+    pub fetcher: Fetcher,
+    /// Whether a fetch thread is still running, possibly after its timeout
+    pub fetching: AtomicBool,
+}
+
+/// Section title
+pub(crate) enum Title {
+    /// Translated label
+    Label(i18n::Label),
+    /// User defined text
+    Text(&'static str),
+}
+
+/// Section data fetcher
+pub(crate) enum Fetcher {
+    /// Built-in module
+    Builtin(fn(&config::Config, &Providers) -> anyhow::Result<ModuleData>),
+    /// User defined command
+    Plugin(&'static config::PluginConfig),
+}
+
+impl Section {
+    /// Get title, translated for built-in sections
+    pub(crate) fn title(&self) -> &'static str {
+        match self.title {
+            Title::Label(label) => i18n::tr(label),
+            Title::Text(text) => text,
+        }
+    }
+
+    /// Fetch section data
+    pub(crate) fn fetch(
+        &self,
+        cfg: &config::Config,
+        providers: &Providers,
+    ) -> anyhow::Result<ModuleData> {
+        match self.fetcher {
+            Fetcher::Builtin(fetch) => fetch(cfg, providers),
+            Fetcher::Plugin(plugin_cfg) => plugin::fetch(plugin_cfg),
+        }
+    }
+
+    /// Fetch section data in a separate thread, and give up after the configured timeout,
+    /// leaving the thread to finish in the background, in which case no other fetch is started
+    /// until it does
+    pub(crate) fn fetch_with_timeout(
+        &'static self,
+        cfg: &Arc<config::Config>,
+        providers: &'static Providers,
+    ) -> anyhow::Result<ModuleData> {
+        let Some(timeout_ms) = (self.timeout)(&cfg.timeouts).or(cfg.timeouts.default) else {
+            return self.fetch(cfg, providers);
+        };
+        anyhow::ensure!(
+            !self.fetching.swap(true, Ordering::SeqCst),
+            "Previous fetch still running"
+        );
+        let (tx, rx) = mpsc::channel();
+        // Thread keeps the config alive if it outlives a reload
+        let cfg = Arc::clone(cfg);
+        thread::spawn(move || {
+            /// Clear fetching flag when the thread ends, even if it panics
+            struct Fetching(&'static AtomicBool);
+            impl Drop for Fetching {
+                fn drop(&mut self) {
+                    self.0.store(false, Ordering::SeqCst);
+                }
+            }
+            let fetching = Fetching(&self.fetching);
+            let data = self.fetch(&cfg, providers);
+            drop(fetching);
+            // Receiver may be gone if timed out
+            let _ = tx.send(data);
+        });
+        match rx.recv_timeout(Duration::from_millis(timeout_ms)) {
+            Ok(data) => data,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                anyhow::bail!("Timed out after {timeout_ms}ms")
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => anyhow::bail!("Fetch thread panicked"),
         }
     }
+
+    /// Get built-in section from its letter
+    pub(crate) fn from_letter(letter: &str) -> Option<&'static Self> {
+        SECTIONS.iter().find(|s| s.letter == letter)
+    }
+}
+
+/// Built-in sections, in default order
+pub(crate) static SECTIONS: [Section; 8] = [
+    builtin::<load::LoadInfo>(),
+    builtin::<mem::MemInfo>(),
+    builtin::<mem::SwapInfo>(),
+    builtin::<fs::FsInfo>(),
+    builtin::<temp::HardwareTemps>(),
+    builtin::<net::NetworkStats>(),
+    builtin::<systemd::FailedUnits>(),
+    builtin::<message::Messages>(),
+];
+
+/// Build section of a built-in module
+const fn builtin<T: BuiltinModule>() -> Section {
+    Section {
+        letter: T::LETTER,
+        key: T::KEY,
+        title: Title::Label(T::TITLE),
+        icon: T::ICON,
+        max_lines: T::max_lines,
+        timeout: T::timeout,
+        sources: T::SOURCES,
+        fetcher: Fetcher::Builtin(fetch_builtin::<T>),
+        fetching: AtomicBool::new(false),
+    }
+}
+
+/// Fetch data of a built-in module
+fn fetch_builtin<T: BuiltinModule>(
+    cfg: &config::Config,
+    providers: &Providers,
+) -> anyhow::Result<ModuleData> {
+    Ok(Box::new(T::fetch(cfg, providers)?))
+}
+
+/// Data of several sections, serialized as a map in section order
+pub(crate) struct SectionsData(pub Vec<(&'static str, ModuleData)>);
+
+impl serde::Serialize for SectionsData {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(k, v)| (k, v)))
+    }
 }
 
 // Global stuff, intitialized by main function or unit tests
 pub(crate) static CPU_COUNT: AtomicUsize = AtomicUsize::new(0);
 pub(crate) static TERM_COLUMNS: AtomicUsize = AtomicUsize::new(0);
+pub(crate) static COLOR: AtomicBool = AtomicBool::new(true);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fetch that outlives its timeout
+    fn fetch_slow(_cfg: &config::Config, _providers: &Providers) -> anyhow::Result<ModuleData> {
+        thread::sleep(Duration::from_millis(200));
+        anyhow::bail!("Too late")
+    }
+
+    static SLOW: Section = Section {
+        letter: "z",
+        key: "slow",
+        title: Title::Text("Slow"),
+        icon: icons::Icon::Plugin,
+        max_lines: |_| None,
+        timeout: |_| Some(10),
+        sources: &[],
+        fetcher: Fetcher::Builtin(fetch_slow),
+        fetching: AtomicBool::new(false),
+    };
+
+    #[test]
+    fn test_fetch_with_timeout() {
+        let cfg = Arc::new(config::Config::default());
+        let providers: &'static Providers = Box::leak(Box::default());
+
+        let err = |res: anyhow::Result<ModuleData>| res.err().unwrap().to_string();
+        assert_eq!(
+            err(SLOW.fetch_with_timeout(&cfg, providers)),
+            "Timed out after 10ms"
+        );
+        assert_eq!(
+            err(SLOW.fetch_with_timeout(&cfg, providers)),
+            "Previous fetch still running"
+        );
+        thread::sleep(Duration::from_millis(400));
+        assert_eq!(
+            err(SLOW.fetch_with_timeout(&cfg, providers)),
+            "Timed out after 10ms"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Section data publishing to an MQTT broker
+
+use std::{
+    env,
+    fmt::Write as _,
+    fs::{self, DirBuilder, OpenOptions},
+    io::Write as _,
+    os::unix::fs::{DirBuilderExt as _, OpenOptionsExt as _},
+    path::PathBuf,
+    process::{self, Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::Context as _;
+
+use crate::{banner, config, module::ModuleData};
+
+/// Publishes section data as JSON, with `mosquitto_pub`
+pub(crate) struct Publisher {
+    /// Broker config
+    cfg: config::MqttConfig,
+    /// Host name, part of topics
+    hostname: String,
+}
+
+impl Publisher {
+    /// Create publisher, if a broker is configured
+    pub(crate) fn new(cfg: &config::MqttConfig) -> anyhow::Result<Self> {
+        anyhow::ensure!(cfg.host.is_some(), "No MQTT broker host in config");
+        Ok(Self {
+            cfg: cfg.clone(),
+            hostname: banner::hostname()?,
+        })
+    }
+
+    /// Publish data of a section, logging errors
+    pub(crate) fn publish(&self, section: &str, data: &ModuleData) {
+        if let Err(err) = self.try_publish(section, data) {
+            eprintln!("Failed to publish {section} data: {err:#}");
+        }
+    }
+
+    /// Publish data of a section to its topic
+    fn try_publish(&self, section: &str, data: &ModuleData) -> anyhow::Result<()> {
+        let payload = serde_json::to_string(data)?;
+        let mut command = Command::new("mosquitto_pub");
+        command.args(self.args(section));
+        // Kept until mosquitto_pub exits
+        let options_dir = if let Some(options) = self.options() {
+            let options_dir = OptionsDir::new(&options)?;
+            command.env("XDG_CONFIG_HOME", &options_dir.0);
+            Some(options_dir)
+        } else {
+            None
+        };
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run mosquitto_pub")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(payload.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        drop(options_dir);
+        anyhow::ensure!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        Ok(())
+    }
+
+    /// Build `mosquitto_pub` arguments, to publish message read from stdin
+    fn args(&self, section: &str) -> Vec<String> {
+        let cfg = &self.cfg;
+        let mut args = vec![
+            "-h".to_owned(),
+            cfg.host.clone().unwrap_or_default(),
+            "-p".to_owned(),
+            cfg.port
+                .unwrap_or(if cfg.tls { 8883 } else { 1883 })
+                .to_string(),
+            "-t".to_owned(),
+            format!(
+                "{}/{}/{section}",
+                cfg.topic_prefix.trim_end_matches('/'),
+                self.hostname
+            ),
+            "-s".to_owned(),
+        ];
+        if cfg.retain {
+            args.push("-r".to_owned());
+        }
+        if cfg.tls {
+            match cfg.ca_file.as_ref() {
+                Some(ca_file) => {
+                    args.extend([
+                        "--cafile".to_owned(),
+                        ca_file.to_string_lossy().into_owned(),
+                    ]);
+                }
+                None => args.extend(["--capath".to_owned(), "/etc/ssl/certs".to_owned()]),
+            }
+        }
+        args
+    }
+
+    /// Build `mosquitto_pub` options file content with credentials, which must not be passed as
+    /// arguments that any local user can read
+    fn options(&self) -> Option<String> {
+        let cfg = &self.cfg;
+        if cfg.username.is_none() && cfg.password.is_none() {
+            return None;
+        }
+        let mut options = String::new();
+        if let Some(username) = cfg.username.as_ref() {
+            let _ = writeln!(options, "-u {username}");
+        }
+        if let Some(password) = cfg.password.as_ref() {
+            let _ = writeln!(options, "-P {password}");
+        }
+        Some(options)
+    }
+}
+
+/// Private directory with a `mosquitto_pub` options file, used as the config directory of
+/// `mosquitto_pub`, and removed when dropped
+struct OptionsDir(PathBuf);
+
+impl OptionsDir {
+    /// Create directory and options file, only readable by the current user
+    fn new(options: &str) -> anyhow::Result<Self> {
+        /// Count of created directories, to get unique names
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let dir = env::temp_dir().join(format!(
+            "motd-mqtt-{}-{}",
+            process::id(),
+            COUNT.fetch_add(1, Ordering::SeqCst)
+        ));
+        // Fails if it already exists, so that it can not be prepared by another user
+        DirBuilder::new().mode(0o700).create(&dir)?;
+        let options_dir = Self(dir);
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(options_dir.0.join("mosquitto_pub"))?
+            .write_all(options.as_bytes())?;
+        Ok(options_dir)
+    }
+}
+
+impl Drop for OptionsDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::MetadataExt as _;
+
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let mut cfg = config::MqttConfig {
+            host: Some("broker.lan".to_owned()),
+            ..config::MqttConfig::default()
+        };
+        let publisher = Publisher {
+            cfg: cfg.clone(),
+            hostname: "server".to_owned(),
+        };
+        assert_eq!(
+            publisher.args("load"),
+            [
+                "-h",
+                "broker.lan",
+                "-p",
+                "1883",
+                "-t",
+                "motd/server/load",
+                "-s",
+                "-r"
+            ]
+        );
+        assert_eq!(publisher.options(), None);
+
+        cfg.tls = true;
+        cfg.retain = false;
+        cfg.username = Some("user".to_owned());
+        cfg.password = Some("pass".to_owned());
+        cfg.ca_file = Some(PathBuf::from("/etc/ca.pem"));
+        let tls_publisher = Publisher {
+            cfg: cfg.clone(),
+            hostname: "server".to_owned(),
+        };
+        assert_eq!(
+            tls_publisher.args("load"),
+            [
+                "-h",
+                "broker.lan",
+                "-p",
+                "8883",
+                "-t",
+                "motd/server/load",
+                "-s",
+                "--cafile",
+                "/etc/ca.pem"
+            ]
+        );
+        assert!(tls_publisher
+            .args("load")
+            .iter()
+            .all(|a| !a.contains("pass")));
+        assert_eq!(
+            tls_publisher.options(),
+            Some("-u user\n-P pass\n".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_options_dir() {
+        let options_dir = OptionsDir::new("-P pass\n").unwrap();
+        let filepath = options_dir.0.join("mosquitto_pub");
+        assert_eq!(fs::read_to_string(&filepath).unwrap(), "-P pass\n");
+        assert_eq!(fs::metadata(&filepath).unwrap().mode() & 0o777, 0o600);
+        let dir = options_dir.0.clone();
+        drop(options_dir);
+        assert!(!dir.exists());
+    }
+}
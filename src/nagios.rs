@@ -0,0 +1,128 @@
+//! Nagios plugin output
+
+use std::{fmt::Write as _, io};
+
+use crate::{fmt, module::ModuleData, prometheus, style::Severity};
+
+/// Plugin status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warning,
+    Critical,
+    Unknown,
+}
+
+impl Status {
+    /// Status name, as displayed in the status line
+    fn name(self) -> &'static str {
+        match self {
+            Self::Ok => "OK",
+            Self::Warning => "WARNING",
+            Self::Critical => "CRITICAL",
+            Self::Unknown => "UNKNOWN",
+        }
+    }
+
+    /// Plugin exit code
+    fn exit_code(self) -> i32 {
+        match self {
+            Self::Ok => 0,
+            Self::Warning => 1,
+            Self::Critical => 2,
+            Self::Unknown => 3,
+        }
+    }
+}
+
+/// Get plugin status from section results, failed sections being unknown unless others are worse
+fn status(results: &[(&str, Result<ModuleData, String>)]) -> Status {
+    let severity = results
+        .iter()
+        .filter_map(|(_, r)| r.as_ref().ok())
+        .map(|d| d.severity())
+        .max()
+        .unwrap_or(Severity::Ok);
+    match severity {
+        Severity::Critical => Status::Critical,
+        Severity::Warning => Status::Warning,
+        Severity::Ok if results.iter().any(|(_, r)| r.is_err()) => Status::Unknown,
+        Severity::Ok => Status::Ok,
+    }
+}
+
+/// Format sample as perfdata, with byte unit if applicable
+fn perfdata(key: &str, value: f64) -> String {
+    let label = key.strip_prefix("motd_").unwrap_or(key).replace('\'', "''");
+    let unit = if key
+        .split('{')
+        .next()
+        .unwrap_or_default()
+        .ends_with("_bytes")
+    {
+        "B"
+    } else {
+        ""
+    };
+    format!("'{label}'={value}{unit}")
+}
+
+/// Write status line with section summaries and perfdata, and get plugin exit code
+pub(crate) fn write_status(
+    out: &mut dyn io::Write,
+    results: &[(&str, Result<ModuleData, String>)],
+) -> anyhow::Result<i32> {
+    let status = status(results);
+    let mut summaries = Vec::new();
+    let mut perfdatas = Vec::new();
+    for (title, result) in results {
+        match result {
+            Ok(data) => {
+                let summary = fmt::strip_ansi(&data.compact());
+                if !summary.is_empty() {
+                    summaries.push(format!("{title}: {summary}"));
+                }
+                let mut metrics = String::new();
+                data.write_prometheus(&mut metrics)?;
+                perfdatas.extend(
+                    prometheus::parse_samples(&metrics)
+                        .into_iter()
+                        .map(|(k, v)| perfdata(k, v)),
+                );
+            }
+            Err(err) => summaries.push(format!("{title}: {err}")),
+        }
+    }
+    let mut line = format!("MOTD {} - {}", status.name(), summaries.join("; "));
+    if !perfdatas.is_empty() {
+        write!(line, " | {}", perfdatas.join(" "))?;
+    }
+    writeln!(out, "{line}")?;
+    Ok(status.exit_code())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perfdata() {
+        assert_eq!(perfdata("motd_tasks", 12.0), "'tasks'=12");
+        assert_eq!(
+            perfdata(
+                "motd_filesystem_used_bytes{mountpoint=\"/mnt/it's\"}",
+                1000.0
+            ),
+            "'filesystem_used_bytes{mountpoint=\"/mnt/it''s\"}'=1000B"
+        );
+    }
+
+    #[test]
+    fn test_status() {
+        assert_eq!(status(&[]), Status::Ok);
+        assert_eq!(
+            status(&[("Load", Err("failed".to_owned()))]),
+            Status::Unknown
+        );
+    }
+}
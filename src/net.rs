@@ -3,13 +3,23 @@ use std::{
     fmt,
     fs::{self, DirEntry, File},
     io::{Read, Seek},
-    thread::sleep,
+    net::{SocketAddr, TcpStream},
+    path::Path,
+    thread::{self, sleep},
     time::{Duration, Instant},
 };
 
-use ansi_term::Colour::{Red, Yellow};
-
-use crate::{fmt::format_kmgt_si, module::ModuleData};
+use crate::{
+    config::{self, RateUnit},
+    dbus,
+    fmt::{format_rate, Align, Table},
+    history, i18n, icons,
+    module::{BuiltinModule, Module},
+    netlink, prometheus,
+    provider::{Providers, Source},
+    style::{self, Severity},
+    sysroot,
+};
 
 /// Network interface pending stats
 struct PendingInterfaceStats {
@@ -17,20 +27,37 @@ struct PendingInterfaceStats {
     rx_bytes: u64,
     /// Tx byte count
     tx_bytes: u64,
-    /// Rx bytes count sysfs file
-    rx_bytes_file: File,
-    /// Tx bytes count sysfs file
-    tx_bytes_file: File,
+    /// Where to read the second sample from
+    source: CounterSource,
     /// Timestamp
     ts: Instant,
     /// Interface speed
     line_bps: Option<u64>,
+    /// Bond or bridge this interface is a member of
+    master: Option<String>,
+    /// Operational state (up, down...)
+    operstate: Option<String>,
+    /// Verbose details
+    details: Option<InterfaceDetails>,
+}
+
+/// Source of interface byte counters
+enum CounterSource {
+    /// Sysfs statistics files, kept open to read them again
+    Sysfs {
+        /// Rx bytes count sysfs file
+        rx_bytes_file: File,
+        /// Tx bytes count sysfs file
+        tx_bytes_file: File,
+    },
+    /// Netlink link dump, with interface index
+    Netlink(u32),
 }
 
 type NetworkPendingStats = BTreeMap<String, PendingInterfaceStats>;
 
 /// Network interface stats
-#[expect(clippy::struct_field_names)]
+#[derive(serde::Serialize)]
 pub(crate) struct InterfaceStats {
     /// Rx bits/s
     rx_bps: u64,
@@ -38,18 +65,209 @@ pub(crate) struct InterfaceStats {
     tx_bps: u64,
     /// Interface speed
     line_bps: Option<u64>,
+    /// Bond or bridge this interface is a member of
+    master: Option<String>,
+    /// Operational state (up, down...)
+    operstate: Option<String>,
+    /// Verbose details
+    details: Option<InterfaceDetails>,
+}
+
+/// Network interface details, only fetched in verbose mode
+#[derive(Clone, serde::Serialize)]
+struct InterfaceDetails {
+    /// Kernel driver name
+    driver: Option<String>,
+    /// MAC address
+    mac: Option<String>,
+    /// IP addresses with prefix length, except link local ones, only available from netlink
+    addresses: Vec<String>,
+    /// Negotiated duplex (full, half...)
+    duplex: Option<String>,
+}
+
+/// Overall network connectivity, as reported by the network manager
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Connectivity {
+    /// Internet is reachable
+    Full,
+    /// Behind a captive portal
+    Portal,
+    /// Connected to a network, but no Internet access
+    Limited,
+    /// No network connection
+    Disconnected,
 }
 
+impl fmt::Display for Connectivity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Full => "full",
+            Self::Portal => "portal",
+            Self::Limited => "limited",
+            Self::Disconnected => "none",
+        })
+    }
+}
+
+/// IPv6 reachability
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Ipv6Status {
+    /// Global address, and target is reachable
+    Ok,
+    /// No global address
+    Absent,
+    /// Global address, but target is unreachable
+    Broken,
+}
+
+impl fmt::Display for Ipv6Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Ok => write!(f, "{}", style::theme().paint("OK", Severity::Ok)),
+            Self::Absent => write!(f, "{}", style::theme().paint("absent", Severity::Warning)),
+            Self::Broken => write!(f, "{}", style::theme().paint("broken", Severity::Critical)),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
 pub(crate) struct NetworkStats {
     interfaces: BTreeMap<String, InterfaceStats>,
+    connectivity: Option<Connectivity>,
+    ipv6: Option<Ipv6Status>,
+    /// Recent throughput sparkline of each interface
+    #[serde(skip)]
+    sparklines: BTreeMap<String, String>,
+    /// Display unit of rates
+    #[serde(skip)]
+    unit: RateUnit,
 }
 
 const MIN_DELAY_BETWEEN_NET_SAMPLES_MS: u64 = 30;
 
-pub(crate) fn fetch() -> anyhow::Result<ModuleData> {
-    let mut sample = get_network_stats()?;
-    let stats = update_network_stats(&mut sample)?;
-    Ok(ModuleData::Network(stats))
+/// Network stats sampler, keeping previous sample to compute rates on repeated fetches
+#[derive(Default)]
+pub(crate) struct Sampler {
+    /// Previous sample, with counter sources still open
+    pending: Option<NetworkPendingStats>,
+}
+
+impl Sampler {
+    /// Take a first sample if there is none, so that the wait before the next one starts now
+    pub(crate) fn prime(&mut self, cfg: &config::NetConfig) -> anyhow::Result<()> {
+        if self.pending.is_none() {
+            self.pending = Some(get_network_stats(cfg)?);
+        }
+        Ok(())
+    }
+
+    /// Fetch network stats, with rates since previous fetch if any
+    pub(crate) fn fetch(&mut self, cfg: &config::NetConfig) -> anyhow::Result<NetworkStats> {
+        let connectivity_fut = cfg.connectivity.then(|| thread::spawn(fetch_connectivity));
+        let ipv6_fut = cfg.ipv6_check.then(|| {
+            let target = cfg.ipv6_target;
+            let timeout = Duration::from_millis(cfg.ipv6_timeout_ms);
+            thread::spawn(move || fetch_ipv6_status(&target, timeout))
+        });
+        let mut sample = match self.pending.take() {
+            Some(sample) => sample,
+            None => get_network_stats(cfg)?,
+        };
+        // If this fails, sample is dropped and the next fetch starts from scratch
+        let mut stats = update_network_stats(&mut sample)?;
+        self.pending = Some(sample);
+        stats.unit = cfg.unit;
+        if let Some(connectivity_fut) = connectivity_fut {
+            stats.connectivity = connectivity_fut
+                .join()
+                .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))?;
+        }
+        if let Some(ipv6_fut) = ipv6_fut {
+            stats.ipv6 = Some(
+                ipv6_fut
+                    .join()
+                    .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))?,
+            );
+        }
+        Ok(stats)
+    }
+}
+
+/// Check if there is a global IPv6 address, and if target can be reached
+fn fetch_ipv6_status(target: &SocketAddr, timeout: Duration) -> Ipv6Status {
+    let has_global_addr = fs::read_to_string(sysroot::path("/proc/net/if_inet6"))
+        .is_ok_and(|if_inet6| if_inet6.lines().any(is_global_inet6_line));
+    if !has_global_addr {
+        Ipv6Status::Absent
+    } else if TcpStream::connect_timeout(target, timeout).is_ok() {
+        Ipv6Status::Ok
+    } else {
+        Ipv6Status::Broken
+    }
+}
+
+/// Check if a `/proc/net/if_inet6` line is a global unicast address, excluding unique local addresses
+fn is_global_inet6_line(line: &str) -> bool {
+    let mut tokens = line.split_whitespace();
+    let (Some(addr), Some(scope)) = (tokens.next(), tokens.nth(2)) else {
+        return false;
+    };
+    scope == "00" && !addr.starts_with("fc") && !addr.starts_with("fd")
+}
+
+/// Get overall connectivity from NetworkManager, or systemd-networkd if not available
+fn fetch_connectivity() -> Option<Connectivity> {
+    let mut conn = dbus::Connection::open(dbus::Bus::System).ok()?;
+    if let Ok(reply) = conn.get_property(
+        "org.freedesktop.NetworkManager",
+        "/org/freedesktop/NetworkManager",
+        "org.freedesktop.NetworkManager",
+        "Connectivity",
+    ) {
+        let mut reader = reply.reader();
+        if reader.signature().ok()? != "u" {
+            return None;
+        }
+        return parse_nm_connectivity(reader.u32().ok()?);
+    }
+    let reply = conn
+        .get_property(
+            "org.freedesktop.network1",
+            "/org/freedesktop/network1",
+            "org.freedesktop.network1.Manager",
+            "OnlineState",
+        )
+        .ok()?;
+    let mut reader = reply.reader();
+    if reader.signature().ok()? != "s" {
+        return None;
+    }
+    parse_networkd_online_state(reader.string().ok()?)
+}
+
+/// Parse NetworkManager `NMConnectivityState` value
+fn parse_nm_connectivity(state: u32) -> Option<Connectivity> {
+    match state {
+        1 => Some(Connectivity::Disconnected),
+        2 => Some(Connectivity::Portal),
+        3 => Some(Connectivity::Limited),
+        4 => Some(Connectivity::Full),
+        // Unknown, typically if connectivity checking is disabled
+        _ => None,
+    }
+}
+
+/// Parse systemd-networkd online state value
+fn parse_networkd_online_state(state: &str) -> Option<Connectivity> {
+    match state {
+        "online" => Some(Connectivity::Full),
+        "partial" => Some(Connectivity::Limited),
+        "offline" => Some(Connectivity::Disconnected),
+        _ => None,
+    }
 }
 
 #[expect(clippy::verbose_file_reads)]
@@ -68,17 +286,133 @@ fn read_interface_stats(
     Ok((rx_bytes, tx_bytes, Instant::now()))
 }
 
-/// Get network stats first sample
-fn get_network_stats() -> anyhow::Result<NetworkPendingStats> {
+/// Read interface details from sysfs
+fn read_interface_details(itf_dir: &Path) -> InterfaceDetails {
+    let read_value = |name| {
+        fs::read_to_string(itf_dir.join(name))
+            .ok()
+            .map(|s| s.trim_end().to_owned())
+            .filter(|s| !s.is_empty() && s != "unknown")
+    };
+    let driver = fs::read_link(itf_dir.join("device/driver"))
+        .ok()
+        .and_then(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(ToOwned::to_owned)
+        });
+    InterfaceDetails {
+        driver,
+        mac: read_value("address"),
+        addresses: Vec::new(),
+        duplex: read_value("duplex"),
+    }
+}
+
+/// Check if an interface should be excluded from stats
+fn is_excluded(cfg: &config::NetConfig, itf_name: &str) -> bool {
+    itf_name == "lo"
+        || cfg.interface_blacklist.iter().any(|r| r.is_match(itf_name))
+        || (!cfg.interface_whitelist.is_empty()
+            && !cfg.interface_whitelist.iter().any(|r| r.is_match(itf_name)))
+}
+
+/// Read interface line speed from sysfs
+fn read_line_bps(itf_dir: &Path) -> Option<u64> {
+    if itf_dir.join("tun_flags").exists() {
+        /* tun always report 10 Mbps even if we can exceed that limit */
+        None
+    } else {
+        fs::read_to_string(itf_dir.join("speed"))
+            .ok()
+            .and_then(|speed_str| {
+                speed_str
+                    .trim_end()
+                    // Some interfaces (bridges) report -1
+                    .parse::<u64>()
+                    .map(|speed| speed * 1_000_000)
+                    .ok()
+            })
+    }
+}
+
+/// Get network stats first sample, from a netlink dump if possible, or from sysfs otherwise
+fn get_network_stats(cfg: &config::NetConfig) -> anyhow::Result<NetworkPendingStats> {
+    // Netlink dumps links of the running system
+    if sysroot::is_set() {
+        return get_network_stats_sysfs(cfg);
+    }
+    match netlink::dump_links() {
+        Ok(dump) => Ok(get_network_stats_netlink(cfg, dump)),
+        Err(_) => get_network_stats_sysfs(cfg),
+    }
+}
+
+/// Get network stats first sample from a netlink link dump
+fn get_network_stats_netlink(
+    cfg: &config::NetConfig,
+    (links, ts): netlink::LinkDump,
+) -> NetworkPendingStats {
     let mut stats: NetworkPendingStats = NetworkPendingStats::new();
+    // Addresses are only shown in verbose mode, and are not worth failing for
+    let mut addresses = if cfg.verbose {
+        netlink::dump_addresses().unwrap_or_default()
+    } else {
+        BTreeMap::new()
+    };
+
+    for (&index, link) in &links {
+        if is_excluded(cfg, &link.name) {
+            continue;
+        }
+        // Line speed is only reported by ethtool, not rtnetlink, and driver details are not part of the link dump
+        let itf_dir = sysroot::path("/sys/class/net").join(&link.name);
+
+        let master = link
+            .master_index
+            .and_then(|i| links.get(&i))
+            .map(|m| m.name.clone());
 
-    let mut dir_entries: Vec<DirEntry> = fs::read_dir("/sys/class/net")?
+        let details = cfg.verbose.then(|| InterfaceDetails {
+            mac: link.mac.clone(),
+            addresses: addresses
+                .remove(&index)
+                .unwrap_or_default()
+                .iter()
+                .map(|a| format!("{}/{}", a.ip, a.prefix_len))
+                .collect(),
+            ..read_interface_details(&itf_dir)
+        });
+
+        stats.insert(
+            link.name.clone(),
+            PendingInterfaceStats {
+                rx_bytes: link.rx_bytes,
+                tx_bytes: link.tx_bytes,
+                source: CounterSource::Netlink(index),
+                ts,
+                line_bps: read_line_bps(&itf_dir),
+                master,
+                operstate: link.operstate.clone(),
+                details,
+            },
+        );
+    }
+
+    stats
+}
+
+/// Get network stats first sample from sysfs files
+fn get_network_stats_sysfs(cfg: &config::NetConfig) -> anyhow::Result<NetworkPendingStats> {
+    let mut stats: NetworkPendingStats = NetworkPendingStats::new();
+
+    let mut dir_entries: Vec<DirEntry> = fs::read_dir(sysroot::path("/sys/class/net"))?
         .filter_map(Result::ok)
         .collect();
     dir_entries.sort_by_key(DirEntry::file_name);
     for dir_entry in dir_entries {
         let itf_name = dir_entry.file_name().clone().into_string().unwrap();
-        if itf_name == "lo" {
+        if is_excluded(cfg, &itf_name) {
             continue;
         }
         let itf_dir = dir_entry.path();
@@ -91,31 +425,33 @@ fn get_network_stats() -> anyhow::Result<NetworkPendingStats> {
         rx_bytes_file.rewind()?;
         tx_bytes_file.rewind()?;
 
-        let line_bps = if itf_dir.join("tun_flags").exists() {
-            /* tun always report 10 Mbps even if we can exceed that limit */
-            None
-        } else {
-            fs::read_to_string(itf_dir.join("speed"))
-                .ok()
-                .and_then(|speed_str| {
-                    speed_str
-                        .trim_end()
-                        // Some interfaces (bridges) report -1
-                        .parse::<u64>()
-                        .map(|speed| speed * 1_000_000)
-                        .ok()
-                })
-        };
+        // Bond & bridge members have a master symlink to their aggregate interface
+        let master = fs::read_link(itf_dir.join("master")).ok().and_then(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(ToOwned::to_owned)
+        });
+
+        let operstate = fs::read_to_string(itf_dir.join("operstate"))
+            .ok()
+            .map(|s| s.trim_end().to_owned());
+
+        let details = cfg.verbose.then(|| read_interface_details(&itf_dir));
 
         stats.insert(
             itf_name,
             PendingInterfaceStats {
                 rx_bytes,
                 tx_bytes,
-                rx_bytes_file,
-                tx_bytes_file,
+                source: CounterSource::Sysfs {
+                    rx_bytes_file,
+                    tx_bytes_file,
+                },
                 ts,
-                line_bps,
+                line_bps: read_line_bps(&itf_dir),
+                master,
+                operstate,
+                details,
             },
         );
     }
@@ -123,101 +459,398 @@ fn get_network_stats() -> anyhow::Result<NetworkPendingStats> {
     Ok(stats)
 }
 
-/// Get network stats second sample and build interface stats
+/// Get network stats next sample and build interface stats, pending stats are updated to the new sample
 fn update_network_stats(pending_stats: &mut NetworkPendingStats) -> anyhow::Result<NetworkStats> {
     let mut stats = BTreeMap::new();
 
-    for (itf_name, pending_itf_stats) in pending_stats.iter_mut() {
-        // Ensure there is sufficient time between samples
-        let now = Instant::now();
-        let ms_since_first_sample = now.duration_since(pending_itf_stats.ts).as_millis() as u64;
+    // Ensure there is sufficient time between samples
+    if let Some(first_ts) = pending_stats.values().map(|s| s.ts).min() {
+        let ms_since_first_sample = first_ts.elapsed().as_millis() as u64;
         if ms_since_first_sample < MIN_DELAY_BETWEEN_NET_SAMPLES_MS {
             let sleep_delay_ms = MIN_DELAY_BETWEEN_NET_SAMPLES_MS - ms_since_first_sample;
             sleep(Duration::from_millis(sleep_delay_ms));
         }
+    }
 
+    // All netlink counters are refreshed with a single dump
+    let dump = if pending_stats
+        .values()
+        .any(|s| matches!(s.source, CounterSource::Netlink(_)))
+    {
+        Some(netlink::dump_links()?)
+    } else {
+        None
+    };
+
+    for (itf_name, pending_itf_stats) in pending_stats.iter_mut() {
         // Read sample
-        let (rx_bytes2, tx_bytes2, ts2) = read_interface_stats(
-            &mut pending_itf_stats.rx_bytes_file,
-            &mut pending_itf_stats.tx_bytes_file,
-        )?;
-
-        // Convert to speed
-        let ts_delta_ms = ts2.duration_since(pending_itf_stats.ts).as_millis();
-        let rx_bps = 1000 * (rx_bytes2 - pending_itf_stats.rx_bytes) * 8 / ts_delta_ms as u64;
-        let tx_bps = 1000 * (tx_bytes2 - pending_itf_stats.tx_bytes) * 8 / ts_delta_ms as u64;
+        let (rx_bytes2, tx_bytes2, ts2) = match &mut pending_itf_stats.source {
+            CounterSource::Sysfs {
+                rx_bytes_file,
+                tx_bytes_file,
+            } => {
+                let sample = read_interface_stats(rx_bytes_file, tx_bytes_file)?;
+                rx_bytes_file.rewind()?;
+                tx_bytes_file.rewind()?;
+                sample
+            }
+            CounterSource::Netlink(index) => {
+                let (links, ts) = dump
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("No netlink dump for {itf_name}"))?;
+                // Interface may have been removed in between
+                let Some(link) = links.get(index) else {
+                    continue;
+                };
+                pending_itf_stats.operstate.clone_from(&link.operstate);
+                (link.rx_bytes, link.tx_bytes, *ts)
+            }
+        };
+
+        // Convert to speed, counters can be reset if interface was recreated
+        let ts_delta_ms = ts2.duration_since(pending_itf_stats.ts).as_millis().max(1);
+        let rx_bps =
+            1000 * rx_bytes2.saturating_sub(pending_itf_stats.rx_bytes) * 8 / ts_delta_ms as u64;
+        let tx_bps =
+            1000 * tx_bytes2.saturating_sub(pending_itf_stats.tx_bytes) * 8 / ts_delta_ms as u64;
+        pending_itf_stats.rx_bytes = rx_bytes2;
+        pending_itf_stats.tx_bytes = tx_bytes2;
+        pending_itf_stats.ts = ts2;
         stats.insert(
-            itf_name.to_string(),
+            itf_name.clone(),
             InterfaceStats {
                 rx_bps,
                 tx_bps,
                 line_bps: pending_itf_stats.line_bps,
+                master: pending_itf_stats.master.clone(),
+                operstate: pending_itf_stats.operstate.clone(),
+                details: pending_itf_stats.details.clone(),
             },
         );
     }
 
-    Ok(NetworkStats { interfaces: stats })
+    Ok(NetworkStats {
+        interfaces: stats,
+        connectivity: None,
+        ipv6: None,
+        sparklines: BTreeMap::new(),
+        unit: RateUnit::default(),
+    })
 }
 
 /// Colorize network speed string
-fn colorize_speed(val: u64, line_rate: Option<u64>, s: String) -> String {
-    if let Some(line_rate) = line_rate {
-        if val >= line_rate * 90 / 100 {
-            Red.paint(s).to_string()
-        } else if val >= line_rate * 80 / 100 {
-            Yellow.paint(s).to_string()
-        } else {
-            s
-        }
+fn colorize_speed(val: u64, line_rate: Option<u64>, metric_key: &str, s: String) -> String {
+    let severity = speed_severity(val, line_rate);
+    let severity = history::baseline_severity(severity, metric_key, val as f64);
+    if line_rate.is_some() || (severity != Severity::Ok) {
+        style::theme().paint(&s, severity)
     } else {
         s
     }
 }
 
-impl fmt::Display for NetworkStats {
-    /// Output network stats
-    #[expect(clippy::similar_names)]
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let unit = "b/s";
-        let Some(max_itf_len) = self.interfaces.keys().map(String::len).max() else {
-            return Ok(());
-        };
-        let mac_rx_str_len = self
-            .interfaces
-            .values()
-            .map(|v| format_kmgt_si(v.rx_bps, unit).len())
-            .max()
-            .unwrap();
-        let mac_tx_str_len = self
+/// Get severity of a rate from interface line rate
+fn speed_severity(val: u64, line_rate: Option<u64>) -> Severity {
+    match line_rate {
+        Some(line_rate) if val >= line_rate * 90 / 100 => Severity::Critical,
+        Some(line_rate) if val >= line_rate * 80 / 100 => Severity::Warning,
+        _ => Severity::Ok,
+    }
+}
+
+/// Indentation of bond/bridge member interfaces
+const MEMBER_INDENT: &str = "  ";
+
+impl BuiltinModule for NetworkStats {
+    const LETTER: &'static str = "n";
+    const KEY: &'static str = "network";
+    const TITLE: i18n::Label = i18n::Label::NetTitle;
+    const ICON: icons::Icon = icons::Icon::Network;
+    const SOURCES: &'static [Source] = &[Source::NetSample];
+
+    fn max_lines(cfg: &config::MaxLinesConfig) -> Option<usize> {
+        cfg.net
+    }
+
+    fn timeout(cfg: &config::TimeoutsConfig) -> Option<u64> {
+        cfg.net
+    }
+
+    fn fetch(cfg: &config::Config, providers: &Providers) -> anyhow::Result<Self> {
+        providers.net.lock().unwrap().fetch(&cfg.net)
+    }
+}
+
+impl Module for NetworkStats {
+    /// Store throughput of each interface in history, and set their sparklines
+    fn update_history(&mut self, cfg: &config::HistoryConfig) {
+        let samples: Vec<(&str, f32)> = self
             .interfaces
-            .values()
-            .map(|v| format_kmgt_si(v.tx_bps, unit).len())
+            .iter()
+            .map(|(name, s)| (name.as_str(), (s.rx_bps + s.tx_bps) as f32))
+            .collect();
+        self.sparklines = history::update("net", &samples, cfg)
+            .into_iter()
+            .map(|(name, values)| (name, history::sparkline(&values)))
+            .collect();
+    }
+
+    /// Only keep saturated or down interfaces, and degraded connectivity
+    fn retain_alerts(&mut self) -> bool {
+        self.interfaces.retain(|_, s| {
+            let saturated = s
+                .line_bps
+                .is_some_and(|l| s.rx_bps.max(s.tx_bps) >= l * 80 / 100);
+            let member_down = s.master.is_some() && s.operstate.as_deref() != Some("up");
+            saturated || member_down
+        });
+        self.connectivity = self.connectivity.filter(|c| *c != Connectivity::Full);
+        self.ipv6 = self.ipv6.filter(|i| *i != Ipv6Status::Ok);
+        !self.interfaces.is_empty() || self.connectivity.is_some() || self.ipv6.is_some()
+    }
+
+    /// Highest severity of interface rates and states, connectivity and IPv6 reachability
+    fn severity(&self) -> Severity {
+        let itf_severity = self.interfaces.values().map(|s| {
+            let member_down = s.master.is_some() && s.operstate.as_deref() != Some("up");
+            let rate_severity = speed_severity(s.rx_bps.max(s.tx_bps), s.line_bps);
+            if member_down {
+                rate_severity.max(Severity::Warning)
+            } else {
+                rate_severity
+            }
+        });
+        let connectivity_severity = match self.connectivity {
+            Some(Connectivity::Full) | None => Severity::Ok,
+            Some(_) => Severity::Critical,
+        };
+        let ipv6_severity = match self.ipv6 {
+            Some(Ipv6Status::Ok) | None => Severity::Ok,
+            Some(Ipv6Status::Absent) => Severity::Warning,
+            Some(Ipv6Status::Broken) => Severity::Critical,
+        };
+        itf_severity
+            .chain([connectivity_severity, ipv6_severity])
             .max()
-            .unwrap();
-
-        for (itf_name, itf_stats) in &self.interfaces {
-            let name_pad = " ".repeat(max_itf_len - itf_name.len());
-            let rx_str = format_kmgt_si(itf_stats.rx_bps, unit);
-            let rx_pad = " ".repeat(mac_rx_str_len - rx_str.len());
-            let tx_str = format_kmgt_si(itf_stats.tx_bps, unit);
-            let tx_pad = " ".repeat(mac_tx_str_len - tx_str.len());
-            writeln!(
+            .unwrap_or(Severity::Ok)
+    }
+
+    /// Single line summary, with total rate of interfaces not member of a bond or bridge
+    fn compact(&self) -> String {
+        let mut summary = String::new();
+        if !self.interfaces.is_empty() {
+            let (rx_bps, tx_bps) = self
+                .interfaces
+                .values()
+                .filter(|s| s.master.is_none())
+                .fold((0, 0), |(rx, tx), s| (rx + s.rx_bps, tx + s.tx_bps));
+            summary = format!(
+                "net ↓ {} ↑ {}",
+                format_rate(rx_bps, self.unit, 0),
+                format_rate(tx_bps, self.unit, 0)
+            );
+        }
+        if let Some(connectivity) = self.connectivity.filter(|c| *c != Connectivity::Full) {
+            if !summary.is_empty() {
+                summary.push(' ');
+            }
+            summary.push_str(&style::theme().paint(&connectivity.to_string(), Severity::Critical));
+        }
+        summary
+    }
+
+    /// Connectivity state, red if not full
+    fn title_suffix(&self) -> String {
+        self.connectivity
+            .map(|c| {
+                let severity = if c == Connectivity::Full {
+                    Severity::Ok
+                } else {
+                    Severity::Critical
+                };
+                format!(
+                    "{}: {}",
+                    i18n::tr(i18n::Label::Connectivity),
+                    style::theme().paint(&c.to_string(), severity)
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// Write network stats as Prometheus metrics
+    fn write_prometheus(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        let metrics: [prometheus::GaugeDef<InterfaceStats, Option<u64>>; 3] = [
+            (
+                "network_receive_bits_per_second",
+                "Interface receive rate",
+                |s| Some(s.rx_bps),
+            ),
+            (
+                "network_transmit_bits_per_second",
+                "Interface transmit rate",
+                |s| Some(s.tx_bps),
+            ),
+            (
+                "network_speed_bits_per_second",
+                "Interface line speed",
+                |s| s.line_bps,
+            ),
+        ];
+        for (name, help, value) in metrics {
+            prometheus::write_gauge(f, name, help)?;
+            for (itf_name, itf_stats) in &self.interfaces {
+                if let Some(value) = value(itf_stats) {
+                    prometheus::write_sample(f, name, &[("interface", itf_name)], value)?;
+                }
+            }
+        }
+
+        if let Some(connectivity) = self.connectivity {
+            prometheus::write_gauge(f, "network_connectivity", "Overall network connectivity")?;
+            prometheus::write_sample(
                 f,
-                "{}:{} ↓ {}{}  ↑ {}{}",
-                itf_name,
-                name_pad,
-                rx_pad,
-                colorize_speed(itf_stats.rx_bps, itf_stats.line_bps, rx_str),
-                tx_pad,
-                colorize_speed(itf_stats.tx_bps, itf_stats.line_bps, tx_str)
+                "network_connectivity",
+                &[("state", &connectivity.to_string())],
+                1,
             )?;
         }
+        if let Some(ipv6) = self.ipv6 {
+            prometheus::write_gauge(f, "network_ipv6_ok", "IPv6 reachability")?;
+            prometheus::write_sample(f, "network_ipv6_ok", &[], u8::from(ipv6 == Ipv6Status::Ok))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl NetworkStats {
+    /// Get interfaces in display order, with bond/bridge members following their master
+    fn display_order(&self) -> Vec<(&String, &InterfaceStats, bool)> {
+        let mut ordered = Vec::with_capacity(self.interfaces.len());
+        for (itf_name, itf_stats) in self.interfaces.iter().filter(|(_, s)| {
+            s.master
+                .as_ref()
+                .is_none_or(|m| !self.interfaces.contains_key(m))
+        }) {
+            ordered.push((itf_name, itf_stats, false));
+            ordered.extend(
+                self.interfaces
+                    .iter()
+                    .filter(|(_, s)| s.master.as_ref() == Some(itf_name))
+                    .map(|(n, s)| (n, s, true)),
+            );
+        }
+        ordered
+    }
+}
+
+/// Format member interface link state and speed
+fn format_link(itf_stats: &InterfaceStats, unit: RateUnit) -> String {
+    let state = itf_stats.operstate.as_deref().unwrap_or("unknown");
+    let s = match itf_stats.line_bps {
+        Some(line_bps) if state == "up" => format!("{state}, {}", format_rate(line_bps, unit, 0)),
+        _ => state.to_owned(),
+    };
+    let severity = if state == "up" {
+        Severity::Ok
+    } else {
+        Severity::Critical
+    };
+    style::theme().paint(&s, severity)
+}
+
+/// Format interface details, half duplex is colorized because it almost always indicates a cabling problem
+fn format_details(details: &InterfaceDetails) -> String {
+    let mut parts = Vec::new();
+    if let Some(driver) = details.driver.as_ref() {
+        parts.push(driver.to_owned());
+    }
+    if let Some(mac) = details.mac.as_ref() {
+        parts.push(mac.to_owned());
+    }
+    parts.extend(details.addresses.iter().cloned());
+    if let Some(duplex) = details.duplex.as_ref() {
+        let duplex_str = format!("{duplex} duplex");
+        parts.push(if duplex == "half" {
+            style::theme().paint(&duplex_str, Severity::Critical)
+        } else {
+            duplex_str
+        });
+    }
+    parts.join(", ")
+}
+
+/// Icon for interface, guessed from its name
+fn interface_icon(name: &str) -> icons::Icon {
+    if name.starts_with("wl") {
+        icons::Icon::Wifi
+    } else {
+        icons::Icon::Ethernet
+    }
+}
+
+impl fmt::Display for NetworkStats {
+    /// Output network stats
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ipv6) = self.ipv6 {
+            writeln!(f, "IPv6: {ipv6}")?;
+        }
+
+        let ordered = self.display_order();
+        let mut table = Table::new(&[Align::Left, Align::Right, Align::Right]);
+        for (itf_name, itf_stats, is_member) in &ordered {
+            table.push(vec![
+                format!(
+                    "{}{}{itf_name}:",
+                    if *is_member { MEMBER_INDENT } else { "" },
+                    icons::prefix(interface_icon(itf_name)),
+                ),
+                colorize_speed(
+                    itf_stats.rx_bps,
+                    itf_stats.line_bps,
+                    &prometheus::sample_key(
+                        "network_receive_bits_per_second",
+                        &[("interface", itf_name)],
+                    ),
+                    format_rate(itf_stats.rx_bps, self.unit, 0),
+                ),
+                colorize_speed(
+                    itf_stats.tx_bps,
+                    itf_stats.line_bps,
+                    &prometheus::sample_key(
+                        "network_transmit_bits_per_second",
+                        &[("interface", itf_name)],
+                    ),
+                    format_rate(itf_stats.tx_bps, self.unit, 0),
+                ),
+            ]);
+        }
+        let rows = table.padded_rows(&table.widths());
+
+        for ((itf_name, itf_stats, is_member), row) in ordered.into_iter().zip(rows) {
+            let indent = if is_member { MEMBER_INDENT } else { "" };
+            write!(f, "{} ↓ {}  ↑ {}", row[0], row[1], row[2])?;
+            if let Some(sparkline) = self.sparklines.get(itf_name) {
+                write!(f, "  {sparkline}")?;
+            }
+            if is_member {
+                write!(f, "  {}", format_link(itf_stats, self.unit))?;
+            }
+            writeln!(f)?;
+            if let Some(details) = itf_stats.details.as_ref() {
+                let details_str = format_details(details);
+                if !details_str.is_empty() {
+                    writeln!(f, "{indent}{MEMBER_INDENT}{details_str}")?;
+                }
+            }
+        }
 
         Ok(())
     }
 }
 
 #[cfg(test)]
+#[expect(clippy::shadow_unrelated)]
 mod tests {
     use super::*;
 
@@ -230,6 +863,9 @@ mod tests {
                 rx_bps: 1,
                 tx_bps: 1_234_567,
                 line_bps: None,
+                master: None,
+                operstate: None,
+                details: None,
             },
         );
         stats.insert(
@@ -238,6 +874,9 @@ mod tests {
                 rx_bps: 1_234_567_890,
                 tx_bps: 1_234,
                 line_bps: None,
+                master: None,
+                operstate: None,
+                details: None,
             },
         );
         stats.insert(
@@ -246,6 +885,9 @@ mod tests {
                 rx_bps: 799_999,
                 tx_bps: 800_000,
                 line_bps: Some(1_000_000),
+                master: None,
+                operstate: None,
+                details: None,
             },
         );
         stats.insert(
@@ -254,6 +896,9 @@ mod tests {
                 rx_bps: 900_000,
                 tx_bps: 899_999,
                 line_bps: Some(1_000_000),
+                master: None,
+                operstate: None,
+                details: None,
             },
         );
         stats.insert(
@@ -262,11 +907,226 @@ mod tests {
                 rx_bps: 900_000_001,
                 tx_bps: 800_000_001,
                 line_bps: Some(1_000_000_000),
+                master: None,
+                operstate: None,
+                details: None,
             },
         );
         assert_eq!(
-            format!("{}", NetworkStats { interfaces: stats }),
+            format!("{}", NetworkStats {
+                    interfaces: stats,
+                    connectivity: None,
+                    ipv6: None,
+                    sparklines: BTreeMap::new(),
+                    unit: RateUnit::Bits
+                }),
             "i1:         ↓      1 b/s  ↑   1.2 Mb/s\ninterface2: ↓   1.2 Gb/s  ↑   1.2 kb/s\nitf3:       ↓ 800.0 kb/s  ↑ \u{1b}[33m800.0 kb/s\u{1b}[0m\nitf4:       ↓ \u{1b}[31m900.0 kb/s\u{1b}[0m  ↑ \u{1b}[33m900.0 kb/s\u{1b}[0m\nitf5:       ↓ \u{1b}[31m900.0 Mb/s\u{1b}[0m  ↑ \u{1b}[33m800.0 Mb/s\u{1b}[0m\n"
         );
     }
+
+    #[test]
+    fn test_output_network_stats_members() {
+        let mut stats = BTreeMap::new();
+        stats.insert(
+            "bond0".to_owned(),
+            InterfaceStats {
+                rx_bps: 2_000,
+                tx_bps: 4_000,
+                line_bps: Some(2_000_000_000),
+                master: None,
+                operstate: Some("up".to_owned()),
+                details: None,
+            },
+        );
+        stats.insert(
+            "eno1".to_owned(),
+            InterfaceStats {
+                rx_bps: 2_000,
+                tx_bps: 4_000,
+                line_bps: Some(1_000_000_000),
+                master: Some("bond0".to_owned()),
+                operstate: Some("up".to_owned()),
+                details: None,
+            },
+        );
+        stats.insert(
+            "eno2".to_owned(),
+            InterfaceStats {
+                rx_bps: 0,
+                tx_bps: 0,
+                line_bps: None,
+                master: Some("bond0".to_owned()),
+                operstate: Some("down".to_owned()),
+                details: None,
+            },
+        );
+        stats.insert(
+            "wlan0".to_owned(),
+            InterfaceStats {
+                rx_bps: 1,
+                tx_bps: 1,
+                line_bps: None,
+                master: None,
+                operstate: Some("up".to_owned()),
+                details: None,
+            },
+        );
+        assert_eq!(
+            format!("{}", NetworkStats {
+                    interfaces: stats,
+                    connectivity: None,
+                    ipv6: None,
+                    sparklines: BTreeMap::new(),
+                    unit: RateUnit::Bits
+                }),
+            "bond0:  ↓ 2.0 kb/s  ↑ 4.0 kb/s\n  eno1: ↓ 2.0 kb/s  ↑ 4.0 kb/s  up, 1.0 Gb/s\n  eno2: ↓    0 b/s  ↑    0 b/s  \u{1b}[31mdown\u{1b}[0m\nwlan0:  ↓    1 b/s  ↑    1 b/s\n"
+        );
+    }
+
+    #[test]
+    fn test_output_network_stats_details() {
+        let mut stats = BTreeMap::new();
+        stats.insert(
+            "eth0".to_owned(),
+            InterfaceStats {
+                rx_bps: 1,
+                tx_bps: 1,
+                line_bps: None,
+                master: None,
+                operstate: Some("up".to_owned()),
+                details: Some(InterfaceDetails {
+                    driver: Some("e1000e".to_owned()),
+                    mac: Some("00:11:22:33:44:55".to_owned()),
+                    addresses: vec!["192.168.1.2/24".to_owned(), "2001:db8::2/64".to_owned()],
+                    duplex: Some("half".to_owned()),
+                }),
+            },
+        );
+        stats.insert(
+            "wg0".to_owned(),
+            InterfaceStats {
+                rx_bps: 1,
+                tx_bps: 1,
+                line_bps: None,
+                master: None,
+                operstate: Some("unknown".to_owned()),
+                details: Some(InterfaceDetails {
+                    driver: None,
+                    mac: None,
+                    addresses: Vec::new(),
+                    duplex: None,
+                }),
+            },
+        );
+        assert_eq!(
+            format!("{}", NetworkStats {
+                    interfaces: stats,
+                    connectivity: None,
+                    ipv6: None,
+                    sparklines: BTreeMap::new(),
+                    unit: RateUnit::Bits
+                }),
+            "eth0: ↓ 1 b/s  ↑ 1 b/s\n  e1000e, 00:11:22:33:44:55, 192.168.1.2/24, 2001:db8::2/64, \u{1b}[31mhalf duplex\u{1b}[0m\nwg0:  ↓ 1 b/s  ↑ 1 b/s\n"
+        );
+    }
+
+    #[test]
+    fn test_output_connectivity() {
+        let stats = NetworkStats {
+            interfaces: BTreeMap::new(),
+            connectivity: Some(Connectivity::Full),
+            ipv6: None,
+            sparklines: BTreeMap::new(),
+            unit: RateUnit::Bits,
+        };
+        assert_eq!(format!("{stats}"), "");
+        assert_eq!(stats.title_suffix(), "Connectivity: full");
+        let stats = NetworkStats {
+            connectivity: Some(Connectivity::Portal),
+            ..stats
+        };
+        assert_eq!(
+            stats.title_suffix(),
+            "Connectivity: \u{1b}[31mportal\u{1b}[0m"
+        );
+        let stats = NetworkStats {
+            connectivity: None,
+            ..stats
+        };
+        assert_eq!(stats.title_suffix(), "");
+    }
+
+    #[test]
+    fn test_parse_connectivity() {
+        assert_eq!(parse_nm_connectivity(4), Some(Connectivity::Full));
+        assert_eq!(parse_nm_connectivity(2), Some(Connectivity::Portal));
+        assert_eq!(parse_nm_connectivity(0), None);
+        assert_eq!(
+            parse_networkd_online_state("partial"),
+            Some(Connectivity::Limited)
+        );
+        assert_eq!(parse_networkd_online_state("unknown"), None);
+    }
+
+    #[test]
+    fn test_output_ipv6() {
+        assert_eq!(
+            format!(
+                "{}",
+                NetworkStats {
+                    interfaces: BTreeMap::new(),
+                    connectivity: None,
+                    ipv6: Some(Ipv6Status::Broken),
+                    sparklines: BTreeMap::new(),
+                    unit: RateUnit::Bits
+                }
+            ),
+            "IPv6: \u{1b}[31mbroken\u{1b}[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_is_global_inet6_line() {
+        assert!(is_global_inet6_line(
+            "2a0102000000000000000000000000ab 02 40 00 00     eth0"
+        ));
+        assert!(!is_global_inet6_line(
+            "fe8000000000000000fc00fffe000001 04 40 20 80     eth0"
+        ));
+        assert!(!is_global_inet6_line(
+            "fd000000000000000000000000000002 04 40 00 82     eth0"
+        ));
+        assert!(!is_global_inet6_line(
+            "00000000000000000000000000000001 01 80 10 80       lo"
+        ));
+    }
+
+    #[test]
+    fn test_interface_icon() {
+        assert_eq!(interface_icon("wlp3s0"), icons::Icon::Wifi);
+        assert_eq!(interface_icon("wlan0"), icons::Icon::Wifi);
+        assert_eq!(interface_icon("enp0s31f6"), icons::Icon::Ethernet);
+        assert_eq!(interface_icon("br0"), icons::Icon::Ethernet);
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let cfg = config::NetConfig {
+            interface_blacklist: vec![regex::Regex::new("^veth").unwrap()],
+            ..config::NetConfig::default()
+        };
+        assert!(is_excluded(&cfg, "lo"));
+        assert!(is_excluded(&cfg, "veth1234"));
+        assert!(!is_excluded(&cfg, "eth0"));
+
+        let cfg = config::NetConfig {
+            interface_whitelist: vec![regex::Regex::new("^(eth|wlan)").unwrap()],
+            interface_blacklist: vec![regex::Regex::new("^eth1$").unwrap()],
+            ..config::NetConfig::default()
+        };
+        assert!(!is_excluded(&cfg, "eth0"));
+        assert!(is_excluded(&cfg, "eth1"));
+        assert!(!is_excluded(&cfg, "wlan0"));
+        assert!(is_excluded(&cfg, "docker0"));
+    }
 }
@@ -0,0 +1,444 @@
+//! Minimal rtnetlink client, to dump network interface information in a single round-trip
+
+use std::{collections::BTreeMap, io, mem, net::IpAddr, time::Instant};
+
+use libc::{
+    c_void, nlmsghdr, recv, sendto, sockaddr, sockaddr_nl, socket, AF_INET, AF_INET6, AF_NETLINK,
+    AF_UNSPEC, IFA_ADDRESS, IFA_LOCAL, IFLA_ADDRESS, IFLA_IFNAME, IFLA_MASTER, IFLA_OPERSTATE,
+    IFLA_STATS64, NETLINK_ROUTE, NLMSG_DONE, NLMSG_ERROR, NLM_F_DUMP, NLM_F_REQUEST, RTM_GETADDR,
+    RTM_GETLINK, RTM_NEWADDR, RTM_NEWLINK, RT_SCOPE_LINK, SOCK_CLOEXEC, SOCK_RAW,
+};
+
+/// Size of netlink message header
+const NLMSG_HDR_LEN: usize = size_of::<nlmsghdr>();
+
+/// Size of `struct ifinfomsg`
+const IFINFOMSG_LEN: usize = 16;
+
+/// Size of `struct ifaddrmsg`
+const IFADDRMSG_LEN: usize = 8;
+
+/// Size of `struct rtattr`
+const RTATTR_HDR_LEN: usize = 4;
+
+/// Offset of `rx_bytes` in `struct rtnl_link_stats64`
+const STATS64_RX_BYTES_OFFSET: usize = 16;
+
+/// Offset of `tx_bytes` in `struct rtnl_link_stats64`
+const STATS64_TX_BYTES_OFFSET: usize = 24;
+
+/// Network link information, as reported by a `RTM_NEWLINK` message
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Link {
+    /// Interface name
+    pub name: String,
+    /// Rx byte count
+    pub rx_bytes: u64,
+    /// Tx byte count
+    pub tx_bytes: u64,
+    /// Interface index of bond or bridge this interface is a member of
+    pub master_index: Option<u32>,
+    /// Operational state, with the same names as in sysfs
+    pub operstate: Option<String>,
+    /// Hardware address
+    pub mac: Option<String>,
+}
+
+/// Links by interface index, and timestamp of the dump
+pub(crate) type LinkDump = (BTreeMap<u32, Link>, Instant);
+
+/// Network address, as reported by a `RTM_NEWADDR` message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Address {
+    /// IP address
+    pub ip: IpAddr,
+    /// Network prefix length
+    pub prefix_len: u8,
+}
+
+/// Netlink socket file descriptor, closed on drop
+struct NetlinkSocket(libc::c_int);
+
+impl Drop for NetlinkSocket {
+    fn drop(&mut self) {
+        // SAFETY: libc call, fd is owned
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+/// Align netlink length to 4 bytes
+const fn nl_align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Dump all network links
+pub(crate) fn dump_links() -> anyhow::Result<LinkDump> {
+    let mut links = BTreeMap::new();
+    dump(RTM_GETLINK, IFINFOMSG_LEN, &mut |msg_type, payload| {
+        if msg_type == RTM_NEWLINK {
+            let (index, link) = parse_link(payload)?;
+            links.insert(index, link);
+        }
+        Ok(())
+    })?;
+    Ok((links, Instant::now()))
+}
+
+/// Dump addresses of all network links, by interface index, excluding link and host local ones
+pub(crate) fn dump_addresses() -> anyhow::Result<BTreeMap<u32, Vec<Address>>> {
+    let mut addresses: BTreeMap<u32, Vec<Address>> = BTreeMap::new();
+    dump(RTM_GETADDR, IFADDRMSG_LEN, &mut |msg_type, payload| {
+        if msg_type == RTM_NEWADDR {
+            if let Some((index, address)) = parse_address(payload)? {
+                addresses.entry(index).or_default().push(address);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(addresses)
+}
+
+/// Handler of each message of a dump response, with its type and payload
+type MessageHandler<'a> = dyn FnMut(u16, &[u8]) -> anyhow::Result<()> + 'a;
+
+/// Send a dump request with an empty family specific header, and handle response messages
+fn dump(
+    request_type: u16,
+    header_len: usize,
+    handle: &mut MessageHandler<'_>,
+) -> anyhow::Result<()> {
+    // SAFETY: libc call
+    let fd = unsafe { socket(AF_NETLINK, SOCK_RAW | SOCK_CLOEXEC, NETLINK_ROUTE) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    let sock = NetlinkSocket(fd);
+
+    // Build request: nlmsghdr + family specific header, starting with the address family
+    let seq: u32 = 1;
+    let req_len = NLMSG_HDR_LEN + header_len;
+    let mut req = Vec::with_capacity(req_len);
+    req.extend_from_slice(&(req_len as u32).to_ne_bytes());
+    req.extend_from_slice(&request_type.to_ne_bytes());
+    req.extend_from_slice(&((NLM_F_REQUEST | NLM_F_DUMP) as u16).to_ne_bytes());
+    req.extend_from_slice(&seq.to_ne_bytes());
+    req.extend_from_slice(&0_u32.to_ne_bytes());
+    req.push(AF_UNSPEC as u8);
+    req.resize(req_len, 0);
+
+    // SAFETY: libc call arg
+    let mut addr: sockaddr_nl = unsafe { mem::zeroed() };
+    addr.nl_family = AF_NETLINK as u16;
+    // SAFETY: libc call
+    let rc = unsafe {
+        sendto(
+            sock.0,
+            req.as_ptr().cast::<c_void>(),
+            req.len(),
+            0,
+            (&raw const addr).cast::<sockaddr>(),
+            size_of::<sockaddr_nl>() as u32,
+        )
+    };
+    if rc < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    // Receive multipart response
+    let mut buf = vec![0_u8; 32 * 1024];
+    loop {
+        // SAFETY: libc call, buffer is valid for its length
+        let len = unsafe { recv(sock.0, buf.as_mut_ptr().cast::<c_void>(), buf.len(), 0) };
+        if len < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        if parse_messages(&buf[..len as usize], seq, handle)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Parse a buffer of netlink messages, return true if the dump is complete
+fn parse_messages(
+    mut buf: &[u8],
+    seq: u32,
+    handle: &mut MessageHandler<'_>,
+) -> anyhow::Result<bool> {
+    while buf.len() >= NLMSG_HDR_LEN {
+        let msg_len = u32::from_ne_bytes(buf[0..4].try_into()?) as usize;
+        let msg_type = u16::from_ne_bytes(buf[4..6].try_into()?);
+        let msg_seq = u32::from_ne_bytes(buf[8..12].try_into()?);
+        anyhow::ensure!(
+            (NLMSG_HDR_LEN..=buf.len()).contains(&msg_len),
+            "Invalid netlink message length"
+        );
+        let payload = &buf[NLMSG_HDR_LEN..msg_len];
+        if msg_seq == seq {
+            match i32::from(msg_type) {
+                NLMSG_DONE => return Ok(true),
+                NLMSG_ERROR => {
+                    let errno = i32::from_ne_bytes(
+                        payload
+                            .get(0..4)
+                            .ok_or_else(|| anyhow::anyhow!("Truncated netlink error"))?
+                            .try_into()?,
+                    );
+                    return Err(io::Error::from_raw_os_error(-errno).into());
+                }
+                _ => handle(msg_type, payload)?,
+            }
+        }
+        buf = buf.get(nl_align(msg_len)..).unwrap_or_default();
+    }
+    Ok(false)
+}
+
+/// Parse `RTM_NEWLINK` message payload
+fn parse_link(payload: &[u8]) -> anyhow::Result<(u32, Link)> {
+    anyhow::ensure!(payload.len() >= IFINFOMSG_LEN, "Truncated ifinfomsg");
+    let index = u32::from_ne_bytes(payload[4..8].try_into()?);
+    let mut link = Link::default();
+
+    parse_attrs(&payload[IFINFOMSG_LEN..], |attr_type, data| {
+        match attr_type {
+            IFLA_IFNAME => {
+                String::from_utf8_lossy(data)
+                    .trim_end_matches('\0')
+                    .clone_into(&mut link.name);
+            }
+            IFLA_ADDRESS => {
+                link.mac = Some(
+                    data.iter()
+                        .map(|b| format!("{b:02x}"))
+                        .collect::<Vec<_>>()
+                        .join(":"),
+                );
+            }
+            IFLA_MASTER if data.len() >= 4 => {
+                link.master_index = Some(u32::from_ne_bytes(data[0..4].try_into()?));
+            }
+            IFLA_OPERSTATE if !data.is_empty() => {
+                link.operstate = Some(operstate_name(data[0]).to_owned());
+            }
+            IFLA_STATS64 if data.len() >= STATS64_TX_BYTES_OFFSET + 8 => {
+                link.rx_bytes = u64::from_ne_bytes(
+                    data[STATS64_RX_BYTES_OFFSET..STATS64_RX_BYTES_OFFSET + 8].try_into()?,
+                );
+                link.tx_bytes = u64::from_ne_bytes(
+                    data[STATS64_TX_BYTES_OFFSET..STATS64_TX_BYTES_OFFSET + 8].try_into()?,
+                );
+            }
+            _ => {}
+        }
+        Ok(())
+    })?;
+
+    Ok((index, link))
+}
+
+/// Parse `RTM_NEWADDR` message payload, if the address is not link or host local
+fn parse_address(payload: &[u8]) -> anyhow::Result<Option<(u32, Address)>> {
+    anyhow::ensure!(payload.len() >= IFADDRMSG_LEN, "Truncated ifaddrmsg");
+    let family = i32::from(payload[0]);
+    let prefix_len = payload[1];
+    let scope = payload[3];
+    let index = u32::from_ne_bytes(payload[4..8].try_into()?);
+    if scope >= RT_SCOPE_LINK {
+        return Ok(None);
+    }
+
+    // Local address takes precedence, address is the peer one for point to point links
+    let mut local = None;
+    let mut address = None;
+    parse_attrs(&payload[IFADDRMSG_LEN..], |attr_type, data| {
+        let ip = match (family, data.len()) {
+            (AF_INET, 4) => IpAddr::from(<[u8; 4]>::try_from(data)?),
+            (AF_INET6, 16) => IpAddr::from(<[u8; 16]>::try_from(data)?),
+            _ => return Ok(()),
+        };
+        match attr_type {
+            IFA_LOCAL => local = Some(ip),
+            IFA_ADDRESS => address = Some(ip),
+            _ => {}
+        }
+        Ok(())
+    })?;
+
+    Ok(local
+        .or(address)
+        .map(|ip| (index, Address { ip, prefix_len })))
+}
+
+/// Parse netlink attributes, and handle each one with its type and data
+fn parse_attrs(
+    mut attrs: &[u8],
+    mut handle: impl FnMut(u16, &[u8]) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    while attrs.len() >= RTATTR_HDR_LEN {
+        let attr_len = u16::from_ne_bytes(attrs[0..2].try_into()?) as usize;
+        let attr_type = u16::from_ne_bytes(attrs[2..4].try_into()?);
+        anyhow::ensure!(
+            (RTATTR_HDR_LEN..=attrs.len()).contains(&attr_len),
+            "Invalid netlink attribute length"
+        );
+        handle(attr_type, &attrs[RTATTR_HDR_LEN..attr_len])?;
+        attrs = attrs.get(nl_align(attr_len)..).unwrap_or_default();
+    }
+    Ok(())
+}
+
+/// Get operational state name from RFC 2863 value, matching sysfs operstate strings
+fn operstate_name(state: u8) -> &'static str {
+    match state {
+        1 => "notpresent",
+        2 => "down",
+        3 => "lowerlayerdown",
+        4 => "testing",
+        5 => "dormant",
+        6 => "up",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::shadow_unrelated)]
+mod tests {
+    use super::*;
+
+    fn attr(attr_type: u16, data: &[u8]) -> Vec<u8> {
+        let mut v = Vec::new();
+        v.extend_from_slice(&((RTATTR_HDR_LEN + data.len()) as u16).to_ne_bytes());
+        v.extend_from_slice(&attr_type.to_ne_bytes());
+        v.extend_from_slice(data);
+        v.resize(nl_align(v.len()), 0);
+        v
+    }
+
+    fn msg(msg_type: u16, seq: u32, payload: &[u8]) -> Vec<u8> {
+        let mut v = Vec::new();
+        v.extend_from_slice(&((NLMSG_HDR_LEN + payload.len()) as u32).to_ne_bytes());
+        v.extend_from_slice(&msg_type.to_ne_bytes());
+        v.extend_from_slice(&0_u16.to_ne_bytes());
+        v.extend_from_slice(&seq.to_ne_bytes());
+        v.extend_from_slice(&0_u32.to_ne_bytes());
+        v.extend_from_slice(payload);
+        v
+    }
+
+    #[test]
+    fn test_parse_messages() {
+        let mut payload = vec![0_u8; IFINFOMSG_LEN];
+        payload[4..8].copy_from_slice(&3_u32.to_ne_bytes());
+        payload.extend(attr(IFLA_IFNAME, b"eno1\0"));
+        payload.extend(attr(IFLA_ADDRESS, &[0, 0x11, 0x22, 0x33, 0x44, 0xab]));
+        payload.extend(attr(IFLA_MASTER, &5_u32.to_ne_bytes()));
+        payload.extend(attr(IFLA_OPERSTATE, &[6]));
+        let mut stats = vec![0_u8; 8 * 24];
+        stats[16..24].copy_from_slice(&1234_u64.to_ne_bytes());
+        stats[24..32].copy_from_slice(&5678_u64.to_ne_bytes());
+        payload.extend(attr(IFLA_STATS64, &stats));
+
+        let mut buf = msg(RTM_NEWLINK, 1, &payload);
+        buf.extend(msg(RTM_NEWLINK, 2, &payload));
+
+        let mut links = BTreeMap::new();
+        let mut handle_link = |msg_type, payload: &[u8]| {
+            assert_eq!(msg_type, RTM_NEWLINK);
+            let (index, link) = parse_link(payload)?;
+            links.insert(index, link);
+            Ok(())
+        };
+        assert!(!parse_messages(&buf, 1, &mut handle_link).unwrap());
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            links[&3],
+            Link {
+                name: "eno1".to_owned(),
+                rx_bytes: 1234,
+                tx_bytes: 5678,
+                master_index: Some(5),
+                operstate: Some("up".to_owned()),
+                mac: Some("00:11:22:33:44:ab".to_owned()),
+            }
+        );
+
+        let mut handle_none = |_, _: &[u8]| anyhow::bail!("Unexpected message");
+        let buf = msg(NLMSG_DONE as u16, 1, &0_i32.to_ne_bytes());
+        assert!(parse_messages(&buf, 1, &mut handle_none).unwrap());
+
+        let buf = msg(NLMSG_ERROR as u16, 1, &(-libc::EPERM).to_ne_bytes());
+        assert!(parse_messages(&buf, 1, &mut handle_none).is_err());
+    }
+
+    #[test]
+    fn test_parse_address() {
+        let payload = |family: i32, prefix_len, scope, attrs: &[Vec<u8>]| {
+            let mut payload = vec![family as u8, prefix_len, 0, scope];
+            payload.extend_from_slice(&3_u32.to_ne_bytes());
+            payload.extend(attrs.concat());
+            payload
+        };
+
+        assert_eq!(
+            parse_address(&payload(
+                AF_INET,
+                24,
+                0,
+                &[attr(IFA_ADDRESS, &[192, 168, 1, 2])]
+            ))
+            .unwrap(),
+            Some((
+                3,
+                Address {
+                    ip: IpAddr::from([192, 168, 1, 2]),
+                    prefix_len: 24
+                }
+            ))
+        );
+        assert_eq!(
+            parse_address(&payload(
+                AF_INET,
+                32,
+                0,
+                &[
+                    attr(IFA_ADDRESS, &[10, 0, 0, 1]),
+                    attr(IFA_LOCAL, &[10, 0, 0, 2])
+                ]
+            ))
+            .unwrap(),
+            Some((
+                3,
+                Address {
+                    ip: IpAddr::from([10, 0, 0, 2]),
+                    prefix_len: 32
+                }
+            ))
+        );
+        let mut ipv6 = [0_u8; 16];
+        ipv6[0..2].copy_from_slice(&[0x20, 0x01]);
+        ipv6[15] = 1;
+        assert_eq!(
+            parse_address(&payload(AF_INET6, 64, 0, &[attr(IFA_ADDRESS, &ipv6)])).unwrap(),
+            Some((
+                3,
+                Address {
+                    ip: IpAddr::from(ipv6),
+                    prefix_len: 64
+                }
+            ))
+        );
+        assert_eq!(
+            parse_address(&payload(
+                AF_INET6,
+                64,
+                RT_SCOPE_LINK,
+                &[attr(IFA_ADDRESS, &ipv6)]
+            ))
+            .unwrap(),
+            None
+        );
+        assert!(parse_address(&[0; 4]).is_err());
+    }
+}
@@ -0,0 +1,333 @@
+//! User defined sections, displaying the output of a command
+
+use std::{
+    fmt,
+    process::{Command, Stdio},
+    sync::{atomic::AtomicBool, Mutex},
+    time::Duration,
+};
+
+use anyhow::Context as _;
+
+use crate::{
+    cache,
+    config::{self, PluginFormat},
+    dylib, icons,
+    module::{Fetcher, Module, ModuleData, Section, Title},
+    prometheus,
+    style::{self, Severity},
+};
+
+/// Letter selecting plugin sections on the command line and in layout config
+pub(crate) const LETTER: &str = "p";
+
+/// Output line
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Line {
+    /// Line text
+    text: String,
+    /// Line severity, used for coloring
+    #[serde(default)]
+    severity: Severity,
+}
+
+/// Plugin output in JSON format
+#[derive(serde::Deserialize)]
+struct JsonOutput {
+    /// Output lines
+    lines: Vec<Line>,
+    /// Single line summary
+    summary: Option<String>,
+}
+
+/// Plugin section data
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct PluginData {
+    /// Plugin name
+    #[serde(skip)]
+    name: String,
+    /// Output lines
+    lines: Vec<Line>,
+    /// Single line summary
+    summary: Option<String>,
+}
+
+/// Get plugin data, from cache if it is recent enough, or by running the plugin otherwise
+pub(crate) fn fetch(cfg: &config::PluginConfig) -> anyhow::Result<ModuleData> {
+    if let Some(library) = cfg.library.as_deref() {
+        // Native plugin data is owned by the plugin, so it is not cached
+        return Ok(Box::new(dylib::fetch(library, &cfg.name)?));
+    }
+    let Some(ttl) = cfg.cache_ttl.map(Duration::from_secs) else {
+        return Ok(Box::new(run(cfg)?));
+    };
+    if let Some(data) = cache::read_section::<PluginData>(&cfg.name, ttl) {
+        return Ok(Box::new(PluginData {
+            name: cfg.name.clone(),
+            ..data
+        }));
+    }
+    let data = run(cfg)?;
+    // Failing to cache only makes the next outputs slower
+    let _ = cache::write_section(&cfg.name, &data);
+    Ok(Box::new(data))
+}
+
+/// Run plugin command, and parse its output
+fn run(cfg: &config::PluginConfig) -> anyhow::Result<PluginData> {
+    let command = cfg
+        .command
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Plugin has no command or library"))?;
+    let output = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {command:?}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json = match cfg.format {
+        PluginFormat::Text => JsonOutput {
+            lines: parse_text(&stdout, output.status.code())?,
+            summary: None,
+        },
+        PluginFormat::Json => {
+            anyhow::ensure!(output.status.success(), "Plugin command failed");
+            parse_json(&stdout)?
+        }
+    };
+    Ok(PluginData {
+        name: cfg.name.clone(),
+        lines: json.lines,
+        summary: json.summary,
+    })
+}
+
+/// Parse JSON output
+fn parse_json(output: &str) -> anyhow::Result<JsonOutput> {
+    serde_json::from_str(output).context("Failed to parse plugin JSON output")
+}
+
+/// Parse text output, with severity from the exit code, like Nagios plugins
+fn parse_text(output: &str, exit_code: Option<i32>) -> anyhow::Result<Vec<Line>> {
+    let severity = match exit_code {
+        Some(0) => Severity::Ok,
+        Some(1) => Severity::Warning,
+        Some(2) => Severity::Critical,
+        _ => anyhow::bail!("Plugin command failed"),
+    };
+    Ok(output
+        .lines()
+        .map(|l| Line {
+            text: l.to_owned(),
+            severity,
+        })
+        .collect())
+}
+
+/// Build sections of plugins, and insert them after the section they are configured to follow,
+/// or at the position of the plugins letter if it is selected
+pub(crate) fn insert_sections(
+    sections: &mut Vec<&'static Section>,
+    mut position: Option<usize>,
+    plugins: &[config::PluginConfig],
+) {
+    for plugin in plugins {
+        let after = plugin
+            .after
+            .as_ref()
+            .and_then(|l| sections.iter().rposition(|s| s.letter == l));
+        if let Some(mut i) = after {
+            // Keep config order of plugins following the same section
+            i += 1;
+            while sections.get(i).is_some_and(|s| s.letter == LETTER) {
+                i += 1;
+            }
+            sections.insert(i, section(plugin));
+            if let Some(position) = position.as_mut().filter(|p| i <= **p) {
+                *position += 1;
+            }
+        } else if let Some(position) = position.as_mut() {
+            sections.insert(*position, section(plugin));
+            *position += 1;
+        }
+    }
+}
+
+/// Plugin sections built so far, reused when the config is reloaded with the same plugins, so that
+/// only changed plugins build new ones
+static BUILT: Mutex<Vec<&'static Section>> = Mutex::new(Vec::new());
+
+/// Get plugin section, built once for each plugin config and living until the program exits
+fn section(cfg: &config::PluginConfig) -> &'static Section {
+    let mut built = BUILT.lock().unwrap();
+    let existing = built
+        .iter()
+        .find(|s| matches!(s.fetcher, Fetcher::Plugin(c) if c == cfg));
+    if let Some(section) = existing {
+        return section;
+    }
+    let cfg: &'static config::PluginConfig = Box::leak(Box::new(cfg.clone()));
+    let section = Box::leak(Box::new(Section {
+        letter: LETTER,
+        key: &cfg.name,
+        title: Title::Text(&cfg.title),
+        icon: icons::Icon::Plugin,
+        max_lines: |_| None,
+        timeout: |t| t.plugin,
+        sources: &[],
+        fetcher: Fetcher::Plugin(cfg),
+        fetching: AtomicBool::new(false),
+    }));
+    built.push(section);
+    section
+}
+
+impl Module for PluginData {
+    /// Only keep lines above warning threshold
+    fn retain_alerts(&mut self) -> bool {
+        self.lines.retain(|l| l.severity != Severity::Ok);
+        !self.lines.is_empty()
+    }
+
+    /// Highest severity of lines
+    fn severity(&self) -> Severity {
+        self.lines
+            .iter()
+            .map(|l| l.severity)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Single line summary, or first line above warning threshold
+    fn compact(&self) -> String {
+        self.summary.clone().unwrap_or_else(|| {
+            self.lines
+                .iter()
+                .find(|l| l.severity != Severity::Ok)
+                .map(|l| l.text.clone())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Write plugin severity as Prometheus metric
+    fn write_prometheus(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        write_severity_metric(f, &self.name, self.severity())
+    }
+}
+
+/// Write plugin severity as Prometheus metric, named after the plugin, because metric families
+/// must not be split between sections
+pub(crate) fn write_severity_metric(
+    f: &mut dyn fmt::Write,
+    plugin_name: &str,
+    severity: Severity,
+) -> fmt::Result {
+    let name = format!(
+        "plugin_{}_severity",
+        plugin_name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>()
+    );
+    prometheus::write_gauge(f, &name, "Plugin severity (0: ok, 1: warning, 2: critical)")?;
+    prometheus::write_sample(f, &name, &[], severity as u8)
+}
+
+impl fmt::Display for PluginData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = style::theme();
+        for line in &self.lines {
+            if line.severity == Severity::Ok {
+                writeln!(f, "{}", line.text)?;
+            } else {
+                writeln!(f, "{}", theme.style(line.severity).paint(&line.text))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_text() {
+        assert_eq!(
+            parse_text("md0: degraded\n", Some(2)).unwrap(),
+            vec![Line {
+                text: "md0: degraded".to_owned(),
+                severity: Severity::Critical
+            }]
+        );
+        assert!(parse_text("", Some(3)).is_err());
+    }
+
+    #[test]
+    fn test_insert_sections() {
+        let plugin = |name: &str, after: Option<&str>| config::PluginConfig {
+            name: name.to_owned(),
+            title: name.to_owned(),
+            command: Some("true".to_owned()),
+            library: None,
+            format: PluginFormat::Text,
+            after: after.map(ToOwned::to_owned),
+            cache_ttl: None,
+        };
+        let mut sections: Vec<_> = ["l", "f", "a"]
+            .into_iter()
+            .filter_map(Section::from_letter)
+            .collect();
+        insert_sections(
+            &mut sections,
+            Some(2),
+            &[
+                plugin("raid", Some("f")),
+                plugin("app", None),
+                plugin("smart", Some("f")),
+                plugin("backup", Some("t")),
+            ],
+        );
+        assert_eq!(
+            sections.iter().map(|s| s.key).collect::<Vec<_>>(),
+            [
+                "load",
+                "filesystems",
+                "raid",
+                "app",
+                "smart",
+                "backup",
+                "messages"
+            ]
+        );
+
+        // Reloading the same plugins reuses their sections
+        let raid = section(&plugin("raid", Some("f")));
+        assert!(std::ptr::eq(raid, sections[2]));
+        assert!(!std::ptr::eq(
+            section(&plugin("raid", Some("l"))),
+            sections[2]
+        ));
+
+        // Without the plugins letter, only plugins following a selected section are displayed
+        let mut anchored: Vec<_> = ["l", "f"]
+            .into_iter()
+            .filter_map(Section::from_letter)
+            .collect();
+        insert_sections(
+            &mut anchored,
+            None,
+            &[
+                plugin("raid", Some("f")),
+                plugin("app", None),
+                plugin("backup", Some("t")),
+                plugin("smart", Some("l")),
+            ],
+        );
+        assert_eq!(
+            anchored.iter().map(|s| s.key).collect::<Vec<_>>(),
+            ["load", "smart", "filesystems", "raid"]
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Prometheus text exposition format output
+
+use std::fmt;
+
+/// Prefix of all metric names
+const METRIC_PREFIX: &str = "motd_";
+
+/// Gauge metric name, help text, and function to get value from an item
+pub(crate) type GaugeDef<T, V> = (&'static str, &'static str, fn(&T) -> V);
+
+/// Write gauge metric family header
+pub(crate) fn write_gauge(f: &mut dyn fmt::Write, name: &str, help: &str) -> fmt::Result {
+    writeln!(f, "# HELP {METRIC_PREFIX}{name} {help}")?;
+    writeln!(f, "# TYPE {METRIC_PREFIX}{name} gauge")
+}
+
+/// Write metric sample
+pub(crate) fn write_sample<V: fmt::Display>(
+    f: &mut dyn fmt::Write,
+    name: &str,
+    labels: &[(&str, &str)],
+    value: V,
+) -> fmt::Result {
+    writeln!(f, "{} {value}", sample_key(name, labels))
+}
+
+/// Get full metric name with labels, as written in samples
+pub(crate) fn sample_key(name: &str, labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return format!("{METRIC_PREFIX}{name}");
+    }
+    let labels = labels
+        .iter()
+        .map(|(k, v)| format!("{k}=\"{}\"", escape_label_value(v)))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{METRIC_PREFIX}{name}{{{labels}}}")
+}
+
+/// Escape label value, as required by the exposition format
+fn escape_label_value(val: &str) -> String {
+    val.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Parse metric names with labels, and values from Prometheus text format
+pub(crate) fn parse_samples(metrics: &str) -> Vec<(&str, f64)> {
+    metrics
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| {
+            let (key, value) = l.rsplit_once(' ')?;
+            Some((key, value.parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_sample() {
+        let mut s = String::new();
+        write_gauge(&mut s, "tasks", "Task count").unwrap();
+        write_sample(&mut s, "tasks", &[], 12).unwrap();
+        write_sample(
+            &mut s,
+            "filesystem_size_bytes",
+            &[("mountpoint", "/mnt/\"a\\b\"")],
+            1000,
+        )
+        .unwrap();
+        write_sample(&mut s, "load_average", &[("period", "1m"), ("x", "y")], 0.5).unwrap();
+        assert_eq!(
+            s,
+            "# HELP motd_tasks Task count\n# TYPE motd_tasks gauge\nmotd_tasks 12\nmotd_filesystem_size_bytes{mountpoint=\"/mnt/\\\"a\\\\b\\\"\"} 1000\nmotd_load_average{period=\"1m\",x=\"y\"} 0.5\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_samples() {
+        let metrics = "# HELP motd_tasks Task count\n# TYPE motd_tasks gauge\nmotd_tasks 12\nmotd_filesystem_used_bytes{mountpoint=\"/mnt/a b\"} 1000\n";
+        assert_eq!(
+            parse_samples(metrics),
+            vec![
+                ("motd_tasks", 12.0),
+                (
+                    "motd_filesystem_used_bytes{mountpoint=\"/mnt/a b\"}",
+                    1000.0
+                )
+            ]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Data sources shared by several sections, so that each is sampled once per output
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use crate::{config, fs, mem, module::Section, net, temp};
+
+/// Shared data source that sections depend on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Source {
+    /// `/proc/meminfo` snapshot
+    MemInfo,
+    /// Mounted filesystems list
+    Mounts,
+    /// Hwmon temperature input files
+    Hwmon,
+    /// Network counters, sampled twice to compute rates
+    NetSample,
+}
+
+/// Value fetched at most once per output, unless fetching fails
+struct Snapshot<T>(Mutex<Option<T>>);
+
+impl<T> Default for Snapshot<T> {
+    fn default() -> Self {
+        Self(Mutex::new(None))
+    }
+}
+
+impl<T: Clone> Snapshot<T> {
+    /// Get value, fetching it if not done since last clear
+    fn get<F: FnOnce() -> anyhow::Result<T>>(&self, fetch: F) -> anyhow::Result<T> {
+        // Lock is held while fetching, so that concurrent users wait for the same value
+        let mut value = self.0.lock().unwrap();
+        if let Some(value) = value.as_ref() {
+            return Ok(value.clone());
+        }
+        let fetched = fetch()?;
+        *value = Some(fetched.clone());
+        Ok(fetched)
+    }
+
+    /// Drop value, so that the next get fetches it again
+    fn clear(&self) {
+        *self.0.lock().unwrap() = None;
+    }
+}
+
+/// Shared data providers, living until the program exits
+#[derive(Default)]
+pub(crate) struct Providers {
+    /// Network stats sampler, keeping previous sample between outputs to compute rates
+    pub net: Mutex<net::Sampler>,
+    /// Memory info, for memory and swap sections
+    meminfo: Snapshot<mem::MemInfo>,
+    /// Mounted filesystems
+    mounts: Snapshot<Vec<fs::MountEntry>>,
+    /// Hwmon temperature input files
+    hwmon: Snapshot<Vec<PathBuf>>,
+}
+
+impl Providers {
+    /// Drop snapshots of the previous output, and start sampling sources the sections depend on
+    /// in the background, each once, so that sections fetching them later wait for less time
+    pub(crate) fn refresh(&'static self, cfg: &Arc<config::Config>, sections: &[&Section]) {
+        self.meminfo.clear();
+        self.mounts.clear();
+        self.hwmon.clear();
+        let mut sources: Vec<Source> = Vec::new();
+        for source in sections.iter().flat_map(|s| s.sources) {
+            if !sources.contains(source) {
+                sources.push(*source);
+            }
+        }
+        for source in sources {
+            // Errors are reported by the sections fetching the source again
+            let cfg = Arc::clone(cfg);
+            thread::spawn(move || {
+                let _ = match source {
+                    Source::MemInfo => self.meminfo().map(drop),
+                    Source::Mounts => self.mounts().map(drop),
+                    Source::Hwmon => self.hwmon().map(drop),
+                    Source::NetSample => self.net.lock().unwrap().prime(&cfg.net),
+                };
+            });
+        }
+    }
+
+    /// Get memory info snapshot
+    pub(crate) fn meminfo(&self) -> anyhow::Result<mem::MemInfo> {
+        self.meminfo.get(mem::fetch)
+    }
+
+    /// Get mounted filesystems snapshot
+    pub(crate) fn mounts(&self) -> anyhow::Result<Vec<fs::MountEntry>> {
+        self.mounts.get(fs::read_mounts)
+    }
+
+    /// Get hwmon temperature input files snapshot
+    pub(crate) fn hwmon(&self) -> anyhow::Result<Vec<PathBuf>> {
+        self.hwmon.get(|| Ok(temp::scan_hwmon()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let snapshot = Snapshot::default();
+        assert_eq!(snapshot.get(|| Ok(1)).unwrap(), 1);
+        assert_eq!(snapshot.get(|| Ok(2)).unwrap(), 1);
+        snapshot.clear();
+        assert!(snapshot.get(|| anyhow::bail!("Failed")).is_err());
+        assert_eq!(snapshot.get(|| Ok(3)).unwrap(), 3);
+    }
+}
@@ -0,0 +1,27 @@
+//! Config reload requests, sent with SIGHUP in persistent modes
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether a reload was requested since the last check
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// SIGHUP handler, only doing an atomic store to be async signal safe
+extern "C" fn on_sighup(_signal: libc::c_int) {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Install SIGHUP handler requesting a reload
+#[expect(clippy::fn_to_numeric_cast_any)]
+pub(crate) fn install_handler() -> anyhow::Result<()> {
+    // libc takes handlers as integers
+    let handler = on_sighup as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: libc call, handler is async signal safe
+    let prev = unsafe { libc::signal(libc::SIGHUP, handler) };
+    anyhow::ensure!(prev != libc::SIG_ERR, "Failed to install SIGHUP handler");
+    Ok(())
+}
+
+/// Check if a reload was requested, and clear the request
+pub(crate) fn requested() -> bool {
+    REQUESTED.swap(false, Ordering::SeqCst)
+}
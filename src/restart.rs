@@ -0,0 +1,140 @@
+//! Detection of services using deleted shared libraries, which need a restart after upgrades
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+};
+
+use crate::sysroot;
+
+/// Services using deleted shared libraries, with the library file names
+pub(crate) type Restarts = BTreeMap<String, BTreeSet<String>>;
+
+/// Scan process memory maps for deleted shared libraries, and get system and user services to
+/// restart
+pub(crate) fn scan() -> anyhow::Result<(Restarts, Restarts)> {
+    // SAFETY: libc call
+    let uid = unsafe { libc::getuid() };
+    let user_manager = format!("user@{uid}.service");
+    let mut system = Restarts::new();
+    let mut user = Restarts::new();
+    for entry in fs::read_dir(sysroot::path("/proc"))?.flatten() {
+        if !entry
+            .file_name()
+            .to_str()
+            .is_some_and(|n| n.bytes().all(|b| b.is_ascii_digit()))
+        {
+            continue;
+        }
+        // Processes may exit during the scan, and maps of other users are not readable
+        let dir = entry.path();
+        let Ok(maps) = fs::read_to_string(dir.join("maps")) else {
+            continue;
+        };
+        let libs: BTreeSet<String> = maps.lines().filter_map(deleted_library).collect();
+        if libs.is_empty() {
+            continue;
+        }
+        let Ok(cgroup) = fs::read_to_string(dir.join("cgroup")) else {
+            continue;
+        };
+        match cgroup_service(&cgroup, &user_manager) {
+            Some((false, unit)) => system.entry(unit).or_default().extend(libs),
+            Some((true, unit)) => user.entry(unit).or_default().extend(libs),
+            None => {}
+        }
+    }
+    Ok((system, user))
+}
+
+/// Get file name of a deleted shared library from a memory map line
+fn deleted_library(line: &str) -> Option<String> {
+    let path = line.strip_suffix(" (deleted)")?;
+    let path = path.get(path.find('/')?..)?;
+    let name = path.rsplit('/').next()?;
+    // Like libfoo.so or libfoo.so.1.2
+    name.split('.')
+        .skip(1)
+        .any(|e| e == "so")
+        .then(|| name.to_owned())
+}
+
+/// Get service of a process from its cgroup, and whether it is a service of the user manager
+fn cgroup_service(cgroup: &str, user_manager: &str) -> Option<(bool, String)> {
+    let path = cgroup.lines().find_map(|l| {
+        l.strip_prefix("0::")
+            .or_else(|| l.split_once(":name=systemd:").map(|(_, p)| p))
+    })?;
+    let mut services = path.split('/').filter(|c| c.ends_with(".service"));
+    let first = services.next()?;
+    match services.next_back() {
+        None => Some((false, first.to_owned())),
+        Some(unit) if first == user_manager => Some((true, unit.to_owned())),
+        // Service of another user
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deleted_library() {
+        assert_eq!(
+            deleted_library(
+                "7f2c1a000000-7f2c1a028000 r--p 00000000 fe:01 1234   /usr/lib/x86_64-linux-gnu/libssl.so.3 (deleted)"
+            ),
+            Some("libssl.so.3".to_owned())
+        );
+        assert_eq!(
+            deleted_library(
+                "7f2c1a000000-7f2c1a028000 r--p 00000000 fe:01 1234   /usr/lib/x86_64-linux-gnu/libssl.so.3"
+            ),
+            None
+        );
+        assert_eq!(
+            deleted_library(
+                "7f2c1a000000-7f2c1a028000 rw-s 00000000 00:01 1234   /memfd:wayland (deleted)"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cgroup_service() {
+        let user_manager = "user@1000.service";
+        assert_eq!(
+            cgroup_service("0::/system.slice/nginx.service\n", user_manager),
+            Some((false, "nginx.service".to_owned()))
+        );
+        assert_eq!(
+            cgroup_service(
+                "0::/user.slice/user-1000.slice/user@1000.service/app.slice/syncthing.service\n",
+                user_manager
+            ),
+            Some((true, "syncthing.service".to_owned()))
+        );
+        assert_eq!(
+            cgroup_service(
+                "0::/user.slice/user-1001.slice/user@1001.service/app.slice/syncthing.service\n",
+                user_manager
+            ),
+            None
+        );
+        assert_eq!(
+            cgroup_service(
+                "0::/user.slice/user-1000.slice/session-2.scope\n",
+                user_manager
+            ),
+            None
+        );
+        assert_eq!(
+            cgroup_service(
+                "12:pids:/system.slice/cron.service\n1:name=systemd:/system.slice/cron.service\n",
+                user_manager
+            ),
+            Some((false, "cron.service".to_owned()))
+        );
+    }
+}
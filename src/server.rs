@@ -0,0 +1,300 @@
+//! HTTP API server
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead as _, BufReader, Read, Write as _},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Maximum size of request line and headers
+const MAX_HEAD_LEN: u64 = 8 * 1024;
+
+/// Time allowed to receive a whole request head, and to send each response write
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum count of connections handled at the same time, others are closed
+const MAX_CONNECTIONS: usize = 32;
+
+/// Server endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Endpoint {
+    /// Section data as JSON
+    Json,
+    /// Prometheus metrics
+    Metrics,
+    /// Text output in a HTML page
+    Html,
+}
+
+impl Endpoint {
+    /// Get endpoint from request path
+    fn from_path(path: &str) -> Option<Self> {
+        match path.split('?').next().unwrap_or_default() {
+            "/json" => Some(Self::Json),
+            "/metrics" => Some(Self::Metrics),
+            "/" | "/html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    /// Response content type
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Metrics => "text/plain; version=0.0.4; charset=utf-8",
+            Self::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+/// Wrap text output in a HTML page
+fn html_page(text: &[u8]) -> Vec<u8> {
+    let text = String::from_utf8_lossy(text)
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>motd</title></head>\n<body><pre>\n{text}</pre></body>\n</html>\n"
+    )
+    .into_bytes()
+}
+
+/// Write HTTP response, and close connection
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)
+}
+
+/// Responses rendered less than the cache duration ago
+type ResponseCache = HashMap<Endpoint, (Instant, Vec<u8>)>;
+
+/// Renders responses, at most once per cache duration
+struct Renderer<F> {
+    /// Render endpoint response body
+    render: F,
+    /// Duration to reuse rendered responses for
+    cache_duration: Duration,
+    /// Rendered responses
+    cache: Mutex<ResponseCache>,
+}
+
+impl<F> Renderer<F>
+where
+    F: Fn(Endpoint) -> anyhow::Result<Vec<u8>>,
+{
+    /// Get response body of an endpoint, rendering one at a time, so that concurrent requests
+    /// wait for the same render and reuse it
+    fn body(&self, endpoint: Endpoint) -> anyhow::Result<Vec<u8>> {
+        let mut cache = self.cache.lock().unwrap();
+        if let Some((ts, body)) = cache.get(&endpoint) {
+            if ts.elapsed() < self.cache_duration {
+                return Ok(body.clone());
+            }
+        }
+        let body = (self.render)(endpoint)?;
+        let body = if endpoint == Endpoint::Html {
+            html_page(&body)
+        } else {
+            body
+        };
+        cache.insert(endpoint, (Instant::now(), body.clone()));
+        Ok(body)
+    }
+}
+
+/// Stream reader failing once a deadline is reached, however slowly data is sent
+struct DeadlineReader<'a> {
+    /// Client connection
+    stream: &'a TcpStream,
+    /// Time after which reads fail
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        (&mut self.stream).read(buf)
+    }
+}
+
+/// Read request head before a deadline, and get its request line
+fn read_request_line(stream: &TcpStream, deadline: Instant) -> anyhow::Result<String> {
+    let mut head = BufReader::new(DeadlineReader { stream, deadline }.take(MAX_HEAD_LEN));
+    let mut request_line = String::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        head.read_line(&mut line)?;
+        anyhow::ensure!(
+            line.ends_with('\n'),
+            "Request head is incomplete or larger than {MAX_HEAD_LEN} bytes"
+        );
+        if request_line.is_empty() {
+            request_line.clone_from(&line);
+        } else if line.trim_end().is_empty() {
+            return Ok(request_line);
+        }
+    }
+}
+
+/// Handle a single request
+fn handle<F>(stream: &mut TcpStream, renderer: &Renderer<F>) -> anyhow::Result<()>
+where
+    F: Fn(Endpoint) -> anyhow::Result<Vec<u8>>,
+{
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let request_line = read_request_line(stream, Instant::now() + IO_TIMEOUT)?;
+
+    let mut tokens = request_line.split_whitespace();
+    let (method, path) = (
+        tokens.next().unwrap_or_default(),
+        tokens.next().unwrap_or_default(),
+    );
+    if method != "GET" {
+        return Ok(write_response(
+            stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method not allowed\n",
+        )?);
+    }
+    let Some(endpoint) = Endpoint::from_path(path) else {
+        return Ok(write_response(
+            stream,
+            "404 Not Found",
+            "text/plain",
+            b"Not found, available endpoints are /json, /metrics and /html\n",
+        )?);
+    };
+
+    let body = match renderer.body(endpoint) {
+        Ok(body) => body,
+        Err(err) => {
+            return Ok(write_response(
+                stream,
+                "500 Internal Server Error",
+                "text/plain",
+                format!("{err:#}\n").as_bytes(),
+            )?);
+        }
+    };
+    Ok(write_response(
+        stream,
+        "200 OK",
+        endpoint.content_type(),
+        &body,
+    )?)
+}
+
+/// Serve requests, each connection in its own thread, rendering responses at most once per cache
+/// duration
+pub(crate) fn serve<F>(addr: SocketAddr, cache_duration: Duration, render: F) -> anyhow::Result<()>
+where
+    F: Fn(Endpoint) -> anyhow::Result<Vec<u8>> + Sync,
+{
+    let listener = TcpListener::bind(addr)?;
+    let renderer = Renderer {
+        render,
+        cache_duration,
+        cache: Mutex::new(ResponseCache::new()),
+    };
+    let connections = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    // Like running out of file descriptors, that may resolve once connections
+                    // are closed
+                    eprintln!("Failed to accept connection: {err}");
+                    thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+            };
+            if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                connections.fetch_sub(1, Ordering::SeqCst);
+                eprintln!("Too many connections, closing new one");
+                continue;
+            }
+            let (renderer, connections) = (&renderer, &connections);
+            scope.spawn(move || {
+                if let Err(err) = handle(&mut stream, renderer) {
+                    eprintln!("Failed to handle request: {err:#}");
+                }
+                connections.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_from_path() {
+        assert_eq!(Endpoint::from_path("/json"), Some(Endpoint::Json));
+        assert_eq!(Endpoint::from_path("/metrics?x=1"), Some(Endpoint::Metrics));
+        assert_eq!(Endpoint::from_path("/"), Some(Endpoint::Html));
+        assert_eq!(Endpoint::from_path("/yaml"), None);
+    }
+
+    #[test]
+    fn test_read_request_line() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let request = |head: Vec<u8>, deadline: Duration| {
+            let mut client = TcpStream::connect(addr).unwrap();
+            client.write_all(&head).unwrap();
+            let (server, _) = listener.accept().unwrap();
+            read_request_line(&server, Instant::now() + deadline)
+        };
+
+        assert_eq!(
+            request(
+                b"GET /json HTTP/1.1\r\nHost: localhost\r\n\r\n".to_vec(),
+                IO_TIMEOUT
+            )
+            .unwrap(),
+            "GET /json HTTP/1.1\r\n"
+        );
+        let mut large = b"GET /json HTTP/1.1\r\nX-Large: ".to_vec();
+        large.resize(MAX_HEAD_LEN as usize + 1, b'a');
+        large.extend(b"\r\n\r\n");
+        assert!(request(large, IO_TIMEOUT).is_err());
+        // Head never completed
+        assert!(request(
+            b"GET /json HTTP/1.1\r\n".to_vec(),
+            Duration::from_millis(100)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_html_page() {
+        assert_eq!(
+            String::from_utf8(html_page(b"a < b & c\n")).unwrap(),
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>motd</title></head>\n<body><pre>\na &lt; b &amp; c\n</pre></body>\n</html>\n"
+        );
+    }
+}
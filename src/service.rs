@@ -0,0 +1,222 @@
+//! Systemd service and timer units generation
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Name of generated units, without extension
+const UNIT_NAME: &str = "motd";
+
+/// Options of generated units
+pub(crate) struct ServiceOptions {
+    /// Generate user units instead of system ones
+    pub user: bool,
+    /// File to regenerate, or update cache if unset
+    pub output: Option<PathBuf>,
+    /// Regeneration interval
+    pub interval: Duration,
+    /// Directory to write units to
+    pub units_dir: PathBuf,
+}
+
+/// Default output file
+pub(crate) fn default_output_filepath(user: bool) -> anyhow::Result<PathBuf> {
+    if user {
+        let binary_name = env!("CARGO_PKG_NAME");
+        let xdg_dirs = xdg::BaseDirectories::with_prefix(binary_name)?;
+        Ok(xdg_dirs.place_cache_file("motd")?)
+    } else {
+        // Displayed by pam_motd, and writable by the sandboxed service without access to /etc
+        Ok(PathBuf::from("/run/motd.d/motd"))
+    }
+}
+
+/// Default directory of units
+pub(crate) fn default_units_dir(user: bool) -> anyhow::Result<PathBuf> {
+    if user {
+        let xdg_dirs = xdg::BaseDirectories::new()?;
+        Ok(xdg_dirs.get_config_home().join("systemd/user"))
+    } else {
+        Ok(PathBuf::from("/etc/systemd/system"))
+    }
+}
+
+/// Quote and escape command line argument for systemd `ExecStart`
+fn quote_arg(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+    if arg
+        .chars()
+        .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ';'))
+    {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg
+    }
+}
+
+/// Sandboxing directives making a directory writable, created by systemd if it is in /run
+fn writable_dir(dir: &Path) -> String {
+    match dir.strip_prefix("/run") {
+        Ok(runtime_dir) if !runtime_dir.as_os_str().is_empty() => format!(
+            "RuntimeDirectory={}\nRuntimeDirectoryPreserve=yes",
+            quote_arg(&runtime_dir.to_string_lossy())
+        ),
+        _ => format!("ReadWritePaths={}", quote_arg(&dir.to_string_lossy())),
+    }
+}
+
+/// Generate service unit, running motd once to regenerate output file or cache
+fn service_unit(opts: &ServiceOptions, exe: &Path) -> String {
+    let (args, writable) = if let Some(output) = opts.output.as_deref() {
+        (
+            format!("--output {}", quote_arg(&output.to_string_lossy())),
+            writable_dir(output.parent().unwrap_or(Path::new("/"))),
+        )
+    } else {
+        (
+            "--update-cache".to_owned(),
+            writable_dir(&Path::new("/run").join(UNIT_NAME)),
+        )
+    };
+    // Most sandboxing directives require privileges. Remaining capabilities are needed to read
+    // memory maps of all processes, to find services to restart, and to query the power state of
+    // drives, to not wake them up reading their temperature
+    let system_sandboxing = if opts.user {
+        String::new()
+    } else {
+        format!(
+            "ProtectSystem=strict
+{writable}
+ProtectHome=read-only
+PrivateTmp=true
+DevicePolicy=closed
+DeviceAllow=block-sd r
+ProtectKernelTunables=true
+ProtectKernelModules=true
+ProtectControlGroups=true
+ProtectClock=true
+ProtectHostname=true
+RestrictNamespaces=true
+RestrictSUIDSGID=true
+RestrictAddressFamilies=AF_UNIX AF_INET AF_INET6 AF_NETLINK
+CapabilityBoundingSet=CAP_SYS_PTRACE CAP_DAC_READ_SEARCH CAP_SYS_RAWIO
+"
+        )
+    };
+    format!(
+        "[Unit]
+Description=Generate MOTD banner
+
+[Service]
+Type=oneshot
+ExecStart={} {args}
+Environment=XDG_STATE_HOME=%S
+StateDirectory={UNIT_NAME}
+NoNewPrivileges=true
+LockPersonality=true
+MemoryDenyWriteExecute=true
+RestrictRealtime=true
+SystemCallArchitectures=native
+{system_sandboxing}",
+        quote_arg(&exe.to_string_lossy()),
+    )
+}
+
+/// Generate timer unit, periodically starting service
+fn timer_unit(opts: &ServiceOptions) -> String {
+    format!(
+        "[Unit]
+Description=Periodically generate MOTD banner
+
+[Timer]
+OnActiveSec=0
+OnUnitActiveSec={}s
+
+[Install]
+WantedBy=timers.target
+",
+        opts.interval.as_secs()
+    )
+}
+
+/// Write service and timer units, return their paths
+pub(crate) fn install(opts: &ServiceOptions) -> anyhow::Result<Vec<PathBuf>> {
+    let exe = std::env::current_exe()?;
+    fs::create_dir_all(&opts.units_dir)?;
+    let mut unit_filepaths = Vec::with_capacity(2);
+    for (ext, content) in [
+        ("service", service_unit(opts, &exe)),
+        ("timer", timer_unit(opts)),
+    ] {
+        let unit_filepath = opts.units_dir.join(format!("{UNIT_NAME}.{ext}"));
+        fs::write(&unit_filepath, content)?;
+        unit_filepaths.push(unit_filepath);
+    }
+    Ok(unit_filepaths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_arg() {
+        assert_eq!(quote_arg("/usr/bin/motd"), "/usr/bin/motd");
+        assert_eq!(quote_arg("/opt/my motd"), "\"/opt/my motd\"");
+        assert_eq!(quote_arg("/tmp/50%"), "/tmp/50%%");
+        assert_eq!(quote_arg("a\"b"), "\"a\\\"b\"");
+    }
+
+    #[test]
+    fn test_units() {
+        let opts = ServiceOptions {
+            user: false,
+            output: Some(PathBuf::from("/etc/motd")),
+            interval: Duration::from_mins(5),
+            units_dir: PathBuf::from("/etc/systemd/system"),
+        };
+        let service = service_unit(&opts, Path::new("/usr/bin/motd"));
+        assert!(service.contains("\nExecStart=/usr/bin/motd --output /etc/motd\n"));
+        assert!(service.contains("\nProtectSystem=strict\nReadWritePaths=/etc\n"));
+        assert!(service.contains("\nDevicePolicy=closed\nDeviceAllow=block-sd r\n"));
+        assert!(!service.contains("PrivateDevices"));
+        assert!(service.contains(
+            "\nCapabilityBoundingSet=CAP_SYS_PTRACE CAP_DAC_READ_SEARCH CAP_SYS_RAWIO\n"
+        ));
+        assert!(timer_unit(&opts).contains("\nOnUnitActiveSec=300s\n"));
+
+        let opts = ServiceOptions {
+            output: Some(default_output_filepath(false).unwrap()),
+            ..opts
+        };
+        let default_service = service_unit(&opts, Path::new("/usr/bin/motd"));
+        assert!(default_service.contains("\nExecStart=/usr/bin/motd --output /run/motd.d/motd\n"));
+        assert!(default_service.contains(
+            "\nProtectSystem=strict\nRuntimeDirectory=motd.d\nRuntimeDirectoryPreserve=yes\n"
+        ));
+        assert!(!default_service.contains("ReadWritePaths"));
+
+        let opts = ServiceOptions {
+            user: true,
+            output: Some(PathBuf::from("/home/user/.cache/motd/motd")),
+            ..opts
+        };
+        let user_service = service_unit(&opts, Path::new("/usr/bin/motd"));
+        assert!(user_service
+            .contains("\nExecStart=/usr/bin/motd --output /home/user/.cache/motd/motd\n"));
+        assert!(!user_service.contains("ProtectSystem"));
+
+        let opts = ServiceOptions {
+            user: false,
+            output: None,
+            ..opts
+        };
+        let cache_service = service_unit(&opts, Path::new("/usr/bin/motd"));
+        assert!(cache_service.contains("\nExecStart=/usr/bin/motd --update-cache\n"));
+        assert!(cache_service.contains(
+            "\nProtectSystem=strict\nRuntimeDirectory=motd\nRuntimeDirectoryPreserve=yes\n"
+        ));
+    }
+}
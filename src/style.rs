@@ -0,0 +1,502 @@
+//! Terminal color capabilities and computed styles
+
+use std::{
+    env,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+use ansi_term::{
+    Colour::{self, Red, Yellow},
+    Style,
+};
+
+/// Styles for semantic roles, used by all sections
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(from = "ThemeConfig", into = "ThemeConfig")]
+pub(crate) struct Theme {
+    /// Base palette
+    pub palette: Palette,
+    /// Suspicious values
+    pub warning: Style,
+    /// Values requiring immediate action
+    pub critical: Style,
+    /// Less important bar parts (cache memory, reserved filesystem space)
+    pub secondary: Style,
+    /// Main bar parts
+    pub bar: Style,
+    /// Section titles
+    pub title: Style,
+    /// Glyphs appended to values depending on their severity, if enabled
+    pub indicators: Option<Indicators>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::from_palette(Palette::default())
+    }
+}
+
+impl Theme {
+    /// Get default styles of a palette
+    fn from_palette(palette: Palette) -> Self {
+        let (warning, critical) = match palette {
+            Palette::Default => (Yellow.normal(), Red.normal()),
+            Palette::Deuteranopia => (Yellow.bold(), Colour::Purple.bold()),
+            Palette::Protanopia => (Yellow.bold(), Colour::Blue.bold()),
+        };
+        Self {
+            palette,
+            warning,
+            critical,
+            secondary: Style::new().dimmed(),
+            bar: Style::new(),
+            title: Style::new(),
+            indicators: None,
+        }
+    }
+
+    /// Get style for a severity
+    pub(crate) fn style(&self, severity: Severity) -> Style {
+        match severity {
+            Severity::Ok => Style::new(),
+            Severity::Warning => self.warning,
+            Severity::Critical => self.critical,
+        }
+    }
+
+    /// Get symbol appended to values of a severity, so it does not rely on colors only
+    pub(crate) fn symbol(&self, severity: Severity) -> String {
+        if let Some(indicators) = self.indicators.as_ref() {
+            let glyph = match severity {
+                Severity::Ok => &indicators.ok,
+                Severity::Warning => &indicators.warning,
+                Severity::Critical => &indicators.critical,
+            };
+            format!(" {glyph}")
+        } else {
+            self.palette.symbol(severity).to_owned()
+        }
+    }
+
+    /// Paint text with the style of a severity, followed by its symbol
+    pub(crate) fn paint(&self, s: &str, severity: Severity) -> String {
+        self.style(severity)
+            .paint(format!("{s}{}", self.symbol(severity)))
+            .to_string()
+    }
+}
+
+/// Value severity
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    /// Normal value
+    #[default]
+    Ok,
+    /// Suspicious value
+    Warning,
+    /// Value requiring immediate action
+    Critical,
+}
+
+/// Severity indicator glyphs
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct Indicators {
+    /// Glyph for normal values
+    pub ok: String,
+    /// Glyph for suspicious values
+    pub warning: String,
+    /// Glyph for values requiring immediate action
+    pub critical: String,
+}
+
+impl Default for Indicators {
+    fn default() -> Self {
+        Self {
+            ok: "✔".to_owned(),
+            warning: "⚠".to_owned(),
+            critical: "✖".to_owned(),
+        }
+    }
+}
+
+/// Theme as read from config, unset styles default to the palette ones
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct ThemeConfig {
+    /// Base palette
+    palette: Palette,
+    /// Suspicious values
+    #[serde(
+        serialize_with = "serialize_style",
+        deserialize_with = "deserialize_style"
+    )]
+    warning: Option<Style>,
+    /// Values requiring immediate action
+    #[serde(
+        serialize_with = "serialize_style",
+        deserialize_with = "deserialize_style"
+    )]
+    critical: Option<Style>,
+    /// Less important bar parts
+    #[serde(
+        serialize_with = "serialize_style",
+        deserialize_with = "deserialize_style"
+    )]
+    secondary: Option<Style>,
+    /// Main bar parts
+    #[serde(
+        serialize_with = "serialize_style",
+        deserialize_with = "deserialize_style"
+    )]
+    bar: Option<Style>,
+    /// Section titles
+    #[serde(
+        serialize_with = "serialize_style",
+        deserialize_with = "deserialize_style"
+    )]
+    title: Option<Style>,
+    /// Append severity indicator glyphs to values
+    indicators: bool,
+    /// Severity indicator glyphs
+    indicator_glyphs: Indicators,
+}
+
+impl From<ThemeConfig> for Theme {
+    fn from(cfg: ThemeConfig) -> Self {
+        let theme = Self::from_palette(cfg.palette);
+        Self {
+            palette: cfg.palette,
+            warning: cfg.warning.unwrap_or(theme.warning),
+            critical: cfg.critical.unwrap_or(theme.critical),
+            secondary: cfg.secondary.unwrap_or(theme.secondary),
+            bar: cfg.bar.unwrap_or(theme.bar),
+            title: cfg.title.unwrap_or(theme.title),
+            indicators: cfg.indicators.then_some(cfg.indicator_glyphs),
+        }
+    }
+}
+
+impl From<Theme> for ThemeConfig {
+    fn from(theme: Theme) -> Self {
+        Self {
+            palette: theme.palette,
+            warning: Some(theme.warning),
+            critical: Some(theme.critical),
+            secondary: Some(theme.secondary),
+            bar: Some(theme.bar),
+            title: Some(theme.title),
+            indicators: theme.indicators.is_some(),
+            indicator_glyphs: theme.indicators.unwrap_or_default(),
+        }
+    }
+}
+
+/// Base color palette
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Palette {
+    /// Yellow and red severity colors, green to red gradients
+    #[default]
+    Default,
+    /// Red-green color blindness safe, with severity symbols
+    Deuteranopia,
+    /// Red-blind safe, with severity symbols
+    Protanopia,
+}
+
+impl Palette {
+    /// Get severity symbol, so severity does not rely on colors only
+    fn symbol(self, severity: Severity) -> &'static str {
+        match (self, severity) {
+            (Self::Default, _) | (_, Severity::Ok) => "",
+            (Self::Deuteranopia | Self::Protanopia, Severity::Warning) => "!",
+            (Self::Deuteranopia | Self::Protanopia, Severity::Critical) => "!!",
+        }
+    }
+
+    /// Get gradient start, middle (warning threshold) and end (critical threshold) colors
+    fn gradient(self) -> [(u8, u8, u8); 3] {
+        match self {
+            Self::Default => [GRADIENT_GREEN, GRADIENT_YELLOW, GRADIENT_RED],
+            // Okabe-Ito colors: blue, orange, reddish purple
+            Self::Deuteranopia => [(0, 114, 178), (230, 159, 0), (204, 121, 167)],
+            // Okabe-Ito colors: blue, yellow, vermillion
+            Self::Protanopia => [(0, 114, 178), (240, 228, 66), (213, 94, 0)],
+        }
+    }
+}
+
+/// Global theme, set from config and replaced on reload
+static THEME: LazyLock<RwLock<Arc<Theme>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(Theme::default())));
+
+/// Set global theme
+pub(crate) fn set_theme(theme: Theme) {
+    *THEME.write().unwrap() = Arc::new(theme);
+}
+
+/// Get global theme
+pub(crate) fn theme() -> Arc<Theme> {
+    Arc::clone(&THEME.read().unwrap())
+}
+
+/// Deserialize style from a string like "bold red", "italic 208" or "underline #ff8800"
+pub(crate) fn deserialize_style<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Style>, D::Error> {
+    let s: String = serde::Deserialize::deserialize(deserializer)?;
+    parse_style(&s).map(Some).map_err(serde::de::Error::custom)
+}
+
+/// Serialize style to a string that [`deserialize_style`] accepts
+#[expect(clippy::ref_option)]
+pub(crate) fn serialize_style<S: serde::Serializer>(
+    style: &Option<Style>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match style {
+        Some(style) => serializer.serialize_str(&format_style(style)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Format style as space separated modifiers and color, "default" if there are none
+fn format_style(style: &Style) -> String {
+    let mut words: Vec<String> = [
+        (style.is_bold, "bold"),
+        (style.is_dimmed, "dim"),
+        (style.is_italic, "italic"),
+        (style.is_underline, "underline"),
+        (style.is_reverse, "reverse"),
+    ]
+    .into_iter()
+    .filter(|(set, _)| *set)
+    .map(|(_, word)| word.to_owned())
+    .collect();
+    if let Some(colour) = style.foreground {
+        words.push(match colour {
+            Colour::Black => "black".to_owned(),
+            Red => "red".to_owned(),
+            Colour::Green => "green".to_owned(),
+            Yellow => "yellow".to_owned(),
+            Colour::Blue => "blue".to_owned(),
+            Colour::Purple => "purple".to_owned(),
+            Colour::Cyan => "cyan".to_owned(),
+            Colour::White => "white".to_owned(),
+            Colour::Fixed(n) => n.to_string(),
+            Colour::RGB(r, g, b) => format!("#{r:02x}{g:02x}{b:02x}"),
+        });
+    }
+    if words.is_empty() {
+        "default".to_owned()
+    } else {
+        words.join(" ")
+    }
+}
+
+/// Parse style from space separated modifiers and color
+fn parse_style(s: &str) -> anyhow::Result<Style> {
+    let mut style = Style::new();
+    for word in s.split_whitespace() {
+        style = match word.to_ascii_lowercase().as_str() {
+            "bold" => style.bold(),
+            "dim" | "dimmed" => style.dimmed(),
+            "italic" => style.italic(),
+            "underline" => style.underline(),
+            "reverse" => style.reverse(),
+            "black" => style.fg(Colour::Black),
+            "red" => style.fg(Red),
+            "green" => style.fg(Colour::Green),
+            "yellow" => style.fg(Yellow),
+            "blue" => style.fg(Colour::Blue),
+            "purple" | "magenta" => style.fg(Colour::Purple),
+            "cyan" => style.fg(Colour::Cyan),
+            "white" => style.fg(Colour::White),
+            "default" | "normal" => style,
+            w if w.starts_with('#') && w.len() == 7 => {
+                let rgb = u32::from_str_radix(w.trim_start_matches('#'), 16)?;
+                style.fg(Colour::RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+            }
+            w => style.fg(Colour::Fixed(
+                w.parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid style {word:?}"))?,
+            )),
+        };
+    }
+    Ok(style)
+}
+
+/// Colors supported by the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColorDepth {
+    /// 8 basic colors
+    Basic,
+    /// 256 colors palette
+    Ansi256,
+    /// 24 bits RGB
+    TrueColor,
+}
+
+/// Detect terminal color support from environment
+pub(crate) fn detect_color_depth() -> ColorDepth {
+    let colorterm = env::var("COLORTERM").unwrap_or_default();
+    let term = env::var("TERM").unwrap_or_default();
+    color_depth(&colorterm, &term)
+}
+
+/// Get color depth from `COLORTERM` and `TERM` environment variable values
+fn color_depth(colorterm: &str, term: &str) -> ColorDepth {
+    if matches!(colorterm, "truecolor" | "24bit") {
+        ColorDepth::TrueColor
+    } else if term.contains("256color") {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Basic
+    }
+}
+
+/// Gradient start color
+const GRADIENT_GREEN: (u8, u8, u8) = (0, 200, 0);
+
+/// Gradient middle color, reached at warning threshold
+const GRADIENT_YELLOW: (u8, u8, u8) = (220, 200, 0);
+
+/// Gradient end color, reached at critical threshold
+const GRADIENT_RED: (u8, u8, u8) = (220, 0, 0);
+
+/// Get gradient color for a value from the theme palette (green → yellow → red by default), or `None` if terminal only supports basic colors
+pub(crate) fn gradient_color(
+    val: f32,
+    warning: f32,
+    critical: f32,
+    depth: ColorDepth,
+) -> Option<Colour> {
+    let [start, middle, end] = theme().palette.gradient();
+    let (r, g, b) = if val <= warning {
+        lerp_rgb(start, middle, val / warning)
+    } else {
+        lerp_rgb(middle, end, (val - warning) / (critical - warning))
+    };
+    match depth {
+        ColorDepth::Basic => None,
+        ColorDepth::Ansi256 => Some(Colour::Fixed(rgb_to_ansi256(r, g, b))),
+        ColorDepth::TrueColor => Some(Colour::RGB(r, g, b)),
+    }
+}
+
+/// Linear interpolation between 2 colors, position is clamped to [0, 1]
+fn lerp_rgb(from: (u8, u8, u8), to: (u8, u8, u8), pos: f32) -> (u8, u8, u8) {
+    let pos = if pos.is_nan() {
+        1.0
+    } else {
+        pos.clamp(0.0, 1.0)
+    };
+    let lerp = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * pos).round() as u8;
+    (lerp(from.0, to.0), lerp(from.1, to.1), lerp(from.2, to.2))
+}
+
+/// Get closest color of the 256 colors palette 6x6x6 cube
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |c: u8| (f32::from(c) * 5.0 / 255.0).round() as u8;
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        assert_eq!(parse_style("").unwrap(), Style::new());
+        assert_eq!(parse_style("red").unwrap(), Red.normal());
+        assert_eq!(parse_style("bold Yellow").unwrap(), Yellow.bold());
+        assert_eq!(parse_style("dim 208").unwrap(), Colour::Fixed(208).dimmed());
+        assert_eq!(
+            parse_style("underline #ff8800").unwrap(),
+            Colour::RGB(255, 136, 0).underline()
+        );
+        assert!(parse_style("blinking").is_err());
+        assert!(parse_style("256").is_err());
+    }
+
+    #[test]
+    fn test_theme_palette() {
+        let deuteranopia: Theme = toml::from_str("palette = \"deuteranopia\"").unwrap();
+        assert_eq!(deuteranopia.warning, Yellow.bold());
+        assert_eq!(
+            deuteranopia.paint("90", Severity::Warning),
+            Yellow.bold().paint("90!").to_string()
+        );
+        assert_eq!(
+            deuteranopia.paint("99", Severity::Critical),
+            Colour::Purple.bold().paint("99!!").to_string()
+        );
+
+        let protanopia: Theme =
+            toml::from_str("palette = \"protanopia\"\ncritical = \"red\"").unwrap();
+        assert_eq!(protanopia.critical, Red.normal());
+        assert_eq!(
+            protanopia.paint("99", Severity::Critical),
+            Red.paint("99!!").to_string()
+        );
+
+        assert_eq!(
+            Theme::default().paint("99", Severity::Critical),
+            Red.paint("99").to_string()
+        );
+        assert_eq!(Theme::default().paint("42", Severity::Ok), "42");
+
+        let indicators: Theme =
+            toml::from_str("indicators = true\n[indicator_glyphs]\nwarning = \"WARN\"").unwrap();
+        assert_eq!(indicators.paint("42", Severity::Ok), "42 ✔");
+        assert_eq!(
+            indicators.paint("90", Severity::Warning),
+            Yellow.paint("90 WARN").to_string()
+        );
+    }
+
+    #[test]
+    fn test_color_depth() {
+        assert_eq!(color_depth("truecolor", "xterm"), ColorDepth::TrueColor);
+        assert_eq!(color_depth("", "xterm-256color"), ColorDepth::Ansi256);
+        assert_eq!(color_depth("", "linux"), ColorDepth::Basic);
+    }
+
+    #[test]
+    fn test_gradient_color() {
+        assert_eq!(
+            gradient_color(0.0, 85.0, 95.0, ColorDepth::TrueColor),
+            Some(Colour::RGB(0, 200, 0))
+        );
+        assert_eq!(
+            gradient_color(42.5, 85.0, 95.0, ColorDepth::TrueColor),
+            Some(Colour::RGB(110, 200, 0))
+        );
+        assert_eq!(
+            gradient_color(90.0, 85.0, 95.0, ColorDepth::TrueColor),
+            Some(Colour::RGB(220, 100, 0))
+        );
+        assert_eq!(
+            gradient_color(100.0, 85.0, 95.0, ColorDepth::TrueColor),
+            Some(Colour::RGB(220, 0, 0))
+        );
+        assert_eq!(
+            gradient_color(100.0, 85.0, 95.0, ColorDepth::Ansi256),
+            Some(Colour::Fixed(160))
+        );
+        assert_eq!(gradient_color(100.0, 85.0, 95.0, ColorDepth::Basic), None);
+    }
+}
@@ -0,0 +1,52 @@
+//! Root directory that procfs and sysfs paths are resolved under, to inspect trees captured from
+//! other machines
+
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// Global root directory, set once at startup
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Set global root directory
+pub(crate) fn set_root(root: PathBuf) {
+    let _ = ROOT.set(root);
+}
+
+/// Whether a root directory is set, in which case data not read from files, like netlink dumps,
+/// would not come from the inspected tree
+pub(crate) fn is_set() -> bool {
+    ROOT.get().is_some()
+}
+
+/// Resolve absolute system path under the global root directory
+pub(crate) fn path(path: &str) -> PathBuf {
+    resolve(ROOT.get().map(PathBuf::as_path), path)
+}
+
+/// Resolve absolute system path under a root directory, if any
+fn resolve(root: Option<&Path>, path: &str) -> PathBuf {
+    match root {
+        Some(root) => root.join(path.trim_start_matches('/')),
+        None => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(resolve(None, "/proc/loadavg"), Path::new("/proc/loadavg"));
+        assert_eq!(
+            resolve(Some(Path::new("/tmp/sosreport")), "/proc/loadavg"),
+            Path::new("/tmp/sosreport/proc/loadavg")
+        );
+        assert_eq!(
+            resolve(Some(Path::new("fixtures/host/")), "/sys/class/net"),
+            Path::new("fixtures/host/sys/class/net")
+        );
+    }
+}
@@ -1,84 +1,554 @@
 use std::{
-    fmt,
-    io::BufRead,
+    cmp, fmt,
     process::{Command, Stdio},
     thread,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use ansi_term::Colour::Red;
-
-use crate::module::ModuleData;
+use crate::{
+    config::{self, SystemdScope},
+    dbus,
+    fmt::format_duration,
+    i18n::{self, Label},
+    icons,
+    module::{BuiltinModule, Module},
+    prometheus,
+    provider::Providers,
+    restart, style,
+};
 
-/// Names of failed Systemd units
-#[derive(Debug)]
+/// Failed Systemd units
+#[derive(Debug, Default, serde::Serialize)]
 pub(crate) struct FailedUnits {
-    system: Vec<String>,
-    user: Vec<String>,
+    system: Vec<FailedUnit>,
+    user: Vec<FailedUnit>,
+    /// Overall state of the system manager
+    system_summary: Option<ScopeSummary>,
+    /// Overall state of the user manager
+    user_summary: Option<ScopeSummary>,
+    /// System services using deleted shared libraries
+    system_restart: restart::Restarts,
+    /// User services using deleted shared libraries
+    user_restart: restart::Restarts,
+}
+
+/// Overall state and unit counts of a Systemd manager
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+struct ScopeSummary {
+    /// Manager state, like running or degraded
+    state: String,
+    /// Count of active units, not ignored
+    active: usize,
+    /// Count of failed units, not ignored
+    failed: usize,
+    /// Count of loaded units, not ignored
+    total: usize,
 }
 
-/// Systemd running mode
-enum SystemdMode {
-    System,
-    User,
+impl ScopeSummary {
+    /// Severity of the manager state, degraded state being ok if only ignored units failed
+    fn severity(&self) -> style::Severity {
+        match self.state.as_str() {
+            "degraded" if self.failed > 0 => style::Severity::Critical,
+            "running" | "degraded" => style::Severity::Ok,
+            _ => style::Severity::Warning,
+        }
+    }
 }
 
-/// Get name of Systemd units in failed state
-pub(crate) fn fetch() -> anyhow::Result<ModuleData> {
-    let system_fut = thread::spawn(|| fetch_mode(SystemdMode::System));
-    let user = fetch_mode(SystemdMode::User)?;
+/// Systemd unit in failed state, or stuck activating or deactivating
+#[derive(Debug, Default, PartialEq, Eq, serde::Serialize)]
+struct FailedUnit {
+    /// Unit name
+    name: String,
+    /// Unit result, like exit-code or timeout
+    result: Option<String>,
+    /// Exit status of the main process, for services
+    exit_status: Option<i32>,
+    /// Last journal line of the unit
+    last_log: Option<String>,
+    /// State the unit is stuck in if not failed, with sub state if different, like
+    /// "activating (auto-restart)"
+    stuck_state: Option<String>,
+    /// Seconds since the unit entered its current state
+    duration_secs: Option<u64>,
+}
 
-    Ok(ModuleData::Systemd(FailedUnits {
-        system: system_fut
+/// Unit, as listed by `ListUnits`
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct ListedUnit {
+    /// Unit name
+    name: String,
+    /// Active state
+    active_state: String,
+    /// Sub state
+    sub_state: String,
+    /// Unit object path
+    path: String,
+}
+
+/// Get Systemd units in failed state
+pub(crate) fn fetch(cfg: &config::SystemdConfig) -> anyhow::Result<FailedUnits> {
+    let ((system_summary, system), (user_summary, user)) = thread::scope(|scope| {
+        let system_fut = scope.spawn(|| fetch_bus(dbus::Bus::System, cfg));
+        let user = fetch_bus(dbus::Bus::Session, cfg)?;
+        let system = system_fut
             .join()
-            .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))??,
-        user,
-    }))
+            .map_err(|e| anyhow::anyhow!("Failed to join thread: {:?}", e))??;
+        anyhow::Ok((system.unzip(), user.unzip()))
+    })?;
+
+    let (mut system_restart, mut user_restart) = if cfg.needs_restart {
+        restart::scan()?
+    } else {
+        Default::default()
+    };
+    for (restarts, scope) in [
+        (&mut system_restart, SystemdScope::System),
+        (&mut user_restart, SystemdScope::User),
+    ] {
+        if cfg.scopes.contains(&scope) {
+            restarts.retain(|unit, _| !is_ignored(unit, cfg));
+        } else {
+            restarts.clear();
+        }
+    }
+
+    Ok(FailedUnits {
+        system: system.unwrap_or_default(),
+        user: user.unwrap_or_default(),
+        system_summary,
+        user_summary,
+        system_restart,
+        user_restart,
+    })
 }
 
-/// Get name of Systemd units in failed state
-#[expect(clippy::needless_pass_by_value)]
-fn fetch_mode(mode: SystemdMode) -> anyhow::Result<Vec<String>> {
-    let mut args = match mode {
-        SystemdMode::System => vec![],
-        SystemdMode::User => vec!["--user"],
+/// Check if a unit matches the ignore list
+fn is_ignored(unit: &str, cfg: &config::SystemdConfig) -> bool {
+    cfg.unit_blacklist.iter().any(|r| r.is_match(unit))
+}
+
+/// Get state of the manager on a bus, and its units in failed state with their failure reason,
+/// or activating or deactivating for at least the configured duration, if the scope is enabled
+/// and the manager is running
+fn fetch_bus(
+    bus: dbus::Bus,
+    cfg: &config::SystemdConfig,
+) -> anyhow::Result<Option<(ScopeSummary, Vec<FailedUnit>)>> {
+    let scope = match bus {
+        dbus::Bus::System => SystemdScope::System,
+        dbus::Bus::Session => SystemdScope::User,
+    };
+    if !cfg.scopes.contains(&scope) {
+        return Ok(None);
+    }
+    let mut conn = match (dbus::Connection::open(bus), scope) {
+        (Ok(conn), _) => conn,
+        // No session bus, so no user manager
+        (Err(_), SystemdScope::User) => return Ok(None),
+        (Err(err), SystemdScope::System) => return Err(err),
     };
-    args.extend(&["--no-legend", "--plain", "--failed"]);
-    let output = Command::new("systemctl")
-        .args(&args)
+    if scope == SystemdScope::User && !conn.name_has_owner(DESTINATION)? {
+        return Ok(None);
+    }
+    let stuck_secs = cfg.stuck_minutes * 60;
+    let state = get_string(&mut conn, MANAGER_PATH, MANAGER_INTERFACE, "SystemState")?;
+    let all_units: Vec<ListedUnit> = list_units(&mut conn)?
+        .into_iter()
+        .filter(|u| !is_ignored(&u.name, cfg))
+        .collect();
+    let summary = ScopeSummary {
+        state,
+        active: all_units
+            .iter()
+            .filter(|u| u.active_state == "active")
+            .count(),
+        failed: all_units
+            .iter()
+            .filter(|u| u.active_state == "failed")
+            .count(),
+        total: all_units.len(),
+    };
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut units: Vec<(ListedUnit, Option<u64>)> = Vec::new();
+    for unit in all_units {
+        let stuck = matches!(unit.active_state.as_str(), "activating" | "deactivating");
+        if unit.active_state != "failed" && !(stuck && stuck_secs > 0) {
+            continue;
+        }
+        let duration_secs = get_u64(
+            &mut conn,
+            &unit.path,
+            "org.freedesktop.systemd1.Unit",
+            "StateChangeTimestamp",
+        )
+        .ok()
+        .filter(|us| *us > 0)
+        .map(|us| now.saturating_sub(us / 1_000_000));
+        if stuck && duration_secs.is_none_or(|d| d < stuck_secs) {
+            continue;
+        }
+        units.push((unit, duration_secs));
+    }
+    units.sort_unstable();
+    let units = units
+        .into_iter()
+        .map(|(unit, duration_secs)| {
+            // Properties are missing for some unit types, and journal may not be readable
+            let interface = unit_interface(&unit.name);
+            let result = get_string(&mut conn, &unit.path, &interface, "Result").ok();
+            let exit_status = unit
+                .name
+                .ends_with(".service")
+                .then(|| get_i32(&mut conn, &unit.path, &interface, "ExecMainStatus").ok())
+                .flatten()
+                .filter(|s| *s != 0);
+            let last_log = last_log_line(bus, &unit.name);
+            let stuck_state = if unit.active_state == "failed" {
+                None
+            } else if unit.sub_state == unit.active_state {
+                Some(unit.active_state)
+            } else {
+                Some(format!("{} ({})", unit.active_state, unit.sub_state))
+            };
+            FailedUnit {
+                name: unit.name,
+                result,
+                exit_status,
+                last_log,
+                stuck_state,
+                duration_secs,
+            }
+        })
+        .collect();
+    Ok(Some((summary, units)))
+}
+
+/// Bus name of the Systemd manager
+const DESTINATION: &str = "org.freedesktop.systemd1";
+
+/// Object path of the Systemd manager
+const MANAGER_PATH: &str = "/org/freedesktop/systemd1";
+
+/// Interface of the Systemd manager
+const MANAGER_INTERFACE: &str = "org.freedesktop.systemd1.Manager";
+
+/// Signature of `ListUnits` reply: name, description, load state, active state, sub
+/// state, followed unit, unit object path, job id, job type, job object path
+const UNITS_SIGNATURE: &str = "a(ssssssouso)";
+
+/// List all loaded units
+fn list_units(conn: &mut dbus::Connection) -> anyhow::Result<Vec<ListedUnit>> {
+    let reply = conn.call(&dbus::MethodCall {
+        destination: DESTINATION,
+        path: MANAGER_PATH,
+        interface: MANAGER_INTERFACE,
+        member: "ListUnits",
+        args: &[],
+    })?;
+    anyhow::ensure!(
+        reply.signature == UNITS_SIGNATURE,
+        "Unexpected ListUnits reply signature {:?}",
+        reply.signature
+    );
+    parse_units(&mut reply.reader())
+}
+
+/// Parse units from `ListUnits` reply
+fn parse_units(reader: &mut dbus::Reader) -> anyhow::Result<Vec<ListedUnit>> {
+    reader.array(8, |r| {
+        r.align(8);
+        let name = r.string()?.to_owned();
+        // Description and load state
+        r.string()?;
+        r.string()?;
+        let active_state = r.string()?.to_owned();
+        let sub_state = r.string()?.to_owned();
+        // Followed unit
+        r.string()?;
+        let path = r.string()?.to_owned();
+        r.u32()?;
+        r.string()?;
+        r.string()?;
+        Ok(ListedUnit {
+            name,
+            active_state,
+            sub_state,
+            path,
+        })
+    })
+}
+
+/// Get D-Bus interface of unit type specific properties, like `org.freedesktop.systemd1.Service`
+fn unit_interface(name: &str) -> String {
+    let unit_type = name.rsplit('.').next().unwrap_or_default();
+    let mut chars = unit_type.chars();
+    let capitalized: String = chars
+        .next()
+        .map(|c| c.to_ascii_uppercase())
+        .into_iter()
+        .chain(chars)
+        .collect();
+    format!("org.freedesktop.systemd1.{capitalized}")
+}
+
+/// Get string property of a unit
+fn get_string(
+    conn: &mut dbus::Connection,
+    path: &str,
+    interface: &str,
+    property: &str,
+) -> anyhow::Result<String> {
+    let reply = conn.get_property(DESTINATION, path, interface, property)?;
+    let mut reader = reply.reader();
+    anyhow::ensure!(reader.signature()? == "s", "{property} is not a string");
+    Ok(reader.string()?.to_owned())
+}
+
+/// Get 64-bit unsigned integer property of a unit
+fn get_u64(
+    conn: &mut dbus::Connection,
+    path: &str,
+    interface: &str,
+    property: &str,
+) -> anyhow::Result<u64> {
+    let reply = conn.get_property(DESTINATION, path, interface, property)?;
+    let mut reader = reply.reader();
+    anyhow::ensure!(reader.signature()? == "t", "{property} is not an integer");
+    reader.u64()
+}
+
+/// Get integer property of a unit
+fn get_i32(
+    conn: &mut dbus::Connection,
+    path: &str,
+    interface: &str,
+    property: &str,
+) -> anyhow::Result<i32> {
+    let reply = conn.get_property(DESTINATION, path, interface, property)?;
+    let mut reader = reply.reader();
+    anyhow::ensure!(reader.signature()? == "i", "{property} is not an integer");
+    reader.i32()
+}
+
+/// Get last journal line of a unit
+fn last_log_line(bus: dbus::Bus, name: &str) -> Option<String> {
+    let unit_arg = match bus {
+        dbus::Bus::System => "--unit",
+        dbus::Bus::Session => "--user-unit",
+    };
+    let output = Command::new("journalctl")
+        .args([
+            unit_arg,
+            name,
+            "--lines",
+            "1",
+            "--output",
+            "cat",
+            "--no-pager",
+            "--quiet",
+        ])
         .stdin(Stdio::null())
         .stderr(Stdio::null())
-        .output()?;
-    anyhow::ensure!(output.status.success(), "systemctl failed");
-
-    let mut units = Vec::new();
-    for line in output.stdout.lines() {
-        units.push(
-            line?
-                .trim_start()
-                .split(' ')
-                .next()
-                .ok_or_else(|| anyhow::anyhow!("Failed to parse systemctl output"))?
-                .to_owned(),
-        );
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().last()?.trim();
+    (output.status.success() && !line.is_empty()).then(|| line.to_owned())
+}
+
+impl BuiltinModule for FailedUnits {
+    const LETTER: &'static str = "u";
+    const KEY: &'static str = "systemd_failed_units";
+    const TITLE: Label = Label::SystemdTitle;
+    const ICON: icons::Icon = icons::Icon::Systemd;
+
+    fn max_lines(cfg: &config::MaxLinesConfig) -> Option<usize> {
+        cfg.systemd
     }
 
-    Ok(units)
+    fn timeout(cfg: &config::TimeoutsConfig) -> Option<u64> {
+        cfg.systemd
+    }
+
+    fn fetch(cfg: &config::Config, _providers: &Providers) -> anyhow::Result<Self> {
+        fetch(&cfg.systemd)
+    }
+}
+
+impl Module for FailedUnits {
+    /// Any failed unit or manager not running requires attention
+    fn retain_alerts(&mut self) -> bool {
+        self.severity() != style::Severity::Ok
+    }
+
+    /// Any failed unit is critical, as well as degraded managers, other states than running and
+    /// services to restart are warnings
+    fn severity(&self) -> style::Severity {
+        let units_severity = if !self.system.is_empty() || !self.user.is_empty() {
+            style::Severity::Critical
+        } else if !self.system_restart.is_empty() || !self.user_restart.is_empty() {
+            style::Severity::Warning
+        } else {
+            style::Severity::Ok
+        };
+        [&self.system_summary, &self.user_summary]
+            .into_iter()
+            .flatten()
+            .map(ScopeSummary::severity)
+            .fold(units_severity, cmp::max)
+    }
+
+    /// Single line summary
+    fn compact(&self) -> String {
+        let count = self.system.len() + self.user.len();
+        let summary = format!("{count} failed units");
+        let mut summary = if count > 0 {
+            style::theme().paint(&summary, style::Severity::Critical)
+        } else {
+            summary
+        };
+        let restart_count = self.system_restart.len() + self.user_restart.len();
+        if restart_count > 0 {
+            summary.push_str(", ");
+            summary.push_str(&style::theme().paint(
+                &format!("{restart_count} services to restart"),
+                style::Severity::Warning,
+            ));
+        }
+        summary
+    }
+
+    /// Write failed unit and services to restart counts as Prometheus metrics
+    fn write_prometheus(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        prometheus::write_gauge(
+            f,
+            "systemd_failed_units",
+            "Count of Systemd units in failed state",
+        )?;
+        prometheus::write_sample(
+            f,
+            "systemd_failed_units",
+            &[("scope", "system")],
+            self.system.len(),
+        )?;
+        prometheus::write_sample(
+            f,
+            "systemd_failed_units",
+            &[("scope", "user")],
+            self.user.len(),
+        )?;
+        prometheus::write_gauge(
+            f,
+            "systemd_services_to_restart",
+            "Count of Systemd services using deleted shared libraries",
+        )?;
+        prometheus::write_sample(
+            f,
+            "systemd_services_to_restart",
+            &[("scope", "system")],
+            self.system_restart.len(),
+        )?;
+        prometheus::write_sample(
+            f,
+            "systemd_services_to_restart",
+            &[("scope", "user")],
+            self.user_restart.len(),
+        )
+    }
 }
 
 impl fmt::Display for FailedUnits {
-    /// Output names of Systemd units in failed state
+    /// Output state of managers, Systemd units in failed state, and services to restart
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = style::theme();
+        for (label, summary, units, restarts) in [
+            (
+                Label::System,
+                &self.system_summary,
+                &self.system,
+                &self.system_restart,
+            ),
+            (
+                Label::User,
+                &self.user_summary,
+                &self.user,
+                &self.user_restart,
+            ),
+        ] {
+            match summary {
+                Some(summary) => writeln!(f, "{}: {summary}", i18n::tr(label))?,
+                None if !units.is_empty() || !restarts.is_empty() => {
+                    writeln!(f, "{}:", i18n::tr(label))?;
+                }
+                None => {}
+            }
+            for u in units {
+                write!(f, "{u}")?;
+            }
+            for (unit, libs) in restarts {
+                writeln!(f, "{}", theme.warning.paint(unit))?;
+                writeln!(
+                    f,
+                    "  {}: {}",
+                    i18n::tr(Label::DeletedLibraries),
+                    libs.iter()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ScopeSummary {
+    /// Output state and unit counts
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = style::theme();
+        let failed = format!("{} {}", self.failed, i18n::tr(Label::Failed));
+        write!(
+            f,
+            "{}, {} {}, {}, {} {}",
+            theme.paint(&self.state, self.severity()),
+            self.active,
+            i18n::tr(Label::Active),
+            if self.failed > 0 {
+                theme.paint(&failed, style::Severity::Critical)
+            } else {
+                failed
+            },
+            self.total,
+            i18n::tr(Label::Units),
+        )
+    }
+}
+
+impl fmt::Display for FailedUnit {
+    /// Output unit name, with stuck state, failure reason and time, and last log line indented
+    /// below
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if !self.system.is_empty() {
-            writeln!(f, "System:")?;
+        writeln!(f, "{}", style::theme().critical.paint(&self.name))?;
+        let mut details: Vec<String> = self.result.iter().cloned().collect();
+        if let Some(status) = self.exit_status {
+            details.push(format!("{} {status}", i18n::tr(Label::ExitStatus)));
         }
-        for u in &self.system {
-            writeln!(f, "{}", Red.paint(u))?;
+        match (&self.stuck_state, self.duration_secs) {
+            (Some(state), Some(duration)) => {
+                writeln!(f, "  {state}, {}", format_duration(duration))?;
+            }
+            (Some(state), None) => writeln!(f, "  {state}")?,
+            (None, Some(duration)) => details.push(format!("{} ago", format_duration(duration))),
+            (None, None) => {}
         }
-        if !self.user.is_empty() {
-            writeln!(f, "User:")?;
+        if !details.is_empty() {
+            writeln!(f, "  {}", details.join(", "))?;
         }
-        for u in &self.user {
-            writeln!(f, "{}", Red.paint(u))?;
+        if let Some(line) = &self.last_log {
+            writeln!(f, "  {line}")?;
         }
         Ok(())
     }
@@ -88,14 +558,22 @@ impl fmt::Display for FailedUnits {
 mod tests {
     use super::*;
 
+    fn unit(name: &str) -> FailedUnit {
+        FailedUnit {
+            name: name.to_owned(),
+            ..FailedUnit::default()
+        }
+    }
+
     #[test]
     fn test_output_failed_units() {
         assert_eq!(
             format!(
                 "{}",
                 FailedUnits {
-                    system: vec!["foo.service".to_owned(), "bar.timer".to_owned()],
-                    user: vec![]
+                    system: vec![unit("foo.service"), unit("bar.timer")],
+                    user: vec![],
+                    ..FailedUnits::default()
                 }
             ),
             "System:\n\u{1b}[31mfoo.service\u{1b}[0m\n\u{1b}[31mbar.timer\u{1b}[0m\n"
@@ -105,7 +583,8 @@ mod tests {
                 "{}",
                 FailedUnits {
                     system: vec![],
-                    user: vec!["foo.service".to_owned(), "bar.timer".to_owned()]
+                    user: vec![unit("foo.service"), unit("bar.timer")],
+                    ..FailedUnits::default()
                 }
             ),
             "User:\n\u{1b}[31mfoo.service\u{1b}[0m\n\u{1b}[31mbar.timer\u{1b}[0m\n"
@@ -114,8 +593,9 @@ mod tests {
             format!(
                 "{}",
                 FailedUnits {
-                    system: vec!["foo.service".to_owned(), "bar.timer".to_owned()],
-                    user: vec!["foo2.service".to_owned()]
+                    system: vec![unit("foo.service"), unit("bar.timer")],
+                    user: vec![unit("foo2.service")],
+                    ..FailedUnits::default()
                 }
             ),
             "System:\n\u{1b}[31mfoo.service\u{1b}[0m\n\u{1b}[31mbar.timer\u{1b}[0m\nUser:\n\u{1b}[31mfoo2.service\u{1b}[0m\n"
@@ -125,10 +605,129 @@ mod tests {
                 "{}",
                 FailedUnits {
                     system: vec![],
-                    user: vec![]
+                    user: vec![],
+                    ..FailedUnits::default()
                 }
             ),
             ""
         );
+        assert_eq!(
+            format!(
+                "{}",
+                FailedUnits {
+                    system: vec![FailedUnit {
+                        name: "foo.service".to_owned(),
+                        result: Some("exit-code".to_owned()),
+                        exit_status: Some(1),
+                        last_log: Some("foo.service: Failed with result 'exit-code'.".to_owned()),
+                        stuck_state: None,
+                        duration_secs: Some(3 * 24 * 60 * 60),
+                    }],
+                    user: vec![FailedUnit {
+                        name: "bar.service".to_owned(),
+                        stuck_state: Some("activating (auto-restart)".to_owned()),
+                        duration_secs: Some(1500),
+                        ..FailedUnit::default()
+                    }],
+                    ..FailedUnits::default()
+                }
+            ),
+            "System:\n\u{1b}[31mfoo.service\u{1b}[0m\n  exit-code, exit status 1, 3d ago\n  foo.service: Failed with result 'exit-code'.\nUser:\n\u{1b}[31mbar.service\u{1b}[0m\n  activating (auto-restart), 25min\n"
+        );
+    }
+
+    #[test]
+    fn test_output_summary() {
+        let summary = |state: &str, failed| {
+            Some(ScopeSummary {
+                state: state.to_owned(),
+                active: 120,
+                failed,
+                total: 200,
+            })
+        };
+        let units = FailedUnits {
+            user: vec![unit("foo.service")],
+            system_summary: summary("running", 0),
+            user_summary: summary("degraded", 1),
+            ..FailedUnits::default()
+        };
+        assert_eq!(
+            units.to_string(),
+            "System: running, 120 active, 0 failed, 200 units\nUser: \u{1b}[31mdegraded\u{1b}[0m, 120 active, \u{1b}[31m1 failed\u{1b}[0m, 200 units\n\u{1b}[31mfoo.service\u{1b}[0m\n"
+        );
+        assert_eq!(units.severity(), style::Severity::Critical);
+        assert_eq!(
+            FailedUnits {
+                system_summary: summary("maintenance", 0),
+                ..FailedUnits::default()
+            }
+            .severity(),
+            style::Severity::Warning
+        );
+        // Only ignored units failed
+        assert_eq!(
+            FailedUnits {
+                system_summary: summary("degraded", 0),
+                ..FailedUnits::default()
+            }
+            .severity(),
+            style::Severity::Ok
+        );
+    }
+
+    #[test]
+    fn test_output_restart() {
+        let units = FailedUnits {
+            system_restart: [(
+                "nginx.service".to_owned(),
+                ["libcrypto.so.3".to_owned(), "libssl.so.3".to_owned()].into(),
+            )]
+            .into(),
+            ..FailedUnits::default()
+        };
+        assert_eq!(
+            units.to_string(),
+            "System:\n\u{1b}[33mnginx.service\u{1b}[0m\n  deleted libraries: libcrypto.so.3, libssl.so.3\n"
+        );
+        assert_eq!(units.severity(), style::Severity::Warning);
+        assert_eq!(
+            units.compact(),
+            "0 failed units, \u{1b}[33m1 services to restart\u{1b}[0m"
+        );
+    }
+
+    #[test]
+    fn test_unit_interface() {
+        assert_eq!(
+            unit_interface("foo.service"),
+            "org.freedesktop.systemd1.Service"
+        );
+        assert_eq!(
+            unit_interface("var-lib.automount"),
+            "org.freedesktop.systemd1.Automount"
+        );
+    }
+
+    #[test]
+    fn test_compact() {
+        assert_eq!(
+            FailedUnits {
+                system: vec![],
+                user: vec![],
+                ..FailedUnits::default()
+            }
+            .compact(),
+            "0 failed units"
+        );
+        assert_eq!(
+            FailedUnits {
+                system: vec![unit("foo.service")],
+                user: vec![unit("foo2.service")],
+                ..FailedUnits::default()
+            }
+            .compact(),
+            "\u{1b}[31m2 failed units\u{1b}[0m"
+        );
     }
 }
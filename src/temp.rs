@@ -1,18 +1,36 @@
 use std::{
-    cmp, fmt, fs,
+    cmp,
+    collections::BTreeMap,
+    fmt::{self, Write as _},
+    fs,
     io::prelude::*,
     net::TcpStream,
+    os::{fd::AsRawFd, unix::fs::OpenOptionsExt},
     path::{Path, PathBuf},
+    process::{Command, Stdio},
     str::FromStr,
+    sync::atomic::Ordering,
+    time::Duration,
 };
 
-use ansi_term::Colour::{Red, Yellow};
+use ansi_term::Style;
 use anyhow::Context;
 
-use crate::{config, ModuleData};
+use crate::{
+    config,
+    fmt::{display, Align, Table},
+    i18n, icons,
+    mem::{write_bar, BarPart},
+    module::{BuiltinModule, Module, TERM_COLUMNS},
+    prometheus,
+    provider::{Providers, Source},
+    style::{self, Severity},
+    sysroot,
+};
 
 /// Type of temperature sensor
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 enum SensorType {
     /// CPU sensor
     Cpu,
@@ -22,15 +40,35 @@ enum SensorType {
     OtherOrUnknown,
 }
 
+impl SensorType {
+    /// Sensor type name, for metrics labels
+    fn name(&self) -> &'static str {
+        match self {
+            SensorType::Cpu => "cpu",
+            SensorType::Drive => "drive",
+            SensorType::OtherOrUnknown => "other",
+        }
+    }
+}
+
+/// Label of the NVMe sensor reporting overall drive temperature
+const NVME_COMPOSITE_LABEL: &str = "Composite";
+
 /// Temperature data
+#[derive(serde::Serialize)]
 pub(crate) struct SensorTemp {
+    /// Name of chip or source the sensor belongs to
+    chip: String,
     /// Name of sensor
     name: String,
     /// Type of sensor
-    #[expect(dead_code)]
     sensor_type: SensorType,
-    /// Temperature value in Celcius
+    /// Temperature value in Celcius, maximum if aggregated
     temp: u32,
+    /// Minimum & average temperature values, if this aggregates several sensors
+    aggregate: Option<(u32, u32)>,
+    /// Temperature value from previous run
+    prev_temp: Option<u32>,
     /// Temperature above which component is considered anormally hot
     temp_warning: u32,
     /// Temperature above which component is considered critically hot
@@ -38,8 +76,15 @@ pub(crate) struct SensorTemp {
 }
 
 /// Deque of fetched temperature data
+#[derive(serde::Serialize)]
 pub(crate) struct HardwareTemps {
     temps: Vec<SensorTemp>,
+    /// Display unit
+    #[serde(skip)]
+    unit: config::TempUnit,
+    /// Display mode
+    #[serde(skip)]
+    display: config::TempDisplay,
 }
 
 /// Read temperature from a given hwmon sysfs file
@@ -55,23 +100,147 @@ fn read_sysfs_temp_value(filepath: &Path) -> anyhow::Result<u32> {
 /// Read string from a given sysfs file
 fn read_sysfs_string_value(filepath: &Path) -> anyhow::Result<String> {
     Ok(fs::read_to_string(filepath)
-        .with_context(|| format!("Failed to read {filepath:?}"))?
+        .with_context(|| format!("Failed to read {}", filepath.display()))?
         .trim_end()
         .to_owned())
 }
 
-/// Probe temperatures from hwmon Linux sensors
-#[expect(clippy::string_slice, clippy::too_many_lines)]
-pub(crate) fn fetch(cfg: &config::TempConfig) -> anyhow::Result<ModuleData> {
-    let mut temps = Vec::new();
+/// Probe temperatures from hwmon Linux sensors, given their input files, thermal zones, and
+/// hddtemp
+pub(crate) fn fetch<F: FnOnce() -> anyhow::Result<Vec<PathBuf>>>(
+    cfg: &config::TempConfig,
+    hwmon_inputs: F,
+) -> anyhow::Result<HardwareTemps> {
+    let mut temps = if cfg.hwmon_enabled {
+        fetch_hwmon(cfg, hwmon_inputs()?)?
+    } else {
+        Vec::new()
+    };
 
     //
-    // Hwmon sensors
+    // lm-sensors, for chips that need its configuration files to be supported
     //
 
-    let re = regex::Regex::new("temp[0-9]+_input").unwrap();
+    if temps.is_empty() && cfg.lm_sensors_fallback {
+        temps = fetch_lm_sensors(cfg).unwrap_or_default();
+    }
+
+    //
+    // Thermal zones, some boards (typically ARM) only expose temperatures this way
+    //
+
+    for sensor_temp in fetch_thermal_zones(cfg)? {
+        if !temps.iter().any(|t| t.name == sensor_temp.name) {
+            temps.push(sensor_temp);
+        }
+    }
+
+    //
+    // HDD temps
+    //
+
+    if cfg.hddtemp_enabled {
+        temps.extend(fetch_hddtemp(cfg)?);
+    }
+
+    apply_threshold_overrides(&mut temps, &cfg.thresholds);
+
+    if cfg.trend {
+        update_trend(&mut temps, cfg.trend_state_file.as_deref());
+    }
+
+    apply_renames(&mut temps, &cfg.rename);
+
+    Ok(HardwareTemps {
+        temps,
+        unit: cfg.unit,
+        display: cfg.display,
+    })
+}
+
+/// Get default trend state filepath
+fn default_trend_state_filepath() -> anyhow::Result<PathBuf> {
+    let binary_name = env!("CARGO_PKG_NAME");
+    let xdg_dirs = xdg::BaseDirectories::with_prefix(binary_name)?;
+    Ok(xdg_dirs.place_state_file("temps")?)
+}
+
+/// Set previous temperatures from state file, and store current ones
+fn update_trend(temps: &mut [SensorTemp], state_filepath: Option<&Path>) {
+    let Some(state_filepath) = state_filepath
+        .map(Path::to_path_buf)
+        .or_else(|| default_trend_state_filepath().ok())
+    else {
+        return;
+    };
+
+    if let Ok(state) = fs::read_to_string(&state_filepath) {
+        let prev_temps = parse_trend_state(&state);
+        for sensor_temp in temps.iter_mut() {
+            sensor_temp.prev_temp = prev_temps
+                .get(&(sensor_temp.chip.as_str(), sensor_temp.name.as_str()))
+                .copied();
+        }
+    }
+
+    // Failing to save state is not worth failing the whole section
+    let _ = fs::write(&state_filepath, serialize_trend_state(temps));
+}
+
+/// Parse trend state file, made of tab separated chip, sensor name and temperature lines
+fn parse_trend_state(state: &str) -> BTreeMap<(&str, &str), u32> {
+    state
+        .lines()
+        .filter_map(|l| {
+            let mut tokens = l.split('\t');
+            let chip = tokens.next()?;
+            let name = tokens.next()?;
+            let temp = tokens.next()?.parse().ok()?;
+            Some(((chip, name), temp))
+        })
+        .collect()
+}
 
-    for input_temp_filepath in walkdir::WalkDir::new("/sys/class/hwmon")
+/// Serialize trend state file
+fn serialize_trend_state(temps: &[SensorTemp]) -> String {
+    temps.iter().fold(String::new(), |mut s, t| {
+        let _ = writeln!(s, "{}\t{}\t{}", t.chip, t.name, t.temp);
+        s
+    })
+}
+
+/// Rename sensors matching user config, first matching rule wins
+fn apply_renames(temps: &mut [SensorTemp], renames: &[config::TempRename]) {
+    for sensor_temp in temps {
+        if let Some(rename) = renames
+            .iter()
+            .find(|r| r.pattern.is_match(&sensor_temp.name))
+        {
+            sensor_temp.name = rename
+                .pattern
+                .replace(&sensor_temp.name, rename.name.as_str())
+                .into_owned();
+        }
+    }
+}
+
+/// Override warning & critical temperatures of sensors matching user config
+fn apply_threshold_overrides(temps: &mut [SensorTemp], overrides: &[config::TempThresholds]) {
+    for sensor_temp in temps {
+        if let Some(thresholds) = overrides
+            .iter()
+            .find(|t| t.label.is_match(&sensor_temp.name))
+        {
+            sensor_temp.temp_warning = thresholds.warning;
+            sensor_temp.temp_critical = thresholds.critical;
+        }
+    }
+}
+
+/// List hwmon temperature input files
+pub(crate) fn scan_hwmon() -> Vec<PathBuf> {
+    let re = regex::Regex::new("temp[0-9]+_input").unwrap();
+    walkdir::WalkDir::new(sysroot::path("/sys/class/hwmon"))
         .follow_links(true)
         .min_depth(2)
         .max_depth(2)
@@ -81,7 +250,20 @@ pub(crate) fn fetch(cfg: &config::TempConfig) -> anyhow::Result<ModuleData> {
         .filter_map(Result::ok)
         .map(walkdir::DirEntry::into_path)
         .filter(|p| re.is_match(p.file_name().unwrap().to_str().unwrap()))
-    {
+        .collect()
+}
+
+/// Probe temperatures from hwmon sensors
+#[expect(clippy::string_slice)]
+fn fetch_hwmon(
+    cfg: &config::TempConfig,
+    hwmon_inputs: Vec<PathBuf>,
+) -> anyhow::Result<Vec<SensorTemp>> {
+    let mut temps = Vec::new();
+    // Core sensors to aggregate, by hwmon directory (one per CPU package)
+    let mut package_cores: BTreeMap<PathBuf, Vec<SensorTemp>> = BTreeMap::new();
+
+    for input_temp_filepath in hwmon_inputs {
         let input_temp_filepath_str = input_temp_filepath.to_str().unwrap();
         let filepath_prefix =
             input_temp_filepath_str[..input_temp_filepath_str.len() - 6].to_owned();
@@ -103,8 +285,29 @@ pub(crate) fn fetch(cfg: &config::TempConfig) -> anyhow::Result<ModuleData> {
         let name_filepath = input_temp_filepath.with_file_name("name");
         let name = read_sysfs_string_value(&name_filepath)?;
 
+        // Block device of drive sensors, to check their power state and name them
+        let mut block_device_name =
+            (name == "drivetemp").then(|| drivetemp_block_device_name(&input_temp_filepath));
+
+        // Reading temperature of a spun down drive would wake it up
+        if cfg.skip_standby_drives
+            && block_device_name
+                .as_ref()
+                .is_some_and(|n| n.as_ref().is_ok_and(|n| is_drive_in_standby(n)))
+        {
+            continue;
+        }
+
+        // Only keep composite temperature of NVMe drives, other sensors are not meaningful on their own
+        let is_nvme = name == "nvme";
+        if is_nvme && label.as_deref() != Some(NVME_COMPOSITE_LABEL) {
+            continue;
+        }
+
         // Deduce type from name
-        let sensor_type = if let Some(label) = label.as_ref() {
+        let sensor_type = if is_nvme {
+            SensorType::Drive
+        } else if let Some(label) = label.as_ref() {
             if label.starts_with("CPU ") || label.starts_with("Core ") {
                 SensorType::Cpu
             } else {
@@ -116,24 +319,20 @@ pub(crate) fn fetch(cfg: &config::TempConfig) -> anyhow::Result<ModuleData> {
             SensorType::OtherOrUnknown
         };
 
-        // Set drivetemp label
-        let sensor_name = if let Some(label) = label {
+        // Set drivetemp & NVMe label
+        let sensor_name = if is_nvme {
+            nvme_sensor_name(&input_temp_filepath)?
+        } else if let Some(label) = label {
             label
         } else if sensor_type == SensorType::Drive {
             let model_filepath = input_temp_filepath.with_file_name("device/model");
             let model = read_sysfs_string_value(&model_filepath)?;
-            let block_dirpath = input_temp_filepath.with_file_name("device/block");
-            let block_device_name = fs::read_dir(&block_dirpath)?
-                .next()
-                .ok_or_else(|| {
-                    anyhow::anyhow!("Unable to get block device from {:?}", block_dirpath)
-                })??
-                .file_name()
-                .into_string()
-                .map_err(|e| anyhow::anyhow!("Unable to decode {:?}", e))?;
+            let block_device_name = block_device_name
+                .take()
+                .unwrap_or_else(|| drivetemp_block_device_name(&input_temp_filepath))?;
             format!("{block_device_name} ({model})")
         } else {
-            name
+            name.clone()
         };
 
         // Read temp
@@ -152,66 +351,293 @@ pub(crate) fn fetch(cfg: &config::TempConfig) -> anyhow::Result<ModuleData> {
         let crit_temp_val = read_sysfs_temp_value(&crit_temp_filepath).ok();
 
         // Compute warning & critical temps
-        let warning_temp;
-        let crit_temp;
-        if let (Some(max_temp_val), Some(crit_temp_val)) = (max_temp_val, crit_temp_val) {
-            let (mut max_temp_val, crit_temp_val) = (
-                cmp::min(max_temp_val, crit_temp_val),
-                cmp::max(max_temp_val, crit_temp_val),
-            );
-            let abs_diff = crit_temp_val - max_temp_val;
-            let delta = match sensor_type {
-                SensorType::Cpu => abs_diff / 2,
-                SensorType::Drive | SensorType::OtherOrUnknown => 5,
+        let (warning_temp, crit_temp) =
+            compute_thresholds(&sensor_type, max_temp_val, crit_temp_val);
+
+        // Store temp
+        let sensor_temp = SensorTemp {
+            chip: name,
+            name: sensor_name,
+            sensor_type,
+            temp: temp_val,
+            aggregate: None,
+            prev_temp: None,
+            temp_warning: warning_temp,
+            temp_critical: crit_temp,
+        };
+        if cfg.aggregate_cpu_cores
+            && sensor_temp.sensor_type == SensorType::Cpu
+            && sensor_temp.name.starts_with("Core ")
+        {
+            if let Some(hwmon_dirpath) = input_temp_filepath.parent() {
+                package_cores
+                    .entry(hwmon_dirpath.to_path_buf())
+                    .or_default()
+                    .push(sensor_temp);
+                continue;
+            }
+        }
+        temps.push(sensor_temp);
+    }
+
+    temps.extend(
+        package_cores
+            .into_values()
+            .filter_map(|c| aggregate_cores(&c)),
+    );
+
+    Ok(temps)
+}
+
+/// Aggregate core sensors of a CPU package into a single one
+fn aggregate_cores(cores: &[SensorTemp]) -> Option<SensorTemp> {
+    let hottest = cores.iter().max_by_key(|c| c.temp)?;
+    let min = cores.iter().map(|c| c.temp).min()?;
+    let avg = cores.iter().map(|c| c.temp).sum::<u32>() / cores.len() as u32;
+    Some(SensorTemp {
+        chip: hottest.chip.clone(),
+        name: format!("Cores ({})", cores.len()),
+        sensor_type: SensorType::Cpu,
+        temp: hottest.temp,
+        aggregate: Some((min, avg)),
+        prev_temp: None,
+        temp_warning: hottest.temp_warning,
+        temp_critical: hottest.temp_critical,
+    })
+}
+
+/// Probe temperatures from lm-sensors JSON output
+fn fetch_lm_sensors(cfg: &config::TempConfig) -> anyhow::Result<Vec<SensorTemp>> {
+    let output = Command::new("sensors")
+        .arg("-j")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()?;
+    anyhow::ensure!(output.status.success(), "sensors failed");
+    parse_lm_sensors_json(&output.stdout, cfg)
+}
+
+/// Parse lm-sensors JSON output
+fn parse_lm_sensors_json(data: &[u8], cfg: &config::TempConfig) -> anyhow::Result<Vec<SensorTemp>> {
+    let mut temps = Vec::new();
+
+    // Chips and features are sorted by name, for a stable order
+    let chips: BTreeMap<String, serde_json::Value> = serde_json::from_slice(data)?;
+    for (chip_name, chip) in chips {
+        let Some(features) = chip.as_object() else {
+            continue;
+        };
+        // Chip names are like "coretemp-isa-0000" or "nvme-pci-0100"
+        let chip_driver = chip_name.split('-').next().unwrap_or_default();
+        for (label, feature) in features.iter().collect::<BTreeMap<_, _>>() {
+            let Some(subfeatures) = feature.as_object() else {
+                // "Adapter" string
+                continue;
             };
-            if let SensorType::OtherOrUnknown = sensor_type {
-                if abs_diff > 20 {
-                    max_temp_val = crit_temp_val - 20;
-                }
+            let subfeature_value = |suffix: &str| {
+                subfeatures
+                    .iter()
+                    .find(|(k, _)| k.starts_with("temp") && k.ends_with(suffix))
+                    .and_then(|(_, v)| v.as_f64())
+                    .filter(|v| *v > 0.0)
+                    .map(|v| v as u32)
+            };
+            let Some(temp_val) = subfeature_value("_input") else {
+                continue;
+            };
+            if cfg.hwmon_label_blacklist.iter().any(|r| r.is_match(label)) {
+                continue;
             }
-            warning_temp = max_temp_val - delta;
-            crit_temp = max_temp_val;
-        } else if let Some(max_temp_val) = max_temp_val {
-            let delta = match sensor_type {
-                SensorType::Cpu => 10,
-                SensorType::Drive | SensorType::OtherOrUnknown => 5,
+
+            let sensor_type = if chip_driver == "drivetemp" || chip_driver == "nvme" {
+                SensorType::Drive
+            } else if label.starts_with("CPU ") || label.starts_with("Core ") {
+                SensorType::Cpu
+            } else {
+                SensorType::OtherOrUnknown
             };
-            warning_temp = max_temp_val - delta;
-            crit_temp = max_temp_val;
+            let (warning_temp, crit_temp) = compute_thresholds(
+                &sensor_type,
+                subfeature_value("_max"),
+                subfeature_value("_crit"),
+            );
+
+            temps.push(SensorTemp {
+                chip: chip_driver.to_owned(),
+                name: label.to_owned(),
+                sensor_type,
+                temp: temp_val,
+                aggregate: None,
+                prev_temp: None,
+                temp_warning: warning_temp,
+                temp_critical: crit_temp,
+            });
+        }
+    }
+
+    Ok(temps)
+}
+
+/// Compute warning & critical temperatures from sensor max & critical values, if any
+fn compute_thresholds(
+    sensor_type: &SensorType,
+    max_temp_val: Option<u32>,
+    crit_temp_val: Option<u32>,
+) -> (u32, u32) {
+    let warning_temp;
+    let crit_temp;
+    if let (Some(max_temp_val), Some(crit_temp_val)) = (max_temp_val, crit_temp_val) {
+        let (mut max_temp_val, crit_temp_val) = (
+            cmp::min(max_temp_val, crit_temp_val),
+            cmp::max(max_temp_val, crit_temp_val),
+        );
+        let abs_diff = crit_temp_val - max_temp_val;
+        let delta = match sensor_type {
+            SensorType::Cpu => abs_diff / 2,
+            SensorType::Drive | SensorType::OtherOrUnknown => 5,
+        };
+        if let SensorType::OtherOrUnknown = sensor_type {
+            if abs_diff > 20 {
+                max_temp_val = crit_temp_val - 20;
+            }
+        }
+        warning_temp = max_temp_val - delta;
+        crit_temp = max_temp_val;
+    } else if let Some(max_temp_val) = max_temp_val {
+        let delta = match sensor_type {
+            SensorType::Cpu => 10,
+            SensorType::Drive | SensorType::OtherOrUnknown => 5,
+        };
+        warning_temp = max_temp_val - delta;
+        crit_temp = max_temp_val;
+    } else {
+        warning_temp = match sensor_type {
+            // Fallback to default value
+            SensorType::Cpu => 60,
+            SensorType::Drive | SensorType::OtherOrUnknown => 50,
+        };
+        crit_temp = match sensor_type {
+            // Fallback to default value
+            SensorType::Cpu => 75,
+            SensorType::Drive | SensorType::OtherOrUnknown => 60,
+        };
+    }
+
+    (warning_temp, crit_temp)
+}
+
+/// Probe temperatures from thermal zones that are not also exposed as hwmon sensors
+fn fetch_thermal_zones(cfg: &config::TempConfig) -> anyhow::Result<Vec<SensorTemp>> {
+    let mut temps = Vec::new();
+
+    let Ok(dir_entries) = fs::read_dir(sysroot::path("/sys/class/thermal")) else {
+        return Ok(temps);
+    };
+    let mut zone_dirpaths: Vec<PathBuf> = dir_entries
+        .filter_map(Result::ok)
+        .filter(|e| {
+            e.file_name()
+                .to_str()
+                .is_some_and(|n| n.starts_with("thermal_zone"))
+        })
+        .map(|e| e.path())
+        .collect();
+    zone_dirpaths.sort();
+
+    for zone_dirpath in zone_dirpaths {
+        // Zones registered as hwmon devices have a hwmon subdirectory, and have already been probed
+        if fs::read_dir(&zone_dirpath)?
+            .filter_map(Result::ok)
+            .any(|e| {
+                e.file_name()
+                    .to_str()
+                    .is_some_and(|n| n.starts_with("hwmon"))
+            })
+        {
+            continue;
+        }
+
+        let Ok(zone_type) = read_sysfs_string_value(&zone_dirpath.join("type")) else {
+            continue;
+        };
+        if cfg
+            .hwmon_label_blacklist
+            .iter()
+            .any(|r| r.is_match(&zone_type))
+        {
+            continue;
+        }
+        let Ok(temp_val) = read_sysfs_temp_value(&zone_dirpath.join("temp")) else {
+            continue;
+        };
+
+        let sensor_type = if zone_type.contains("cpu") || zone_type.contains("soc") {
+            SensorType::Cpu
         } else {
-            warning_temp = match sensor_type {
-                // Fallback to default value
-                SensorType::Cpu => 60,
-                SensorType::Drive | SensorType::OtherOrUnknown => 50,
+            SensorType::OtherOrUnknown
+        };
+
+        // Use trip points as max & critical temps
+        let mut max_temp_val = None;
+        let mut crit_temp_val = None;
+        for trip_idx in 0.. {
+            let Ok(trip_type) =
+                read_sysfs_string_value(&zone_dirpath.join(format!("trip_point_{trip_idx}_type")))
+            else {
+                break;
             };
-            crit_temp = match sensor_type {
-                // Fallback to default value
-                SensorType::Cpu => 75,
-                SensorType::Drive | SensorType::OtherOrUnknown => 60,
+            let Ok(trip_temp) =
+                read_sysfs_temp_value(&zone_dirpath.join(format!("trip_point_{trip_idx}_temp")))
+            else {
+                continue;
             };
+            match trip_type.as_str() {
+                "critical" => crit_temp_val = Some(trip_temp),
+                "hot" | "passive" => {
+                    max_temp_val = Some(max_temp_val.map_or(trip_temp, |v: u32| v.min(trip_temp)));
+                }
+                _ => {}
+            }
         }
+        let (warning_temp, crit_temp) = match (max_temp_val, crit_temp_val) {
+            // Only critical trip point, use it as max
+            (None, Some(crit_temp_val)) => {
+                compute_thresholds(&sensor_type, Some(crit_temp_val), None)
+            }
+            (max_temp_val, crit_temp_val) => {
+                compute_thresholds(&sensor_type, max_temp_val, crit_temp_val)
+            }
+        };
 
-        // Store temp
-        let sensor_temp = SensorTemp {
-            name: sensor_name,
+        temps.push(SensorTemp {
+            chip: "thermal".to_owned(),
+            name: zone_type,
             sensor_type,
             temp: temp_val,
+            aggregate: None,
+            prev_temp: None,
             temp_warning: warning_temp,
             temp_critical: crit_temp,
-        };
-        temps.push(sensor_temp);
+        });
     }
 
-    //
-    // HDD temps
-    //
+    Ok(temps)
+}
+
+/// Fetch drive temperatures from hddtemp daemon
+fn fetch_hddtemp(cfg: &config::TempConfig) -> anyhow::Result<Vec<SensorTemp>> {
+    let mut temps = Vec::new();
 
     // Connect
-    if let Ok(mut stream) = TcpStream::connect("127.0.0.1:7634") {
-        // TODO port const
-        // Read
+    let timeout = Duration::from_millis(cfg.hddtemp_timeout_ms);
+    if let Ok(mut stream) = TcpStream::connect_timeout(&cfg.hddtemp_addr, timeout) {
+        stream.set_read_timeout(Some(timeout))?;
+
+        // Read, something else than hddtemp may be listening on that port, so don't fail on timeout
         let mut data = String::new();
-        stream.read_to_string(&mut data)?;
+        if stream.read_to_string(&mut data).is_err() {
+            return Ok(temps);
+        }
 
         // Parse
         let drives_data: Vec<&str> = data.split('|').collect();
@@ -224,9 +650,12 @@ pub(crate) fn fetch(cfg: &config::TempConfig) -> anyhow::Result<ModuleData> {
 
             // Store temp
             let sensor_temp = SensorTemp {
+                chip: "hddtemp".to_owned(),
                 name: format!("{} ({})", drive_path.to_str().unwrap(), pretty_name),
                 sensor_type: SensorType::Drive,
                 temp,
+                aggregate: None,
+                prev_temp: None,
                 temp_warning: 45,
                 temp_critical: 55,
             };
@@ -234,7 +663,94 @@ pub(crate) fn fetch(cfg: &config::TempConfig) -> anyhow::Result<ModuleData> {
         }
     }
 
-    Ok(ModuleData::HardwareTemps(HardwareTemps { temps }))
+    Ok(temps)
+}
+
+/// Get block device name (ie. sda) of a drivetemp sensor
+fn drivetemp_block_device_name(input_temp_filepath: &Path) -> anyhow::Result<String> {
+    let block_dirpath = input_temp_filepath.with_file_name("device/block");
+    fs::read_dir(&block_dirpath)?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Unable to get block device from {:?}", block_dirpath))??
+        .file_name()
+        .into_string()
+        .map_err(|e| anyhow::anyhow!("Unable to decode {:?}", e))
+}
+
+/// `HDIO_DRIVE_CMD` ioctl request, from linux/hdreg.h
+const HDIO_DRIVE_CMD: libc::Ioctl = 0x031f;
+
+/// ATA CHECK POWER MODE command
+const ATA_CHECK_POWER_MODE: u8 = 0xe5;
+
+/// Check if a drive may be in standby (spun down), the same way `hdparm -C` does.
+fn is_drive_in_standby(block_device_name: &str) -> bool {
+    let runtime_status = read_sysfs_string_value(
+        &sysroot::path("/sys/block")
+            .join(block_device_name)
+            .join("device/power/runtime_status"),
+    )
+    .ok();
+    is_standby_power_state(runtime_status.as_deref(), || {
+        drive_power_mode(block_device_name)
+    })
+}
+
+/// Check if a drive may be in standby, from its runtime power management status, and its ATA power
+/// mode if it is not suspended.
+/// If power mode can not be queried (ie. without root or `CAP_SYS_RAWIO`), the drive is only
+/// assumed to be active if runtime power management reports it so.
+fn is_standby_power_state(
+    runtime_status: Option<&str>,
+    power_mode: impl FnOnce() -> Option<u8>,
+) -> bool {
+    if runtime_status == Some("suspended") {
+        return true;
+    }
+    match power_mode() {
+        // Sector count register is 0x00 for standby
+        Some(mode) => mode == 0x00,
+        None => runtime_status != Some("active"),
+    }
+}
+
+/// Query ATA power mode of a drive
+fn drive_power_mode(block_device_name: &str) -> Option<u8> {
+    let file = fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(Path::new("/dev").join(block_device_name))
+        .ok()?;
+    let mut args: [u8; 4] = [ATA_CHECK_POWER_MODE, 0, 0, 0];
+    // SAFETY: libc call, args buffer is valid for HDIO_DRIVE_CMD
+    let rc = unsafe { libc::ioctl(file.as_raw_fd(), HDIO_DRIVE_CMD, args.as_mut_ptr()) };
+    (rc == 0).then_some(args[2])
+}
+
+/// Build NVMe sensor name from first namespace block device and controller model
+fn nvme_sensor_name(input_temp_filepath: &Path) -> anyhow::Result<String> {
+    let ctrl_dirpath = input_temp_filepath.with_file_name("device");
+    let model = read_sysfs_string_value(&ctrl_dirpath.join("model"))?;
+    let ctrl_name = fs::canonicalize(&ctrl_dirpath)?
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| anyhow::anyhow!("Unable to get NVMe controller name"))?;
+    // Namespaces are named like nvme0n1, if there is none, fallback to controller name
+    let block_device_name = fs::read_dir(&ctrl_dirpath)?
+        .filter_map(Result::ok)
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|n| is_nvme_namespace_name(n, &ctrl_name))
+        .min()
+        .unwrap_or(ctrl_name);
+    Ok(format!("{block_device_name} ({model})"))
+}
+
+/// Check if a name is a namespace of NVMe controller
+fn is_nvme_namespace_name(name: &str, ctrl_name: &str) -> bool {
+    name.strip_prefix(ctrl_name)
+        .and_then(|s| s.strip_prefix('n'))
+        .is_some_and(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()))
 }
 
 /// Normalize a drive device path by making it absolute and following links
@@ -257,33 +773,272 @@ fn normalize_drive_path(path: &Path) -> anyhow::Result<PathBuf> {
 }
 
 /// Colorize a string for terminal display according to temperature level
-fn colorize_from_temp(string: String, temp: u32, temp_warning: u32, temp_critical: u32) -> String {
+fn colorize_from_temp(string: &str, temp: u32, temp_warning: u32, temp_critical: u32) -> String {
+    style::theme().paint(
+        string,
+        severity_from_temp(temp, temp_warning, temp_critical),
+    )
+}
+
+/// Convert a Celsius temperature to display unit, and get unit symbol
+fn convert_temp(temp: u32, unit: config::TempUnit) -> (u32, &'static str) {
+    match unit {
+        config::TempUnit::Celsius => (temp, "°C"),
+        config::TempUnit::Fahrenheit => ((temp * 9 + 2) / 5 + 32, "°F"),
+    }
+}
+
+/// Format temperature trend since previous run, in display unit
+fn format_trend(prev_temp: u32, temp: u32, unit: config::TempUnit) -> String {
+    let delta = i64::from(convert_temp(temp, unit).0) - i64::from(convert_temp(prev_temp, unit).0);
+    match delta.cmp(&0) {
+        cmp::Ordering::Greater => format!(" ↑ +{delta}"),
+        cmp::Ordering::Less => format!(" ↓ {delta}"),
+        cmp::Ordering::Equal => " →".to_owned(),
+    }
+}
+
+/// Indentation of sensors under their chip name
+const SENSOR_INDENT: &str = "  ";
+
+/// Ambient temperature, lower bound of temperature bars
+const AMBIENT_TEMP: u32 = 20;
+
+/// Minimum temperature bar length
+const MIN_TEMP_BAR_LEN: usize = 12;
+
+impl BuiltinModule for HardwareTemps {
+    const LETTER: &'static str = "t";
+    const KEY: &'static str = "temperatures";
+    const TITLE: i18n::Label = i18n::Label::TempTitle;
+    const ICON: icons::Icon = icons::Icon::Thermometer;
+    const SOURCES: &'static [Source] = &[Source::Hwmon];
+
+    fn max_lines(cfg: &config::MaxLinesConfig) -> Option<usize> {
+        cfg.temp
+    }
+
+    fn timeout(cfg: &config::TimeoutsConfig) -> Option<u64> {
+        cfg.temp
+    }
+
+    fn fetch(cfg: &config::Config, providers: &Providers) -> anyhow::Result<Self> {
+        fetch(&cfg.temp, || providers.hwmon())
+    }
+}
+
+impl Module for HardwareTemps {
+    /// Only keep sensors above their warning temperature
+    fn retain_alerts(&mut self) -> bool {
+        self.temps.retain(|t| t.temp >= t.temp_warning);
+        !self.temps.is_empty()
+    }
+
+    /// Highest severity of sensor temperatures
+    fn severity(&self) -> Severity {
+        self.temps
+            .iter()
+            .map(|t| severity_from_temp(t.temp, t.temp_warning, t.temp_critical))
+            .max()
+            .unwrap_or(Severity::Ok)
+    }
+
+    /// Single line summary, with the hottest sensor temperature
+    fn compact(&self) -> String {
+        let Some(hottest) = self.temps.iter().max_by_key(|t| t.temp) else {
+            return String::new();
+        };
+        let (temp, unit) = convert_temp(hottest.temp, self.unit);
+        format!(
+            "temp {}",
+            colorize_from_temp(
+                &format!("{temp} {unit}"),
+                hottest.temp,
+                hottest.temp_warning,
+                hottest.temp_critical
+            )
+        )
+    }
+
+    /// Write temperatures as Prometheus metrics
+    fn write_prometheus(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        let metrics: [prometheus::GaugeDef<SensorTemp, u32>; 3] = [
+            ("temperature_celsius", "Sensor temperature", |t| t.temp),
+            (
+                "temperature_warning_celsius",
+                "Sensor temperature above which component is anormally hot",
+                |t| t.temp_warning,
+            ),
+            (
+                "temperature_critical_celsius",
+                "Sensor temperature above which component is critically hot",
+                |t| t.temp_critical,
+            ),
+        ];
+        for (name, help, value) in metrics {
+            prometheus::write_gauge(f, name, help)?;
+            for sensor_temp in &self.temps {
+                prometheus::write_sample(
+                    f,
+                    name,
+                    &[
+                        ("chip", &sensor_temp.chip),
+                        ("sensor", &sensor_temp.name),
+                        ("type", sensor_temp.sensor_type.name()),
+                    ],
+                    value(sensor_temp),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl HardwareTemps {
+    /// Group sensors by chip, in order of first appearance
+    fn by_chip(&self) -> Vec<(&str, Vec<&SensorTemp>)> {
+        let mut groups: Vec<(&str, Vec<&SensorTemp>)> = Vec::new();
+        for sensor_temp in &self.temps {
+            if let Some((_, group)) = groups.iter_mut().find(|(c, _)| *c == sensor_temp.chip) {
+                group.push(sensor_temp);
+            } else {
+                groups.push((&sensor_temp.chip, vec![sensor_temp]));
+            }
+        }
+        groups
+    }
+
+    /// Format temperature value(s) of a sensor in display unit
+    fn format_value(&self, sensor_temp: &SensorTemp) -> String {
+        let (temp, unit) = convert_temp(sensor_temp.temp, self.unit);
+        if let Some((min, avg)) = sensor_temp.aggregate {
+            let (min, _) = convert_temp(min, self.unit);
+            let (avg, _) = convert_temp(avg, self.unit);
+            format!("{min}/{avg}/{temp} {unit} (min/avg/max)")
+        } else {
+            format!("{temp} {unit}")
+        }
+    }
+}
+
+/// Generate a bar representing temperature between ambient and critical temperatures
+fn get_temp_bar(sensor_temp: &SensorTemp, length: usize, style: Style) -> String {
+    let range = sensor_temp
+        .temp_critical
+        .saturating_sub(AMBIENT_TEMP)
+        .max(1);
+    let prct =
+        (100.0 * sensor_temp.temp.saturating_sub(AMBIENT_TEMP) as f32 / range as f32).min(100.0);
+    let mut bar = String::new();
+    // Writing to a String can not fail
+    let _ = write_bar(
+        &[
+            BarPart {
+                label: vec![],
+                prct,
+                text_style: style,
+                fill_style: style,
+                bar_char: display().bar.fill,
+            },
+            BarPart {
+                label: vec![],
+                prct: 100.0 - prct,
+                text_style: style,
+                fill_style: style,
+                bar_char: display().bar.empty,
+            },
+        ],
+        length,
+        &mut bar,
+    );
+    bar
+}
+
+/// Get severity of a temperature level
+fn severity_from_temp(temp: u32, temp_warning: u32, temp_critical: u32) -> Severity {
     if temp >= temp_critical {
-        Red.paint(string).to_string()
+        Severity::Critical
     } else if temp >= temp_warning {
-        Yellow.paint(string).to_string()
+        Severity::Warning
     } else {
-        string
+        Severity::Ok
     }
 }
 
 impl fmt::Display for HardwareTemps {
-    /// Output all temperatures
+    /// Output all temperatures, grouped by chip
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let max_name_len = self.temps.iter().map(|x| x.name.len()).max();
-        for sensor_temp in &self.temps {
-            let pad = " ".repeat(max_name_len.unwrap() - sensor_temp.name.len());
-            let line = format!("{}: {}{} °C", sensor_temp.name, pad, sensor_temp.temp);
-            writeln!(
-                f,
-                "{}",
-                colorize_from_temp(
-                    line,
-                    sensor_temp.temp,
-                    sensor_temp.temp_warning,
-                    sensor_temp.temp_critical,
-                )
-            )?;
+        let by_chip = self.by_chip();
+        let mut table = Table::new(&[Align::Left, Align::Left]);
+        for sensor_temp in by_chip.iter().flat_map(|(_, t)| t) {
+            table.push(vec![
+                format!("{}:", sensor_temp.name),
+                self.format_value(sensor_temp),
+            ]);
+        }
+        if table.is_empty() {
+            return Ok(());
+        }
+        let term_columns = TERM_COLUMNS.load(Ordering::SeqCst);
+        // Shrink names so that bars have their minimum length, with a space before and after
+        let widths = match self.display {
+            config::TempDisplay::Text => table.widths(),
+            config::TempDisplay::Bars => table.fit_widths(
+                0,
+                term_columns.saturating_sub(SENSOR_INDENT.len() + MIN_TEMP_BAR_LEN + 2),
+                0,
+            ),
+        };
+        let bar_len = cmp::max(
+            term_columns.saturating_sub(SENSOR_INDENT.len() + widths.iter().sum::<usize>() + 2),
+            MIN_TEMP_BAR_LEN,
+        );
+        let mut rows = table.padded_rows(&widths).into_iter();
+
+        let icon = icons::prefix(icons::Icon::Thermometer);
+        for (chip, sensor_temps) in by_chip {
+            writeln!(f, "{icon}{chip}:")?;
+            for sensor_temp in sensor_temps {
+                let Some(row) = rows.next() else {
+                    unreachable!();
+                };
+                let name = &row[0];
+                let value = self.format_value(sensor_temp);
+                let trend = sensor_temp
+                    .prev_temp
+                    .map(|prev_temp| format_trend(prev_temp, sensor_temp.temp, self.unit))
+                    .unwrap_or_default();
+                match self.display {
+                    config::TempDisplay::Text => {
+                        let line = format!("{name} {value}");
+                        writeln!(
+                            f,
+                            "{SENSOR_INDENT}{}{trend}",
+                            colorize_from_temp(
+                                &line,
+                                sensor_temp.temp,
+                                sensor_temp.temp_warning,
+                                sensor_temp.temp_critical,
+                            )
+                        )?;
+                    }
+                    config::TempDisplay::Bars => {
+                        let severity = severity_from_temp(
+                            sensor_temp.temp,
+                            sensor_temp.temp_warning,
+                            sensor_temp.temp_critical,
+                        );
+                        let style = style::theme().style(severity);
+                        writeln!(
+                            f,
+                            "{SENSOR_INDENT}{}{} {}{trend}",
+                            style.paint(format!("{name} ")),
+                            get_temp_bar(sensor_temp, bar_len, style),
+                            style::theme().paint(&value, severity)
+                        )?;
+                    }
+                }
+            }
         }
 
         Ok(())
@@ -292,8 +1047,24 @@ impl fmt::Display for HardwareTemps {
 
 #[cfg(test)]
 mod tests {
+    use serial_test::serial;
+
     use super::*;
 
+    /// Build CPU sensor, with default thresholds
+    fn sensor(chip: &str, name: &str, temp: u32) -> SensorTemp {
+        SensorTemp {
+            chip: chip.to_owned(),
+            name: name.to_owned(),
+            sensor_type: SensorType::Cpu,
+            temp,
+            aggregate: None,
+            prev_temp: None,
+            temp_warning: 70,
+            temp_critical: 80,
+        }
+    }
+
     #[test]
     fn test_output_temps() {
         assert_eq!(
@@ -301,56 +1072,284 @@ mod tests {
                 "{}",
                 HardwareTemps {
                     temps: vec![
+                        sensor("coretemp", "sensor1", 95),
                         SensorTemp {
-                            name: "sensor1".to_owned(),
-                            sensor_type: SensorType::Cpu,
-                            temp: 95,
-                            temp_warning: 70,
-                            temp_critical: 80
-                        },
-                        SensorTemp {
-                            name: "sensor222222222".to_owned(),
                             sensor_type: SensorType::Drive,
-                            temp: 40,
-                            temp_warning: 70,
-                            temp_critical: 80
+                            ..sensor("drivetemp", "sensor222222222", 40)
                         },
                         SensorTemp {
-                            name: "sensor333".to_owned(),
                             sensor_type: SensorType::OtherOrUnknown,
-                            temp: 50,
                             temp_warning: 45,
-                            temp_critical: 60
+                            temp_critical: 60,
+                            ..sensor("coretemp", "sensor333", 50)
                         }
-                    ]
+                    ],
+                    unit: config::TempUnit::Celsius,
+                    display: config::TempDisplay::Text
                 }
             ),
-            "\u{1b}[31msensor1:         95 °C\u{1b}[0m\nsensor222222222: 40 °C\n\u{1b}[33msensor333:       50 °C\u{1b}[0m\n"
+            "coretemp:\n  \u{1b}[31msensor1:         95 °C\u{1b}[0m\n  \u{1b}[33msensor333:       50 °C\u{1b}[0m\ndrivetemp:\n  sensor222222222: 40 °C\n"
         );
     }
 
     #[test]
     fn test_colorize_from_temp() {
-        assert_eq!(colorize_from_temp("hey".to_owned(), 59, 60, 75), "hey");
+        assert_eq!(colorize_from_temp("hey", 59, 60, 75), "hey");
         assert_eq!(
-            colorize_from_temp("hey".to_owned(), 60, 60, 75),
+            colorize_from_temp("hey", 60, 60, 75),
             "\u{1b}[33mhey\u{1b}[0m"
         );
         assert_eq!(
-            colorize_from_temp("hey".to_owned(), 60, 60, 75),
+            colorize_from_temp("hey", 60, 60, 75),
             "\u{1b}[33mhey\u{1b}[0m"
         );
         assert_eq!(
-            colorize_from_temp("hey".to_owned(), 74, 60, 75),
+            colorize_from_temp("hey", 74, 60, 75),
             "\u{1b}[33mhey\u{1b}[0m"
         );
         assert_eq!(
-            colorize_from_temp("hey".to_owned(), 75, 60, 75),
+            colorize_from_temp("hey", 75, 60, 75),
             "\u{1b}[31mhey\u{1b}[0m"
         );
         assert_eq!(
-            colorize_from_temp("hey".to_owned(), 76, 60, 75),
+            colorize_from_temp("hey", 76, 60, 75),
             "\u{1b}[31mhey\u{1b}[0m"
         );
     }
+
+    #[test]
+    fn test_is_nvme_namespace_name() {
+        assert!(is_nvme_namespace_name("nvme0n1", "nvme0"));
+        assert!(is_nvme_namespace_name("nvme1n12", "nvme1"));
+        assert!(!is_nvme_namespace_name("nvme1n1", "nvme0"));
+        assert!(!is_nvme_namespace_name("nvme0n", "nvme0"));
+        assert!(!is_nvme_namespace_name("nvme0c0n1", "nvme0"));
+        assert!(!is_nvme_namespace_name("hwmon2", "nvme0"));
+    }
+
+    #[test]
+    fn test_is_standby_power_state() {
+        assert!(is_standby_power_state(Some("suspended"), || unreachable!()));
+        assert!(is_standby_power_state(Some("active"), || Some(0x00)));
+        assert!(!is_standby_power_state(Some("active"), || Some(0xff)));
+        assert!(!is_standby_power_state(None, || Some(0xff)));
+        // Power mode can not be queried without privileges
+        assert!(!is_standby_power_state(Some("active"), || None));
+        assert!(is_standby_power_state(Some("unsupported"), || None));
+        assert!(is_standby_power_state(None, || None));
+    }
+
+    #[test]
+    fn test_compute_thresholds() {
+        assert_eq!(
+            compute_thresholds(&SensorType::Cpu, Some(80), Some(100)),
+            (70, 80)
+        );
+        assert_eq!(
+            compute_thresholds(&SensorType::OtherOrUnknown, Some(100), Some(70)),
+            (75, 80)
+        );
+        assert_eq!(
+            compute_thresholds(&SensorType::OtherOrUnknown, Some(50), Some(105)),
+            (80, 85)
+        );
+        assert_eq!(
+            compute_thresholds(&SensorType::Drive, Some(70), None),
+            (65, 70)
+        );
+        assert_eq!(compute_thresholds(&SensorType::Cpu, None, None), (60, 75));
+    }
+
+    #[test]
+    fn test_parse_lm_sensors_json() {
+        let data = br#"{
+   "coretemp-isa-0000":{
+      "Adapter": "ISA adapter",
+      "Package id 0":{
+         "temp1_input": 45.000,
+         "temp1_max": 80.000,
+         "temp1_crit": 100.000,
+         "temp1_crit_alarm": 0.000
+      },
+      "Core 0":{
+         "temp2_input": 42.000,
+         "temp2_max": 80.000,
+         "temp2_crit": 100.000
+      }
+   },
+   "acpitz-acpi-0":{
+      "Adapter": "ACPI interface",
+      "temp1":{
+         "temp1_input": 27.800
+      }
+   },
+   "nct6775-isa-0290":{
+      "Adapter": "ISA adapter",
+      "CPUTIN":{
+         "temp2_input": 38.500
+      },
+      "fan1":{
+         "fan1_input": 1200.000
+      }
+   }
+}"#;
+        let cfg = config::TempConfig {
+            hwmon_label_blacklist: vec![regex::Regex::new("^CPUTIN$").unwrap()],
+            ..config::TempConfig::default()
+        };
+        let temps = parse_lm_sensors_json(data, &cfg).unwrap();
+        assert_eq!(
+            temps
+                .iter()
+                .map(|t| (
+                    t.name.as_str(),
+                    &t.sensor_type,
+                    t.temp,
+                    t.temp_warning,
+                    t.temp_critical
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("temp1", &SensorType::OtherOrUnknown, 27, 50, 60),
+                ("Core 0", &SensorType::Cpu, 42, 70, 80),
+                ("Package id 0", &SensorType::OtherOrUnknown, 45, 75, 80),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_cores() {
+        let core = |temp, temp_warning| SensorTemp {
+            temp_warning,
+            temp_critical: 90,
+            ..sensor("coretemp", "Core 0", temp)
+        };
+        let aggregated = aggregate_cores(&[core(40, 80), core(52, 70), core(45, 80)]).unwrap();
+        assert_eq!(
+            format!(
+                "{}",
+                HardwareTemps {
+                    temps: vec![aggregated],
+                    unit: config::TempUnit::Celsius,
+                    display: config::TempDisplay::Text
+                }
+            ),
+            "coretemp:\n  Cores (3): 40/45/52 °C (min/avg/max)\n"
+        );
+        assert!(aggregate_cores(&[]).is_none());
+    }
+
+    #[test]
+    fn test_convert_temp() {
+        assert_eq!(convert_temp(40, config::TempUnit::Celsius), (40, "°C"));
+        assert_eq!(convert_temp(0, config::TempUnit::Fahrenheit), (32, "°F"));
+        assert_eq!(convert_temp(37, config::TempUnit::Fahrenheit), (99, "°F"));
+        assert_eq!(convert_temp(100, config::TempUnit::Fahrenheit), (212, "°F"));
+    }
+
+    #[test]
+    fn test_apply_threshold_overrides() {
+        let mut temps = vec![
+            sensor("nvme", "nvme0n1 (Samsung SSD 980)", 50),
+            sensor("nvme", "sda (WDC)", 50),
+        ];
+        apply_threshold_overrides(
+            &mut temps,
+            &[
+                config::TempThresholds {
+                    label: regex::Regex::new("^nvme").unwrap(),
+                    warning: 60,
+                    critical: 70,
+                },
+                config::TempThresholds {
+                    label: regex::Regex::new("Samsung").unwrap(),
+                    warning: 1,
+                    critical: 2,
+                },
+            ],
+        );
+        assert_eq!(
+            temps
+                .iter()
+                .map(|t| (t.temp_warning, t.temp_critical))
+                .collect::<Vec<_>>(),
+            vec![(60, 70), (70, 80)]
+        );
+    }
+
+    #[test]
+    fn test_trend() {
+        let state = serialize_trend_state(&[
+            sensor("coretemp", "Core 0", 40),
+            sensor("coretemp", "Core 1", 45),
+        ]);
+        assert_eq!(state, "coretemp\tCore 0\t40\ncoretemp\tCore 1\t45\n");
+        let prev_temps = parse_trend_state(&state);
+        assert_eq!(prev_temps.get(&("coretemp", "Core 1")), Some(&45));
+        assert_eq!(prev_temps.get(&("coretemp", "Core 2")), None);
+
+        assert_eq!(format_trend(40, 43, config::TempUnit::Celsius), " ↑ +3");
+        assert_eq!(format_trend(40, 38, config::TempUnit::Fahrenheit), " ↓ -4");
+        assert_eq!(format_trend(40, 40, config::TempUnit::Celsius), " →");
+
+        let mut temps = vec![sensor("coretemp", "Core 0", 43)];
+        temps[0].prev_temp = Some(40);
+        assert_eq!(
+            format!(
+                "{}",
+                HardwareTemps {
+                    temps,
+                    unit: config::TempUnit::Celsius,
+                    display: config::TempDisplay::Text
+                }
+            ),
+            "coretemp:\n  Core 0: 43 °C ↑ +3\n"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_output_temps_bars() {
+        TERM_COLUMNS.store(40, Ordering::SeqCst);
+        assert_eq!(
+            format!(
+                "{}",
+                HardwareTemps {
+                    temps: vec![
+                        sensor("coretemp", "Core 0", 50),
+                        sensor("coretemp", "Core 10", 75)
+                    ],
+                    unit: config::TempUnit::Celsius,
+                    display: config::TempDisplay::Bars
+                }
+            ),
+            "coretemp:\n  Core 0:  ▕██████████▌          ▏ 50 °C\n  \u{1b}[33mCore 10: \u{1b}[0m▕\u{1b}[33m█████████\u{1b}[0m\u{1b}[33m██████████\u{1b}[0m\u{1b}[33m▎\u{1b}[0m\u{1b}[33m \u{1b}[0m▏ \u{1b}[33m75 °C\u{1b}[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_renames() {
+        let mut temps = vec![
+            sensor("nvme", "nvme0n1 (Samsung SSD 980)", 50),
+            sensor("nvme", "sda (WDC WD40EFRX)", 50),
+            sensor("coretemp", "Core 0", 50),
+        ];
+        apply_renames(
+            &mut temps,
+            &[
+                config::TempRename {
+                    pattern: regex::Regex::new(r"^nvme0n1 \(Samsung SSD 980\)$").unwrap(),
+                    name: "System SSD".to_owned(),
+                },
+                config::TempRename {
+                    pattern: regex::Regex::new(r"^(sd[a-z]+) \(WDC .*\)$").unwrap(),
+                    name: "Archive disk $1".to_owned(),
+                },
+            ],
+        );
+        assert_eq!(
+            temps.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(),
+            vec!["System SSD", "Archive disk sda", "Core 0"]
+        );
+    }
 }
@@ -0,0 +1,88 @@
+//! User template rendering, replacing the fixed section layout
+
+use std::collections::BTreeMap;
+
+use ansi_term::Style;
+
+use crate::{
+    fmt::{display, format_size},
+    mem::{write_bar, BarPart},
+    module::SectionsData,
+    style,
+};
+
+/// Values available to templates
+#[derive(serde::Serialize)]
+struct TemplateContext<'a> {
+    /// Raw section data, as in structured output
+    #[serde(flatten)]
+    data: &'a SectionsData,
+    /// Section text, as in the fixed layout
+    sections: BTreeMap<&'static str, String>,
+}
+
+/// Template function to draw a usage bar of a given length (including borders)
+fn bar(used: u64, total: u64, length: usize) -> String {
+    let used_prct = if total > 0 {
+        (used as f32 * 100.0 / total as f32).min(100.0)
+    } else {
+        0.0
+    };
+    let theme = style::theme();
+    let parts = [
+        BarPart {
+            label: Vec::new(),
+            prct: used_prct,
+            text_style: theme.bar,
+            fill_style: theme.bar,
+            bar_char: display().bar.fill,
+        },
+        BarPart {
+            label: Vec::new(),
+            prct: 100.0 - used_prct,
+            text_style: Style::new(),
+            fill_style: Style::new(),
+            bar_char: display().bar.empty,
+        },
+    ];
+    let mut s = String::new();
+    // Writing to a String can not fail
+    let _ = write_bar(&parts, length.max(3), &mut s);
+    s
+}
+
+/// Render template with sections data and their default text
+pub(crate) fn render(
+    template: &str,
+    data: &SectionsData,
+    sections: BTreeMap<&'static str, String>,
+) -> anyhow::Result<String> {
+    let mut env = minijinja::Environment::new();
+    // Values of sections not selected render empty
+    env.set_undefined_behavior(minijinja::UndefinedBehavior::Chainable);
+    env.add_filter("size", |bytes: u64| format_size(bytes, 0));
+    env.add_function("bar", bar);
+    let ctx = TemplateContext { data, sections };
+    Ok(env.render_str(template, ctx)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let data = SectionsData(Vec::new());
+        let sections = BTreeMap::from([("load", "Tasks: 12\n".to_owned())]);
+        assert_eq!(
+            render(
+                "{{ sections.load }}{{ 1536 | size }} [{{ bar(1, 4, 10) }}]{{ memory.total }}",
+                &data,
+                sections
+            )
+            .unwrap(),
+            "Tasks: 12\n1.5 KB [▕██      ▏]"
+        );
+        assert!(render("{{ unclosed", &data, BTreeMap::new()).is_err());
+    }
+}
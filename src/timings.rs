@@ -0,0 +1,109 @@
+//! Fetch and render durations of sections, to find the ones slowing down output
+
+use std::{
+    fmt::Write as _,
+    ptr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    fmt::{Align, Table},
+    module::{ModuleData, Section},
+};
+
+/// Durations of a single section
+struct SectionTimings {
+    /// Measured section
+    section: &'static Section,
+    /// Fetch duration
+    fetch: Duration,
+    /// Render duration, if data was fetched
+    render: Option<Duration>,
+}
+
+/// Durations of all sections of an output
+pub(crate) struct Timings {
+    /// Output start
+    start: Instant,
+    /// Sections measured so far, in completion order
+    sections: Mutex<Vec<SectionTimings>>,
+}
+
+impl Timings {
+    /// Start measuring an output
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            sections: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Record fetch duration of a section, and measure rendering of its data if it was fetched
+    pub(crate) fn record(
+        &self,
+        section: &'static Section,
+        fetch: Duration,
+        data: Option<&ModuleData>,
+    ) {
+        let render = data.map(|data| {
+            let render_start = Instant::now();
+            let _ = data.to_string();
+            render_start.elapsed()
+        });
+        self.sections.lock().unwrap().push(SectionTimings {
+            section,
+            fetch,
+            render,
+        });
+    }
+
+    /// Format table of section durations in display order, and total duration since start
+    pub(crate) fn report(&self, sections: &[&Section]) -> String {
+        let measured = self.sections.lock().unwrap();
+        let mut table = Table::with_header(
+            &[Align::Left, Align::Right, Align::Right],
+            &["Section", "Fetch", "Render"],
+        );
+        for section in sections {
+            let Some(timings) = measured.iter().find(|t| ptr::eq(t.section, *section)) else {
+                continue;
+            };
+            table.push(vec![
+                section.title().to_owned(),
+                format_ms(timings.fetch),
+                timings
+                    .render
+                    .map_or_else(|| "failed".to_owned(), format_ms),
+            ]);
+        }
+        table.push(vec![
+            "Total".to_owned(),
+            format_ms(self.start.elapsed()),
+            String::new(),
+        ]);
+        let widths = table.widths();
+        let mut report = String::new();
+        for row in table.padded_rows(&widths) {
+            let _ = writeln!(report, "{}", row.join("  ").trim_end());
+        }
+        report
+    }
+}
+
+/// Format duration in milliseconds
+fn format_ms(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_ms() {
+        assert_eq!(format_ms(Duration::from_micros(1540)), "1.5 ms");
+        assert_eq!(format_ms(Duration::from_millis(1500)), "1500.0 ms");
+        assert_eq!(format_ms(Duration::ZERO), "0.0 ms");
+    }
+}
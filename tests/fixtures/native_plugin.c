@@ -0,0 +1,64 @@
+/* Native plugin used by tests, reporting RAID arrays */
+
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+struct motd_plugin {
+    uint32_t abi_version;
+    void *(*fetch)(void);
+    char *(*render)(const void *data);
+    uint32_t (*severity)(const void *data);
+    char *(*summary)(const void *data);
+    char *(*json)(const void *data);
+    void (*free_string)(char *s);
+    void (*free_data)(void *data);
+};
+
+struct raid {
+    int arrays;
+    int degraded;
+};
+
+static void *fetch(void) {
+    struct raid *raid = malloc(sizeof(*raid));
+    if (raid != NULL) {
+        raid->arrays = 2;
+        raid->degraded = 1;
+    }
+    return raid;
+}
+
+static char *render(const void *data) {
+    (void)data;
+    return strdup("md0: ok\nmd1: degraded");
+}
+
+static uint32_t severity(const void *data) {
+    return ((const struct raid *)data)->degraded > 0 ? 1 : 0;
+}
+
+static char *summary(const void *data) {
+    (void)data;
+    return strdup("1 degraded array");
+}
+
+static char *json(const void *data) {
+    (void)data;
+    return strdup("{\"arrays\":2,\"degraded\":1}");
+}
+
+static const struct motd_plugin plugin = {
+    .abi_version = 2,
+    .fetch = fetch,
+    .render = render,
+    .severity = severity,
+    .summary = summary,
+    .json = json,
+    .free_string = (void (*)(char *))free,
+    .free_data = free,
+};
+
+const struct motd_plugin *motd_plugin_register(void) {
+    return &plugin;
+}
@@ -0,0 +1,68 @@
+//! Output of fixture trees captured from other machines, read with `--sysroot`
+
+// Dependencies of the binary, not used here
+#![expect(unused_crate_dependencies)]
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path, process, process::Command};
+
+    /// Run motd on a fixture tree with default config, and get its JSON output
+    fn output_json(host: &str, sections: &[&str]) -> serde_json::Value {
+        let home = env::temp_dir().join(format!("motd-sysroot-test-{}-{host}", process::id()));
+        fs::create_dir_all(&home).unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_motd"))
+            .arg("--sysroot")
+            .arg(
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/fixtures/sysroot")
+                    .join(host),
+            )
+            .args(["--format", "json", "-s"])
+            .args(sections)
+            .env("XDG_CONFIG_HOME", &home)
+            .env("XDG_CACHE_HOME", &home)
+            .env("XDG_STATE_HOME", &home)
+            .env("XDG_DATA_HOME", &home)
+            .output()
+            .unwrap();
+        fs::remove_dir_all(&home).unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_server() {
+        let output = output_json("server", &["l", "f", "t", "n"]);
+
+        assert_eq!(output["load"]["load_avg_1m"], 0.52);
+        assert_eq!(output["load"]["task_count"], 345);
+
+        // Device mounts are listed, but usage is not read from the tree
+        let mounts = output["filesystems"]["mounts"].as_array().unwrap();
+        assert_eq!(mounts.len(), 2);
+        assert_eq!(mounts[0]["mount_path"], "/");
+        assert_eq!(mounts[0]["problem"], "errors");
+        assert_eq!(mounts[0]["usage_unavailable"], true);
+        assert_eq!(mounts[1]["mount_path"], "/boot");
+        assert_eq!(mounts[1]["problem"], serde_json::Value::Null);
+        assert_eq!(mounts[1]["usage_unavailable"], true);
+
+        let temps = output["temperatures"]["temps"].as_array().unwrap();
+        assert_eq!(temps.len(), 1);
+        assert_eq!(temps[0]["chip"], "coretemp");
+        assert_eq!(temps[0]["name"], "Package id 0");
+        assert_eq!(temps[0]["temp"], 45);
+        assert_eq!(temps[0]["temp_critical"], 80);
+
+        let interfaces = output["network"]["interfaces"].as_object().unwrap();
+        assert_eq!(interfaces.keys().collect::<Vec<_>>(), vec!["eth0"]);
+        assert_eq!(interfaces["eth0"]["line_bps"], 1_000_000_000);
+        assert_eq!(interfaces["eth0"]["operstate"], "up");
+        assert_eq!(interfaces["eth0"]["rx_bps"], 0);
+    }
+}